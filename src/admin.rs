@@ -1,15 +1,21 @@
 //! Admin dashboard API foundation.
 
 use crate::auth::{current_session_user, UserIdentity};
+use crate::crypto::constant_time_eq;
 use crate::database::AdminMemberRecord;
+use crate::protocol::signing::MessageSigner;
+use crate::protocol::NoticeLevel;
 use crate::state::AppState;
-use axum::extract::State;
-use axum::http::{HeaderMap, StatusCode};
+use crate::state::Motd;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::http::{header::AUTHORIZATION, HeaderMap, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -116,6 +122,72 @@ struct AdminBillingEventListResponse {
     processed_at: u64,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateMotdRequest {
+    #[serde(default)]
+    text: String,
+    level: Option<NoticeLevel>,
+}
+
+#[derive(Debug, Serialize)]
+struct MotdResponse {
+    text: Option<String>,
+    level: Option<NoticeLevel>,
+}
+
+#[derive(Debug, Serialize)]
+struct EventsTicketResponse {
+    ticket: String,
+    expires_in_secs: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CloseRequest {
+    #[serde(default)]
+    reason: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClosePeerResponse {
+    peer_id: String,
+    closed: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CloseRoomResponse {
+    room_id: String,
+    removed_peers: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DrainRequest {
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DrainRoomResponse {
+    room_id: String,
+    members: usize,
+    timeout_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReserveParams {
+    /// 예약 유지 시간(초)
+    ttl: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReserveRoomResponse {
+    room_id: String,
+    ttl_secs: u64,
+}
+
 #[derive(Debug, Serialize)]
 struct AdminErrorBody {
     error: String,
@@ -263,6 +335,301 @@ pub async fn operations(State(state): State<Arc<AppState>>, headers: HeaderMap)
     }
 }
 
+/// 방 멤버 전체에 Ping을 보내 송신 채널 상태를 점검한다.
+pub async fn ping_room(
+    State(state): State<Arc<AppState>>,
+    Path(room_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    if !require_admin_token(&state, &headers) {
+        return admin_error(StatusCode::FORBIDDEN, "Admin token is required");
+    }
+
+    match crate::handlers::ping_room(&state, &room_id).await {
+        Some(report) => Json(report).into_response(),
+        None => admin_error(StatusCode::NOT_FOUND, "Room not found"),
+    }
+}
+
+/// 피어 연결을 강제로 끊는다. body의 reason은 선택 사항이다.
+pub async fn close_peer(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+    headers: HeaderMap,
+    request: Option<Json<CloseRequest>>,
+) -> Response {
+    if !require_admin_token(&state, &headers) {
+        return admin_error(StatusCode::FORBIDDEN, "Admin token is required");
+    }
+
+    let reason = close_reason(request);
+    if crate::handlers::close_peer(state, &peer_id, &reason).await {
+        Json(ClosePeerResponse {
+            peer_id,
+            closed: true,
+        })
+        .into_response()
+    } else {
+        admin_error(StatusCode::NOT_FOUND, "Peer not found")
+    }
+}
+
+/// 방을 닫고 멤버 전원을 내보낸다.
+pub async fn close_room(
+    State(state): State<Arc<AppState>>,
+    Path(room_id): Path<String>,
+    headers: HeaderMap,
+    request: Option<Json<CloseRequest>>,
+) -> Response {
+    if !require_admin_token(&state, &headers) {
+        return admin_error(StatusCode::FORBIDDEN, "Admin token is required");
+    }
+
+    let reason = close_reason(request);
+    match crate::handlers::close_room(&state, &room_id, &reason).await {
+        Some(removed_peers) => Json(CloseRoomResponse {
+            room_id,
+            removed_peers,
+        })
+        .into_response(),
+        None => admin_error(StatusCode::NOT_FOUND, "Room not found"),
+    }
+}
+
+/// 방 drain 대기 시간 기본값
+const DEFAULT_DRAIN_TIMEOUT_MS: u64 = 30_000;
+
+/// 방 하나를 점검용으로 비운다. 새 입장을 막고 멤버가 나가거나 timeout_ms가 지나면 삭제한다.
+pub async fn drain_room(
+    State(state): State<Arc<AppState>>,
+    Path(room_id): Path<String>,
+    headers: HeaderMap,
+    request: Option<Json<DrainRequest>>,
+) -> Response {
+    if !require_admin_token(&state, &headers) {
+        return admin_error(StatusCode::FORBIDDEN, "Admin token is required");
+    }
+
+    let timeout_ms = request
+        .and_then(|Json(request)| request.timeout_ms)
+        .unwrap_or(DEFAULT_DRAIN_TIMEOUT_MS);
+    let timeout = std::time::Duration::from_millis(timeout_ms);
+    match crate::handlers::drain_room(state, &room_id, timeout).await {
+        Some(members) => Json(DrainRoomResponse {
+            room_id,
+            members,
+            timeout_ms,
+        })
+        .into_response(),
+        None => admin_error(StatusCode::NOT_FOUND, "Room not found"),
+    }
+}
+
+/// 방 예약 유지 시간 기본값과 상한(초)
+const DEFAULT_RESERVE_TTL_SECS: u64 = 3_600;
+const MAX_RESERVE_TTL_SECS: u64 = 7 * 24 * 3_600;
+
+/// 아무도 없는 방을 미리 만들어 둔다. `ttl` 동안 정리되지 않고, 처음 들어온 참가자가 owner가 된다.
+pub async fn reserve_room(
+    State(state): State<Arc<AppState>>,
+    Path(room_id): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<ReserveParams>,
+) -> Response {
+    if !require_admin_token(&state, &headers) {
+        return admin_error(StatusCode::FORBIDDEN, "Admin token is required");
+    }
+
+    let room_id = room_id.trim().to_string();
+    let printable =
+        !state.config.room.strict_ids || crate::handlers::room::is_printable_room_id(&room_id);
+    if room_id.is_empty() || !printable {
+        return admin_error(StatusCode::BAD_REQUEST, "Invalid room id");
+    }
+    let ttl_secs = params
+        .ttl
+        .unwrap_or(DEFAULT_RESERVE_TTL_SECS)
+        .clamp(1, MAX_RESERVE_TTL_SECS);
+    let ttl = std::time::Duration::from_secs(ttl_secs);
+    if crate::handlers::reserve_room(&state, &room_id, ttl) {
+        Json(ReserveRoomResponse { room_id, ttl_secs }).into_response()
+    } else {
+        admin_error(StatusCode::CONFLICT, "Room already exists")
+    }
+}
+
+fn close_reason(request: Option<Json<CloseRequest>>) -> String {
+    let reason = request.map(|Json(request)| request.reason.trim().to_string());
+    match reason {
+        Some(reason) if !reason.is_empty() => reason,
+        _ => "Closed by administrator".to_string(),
+    }
+}
+
+/// `GET /admin/events`로 흘려보내는 시그널링 실시간 이벤트
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AdminEvent {
+    PeerConnected {
+        peer_id: String,
+    },
+    PeerDisconnected {
+        peer_id: String,
+    },
+    RoomCreated {
+        room_id: String,
+    },
+    RoomDeleted {
+        room_id: String,
+    },
+    PeerJoined {
+        room_id: String,
+        peer_id: String,
+    },
+    PeerLeft {
+        room_id: String,
+        peer_id: String,
+    },
+    /// 구독자가 따라오지 못해 건너뛴 이벤트 수
+    Lagged {
+        skipped: u64,
+    },
+}
+
+/// `/admin/events?ticket=`에 쓰는 티켓의 유효 시간
+const EVENTS_TICKET_TTL_SECS: u64 = 30;
+
+#[derive(Debug, Deserialize)]
+pub struct EventsParams {
+    ticket: Option<String>,
+}
+
+/// `/admin/events` 전용 단기 티켓을 발급한다. 브라우저는 WebSocket에 헤더를 붙일 수 없으므로
+/// 헤더로 이 티켓을 받은 뒤 `?ticket=`으로 연결한다. URL이 로그에 남아도 ADMIN_TOKEN은 드러나지 않고,
+/// 티켓은 이벤트 구독에만 쓰이며 EVENTS_TICKET_TTL_SECS 뒤 만료된다.
+pub async fn events_ticket(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    if !require_admin_token(&state, &headers) {
+        return admin_error(StatusCode::FORBIDDEN, "Admin token is required");
+    }
+    let Some(ticket) = sign_events_ticket(&state, unix_now() + EVENTS_TICKET_TTL_SECS) else {
+        return admin_error(StatusCode::FORBIDDEN, "Admin token is required");
+    };
+
+    Json(EventsTicketResponse {
+        ticket,
+        expires_in_secs: EVENTS_TICKET_TTL_SECS,
+    })
+    .into_response()
+}
+
+/// 실시간 이벤트 WebSocket. `Authorization` 헤더나 `events_ticket`이 발급한 `?ticket=`으로 인증한다.
+pub async fn events(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<EventsParams>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let ticket = params.ticket.as_deref().unwrap_or_default();
+    if !require_admin_token(&state, &headers) && !events_ticket_valid(&state, ticket) {
+        return admin_error(StatusCode::FORBIDDEN, "Admin token is required");
+    }
+
+    // 업그레이드 전에 구독해 연결 직후의 이벤트도 놓치지 않는다.
+    let events = state.admin_events.subscribe();
+    ws.on_upgrade(move |socket| stream_events(socket, events))
+}
+
+async fn stream_events(mut socket: WebSocket, mut events: broadcast::Receiver<AdminEvent>) {
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => AdminEvent::Lagged { skipped },
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        let Ok(json) = serde_json::to_string(&event) else {
+            continue;
+        };
+        if socket.send(Message::Text(json)).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// 실행 중인 설정을 돌려준다. 비밀 값은 `--check-config`와 같은 방식으로 가린다.
+pub async fn effective_config(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    if !require_admin_token(&state, &headers) {
+        return admin_error(StatusCode::FORBIDDEN, "Admin token is required");
+    }
+
+    Json(state.config.as_ref()).into_response()
+}
+
+/// 접속 시 보내는 MOTD를 교체한다. 빈 text는 MOTD를 끈다.
+/// 이미 연결된 클라이언트에는 보내지 않는다.
+pub async fn update_motd(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<UpdateMotdRequest>,
+) -> Response {
+    if !require_admin_token(&state, &headers) {
+        return admin_error(StatusCode::FORBIDDEN, "Admin token is required");
+    }
+
+    let text = request.text.trim().to_string();
+    let motd = (!text.is_empty()).then(|| Motd {
+        text,
+        level: request.level.unwrap_or(NoticeLevel::Info),
+    });
+    *state.motd.write().await = motd.clone();
+    tracing::info!(enabled = motd.is_some(), "MOTD updated");
+
+    Json(MotdResponse {
+        text: motd.as_ref().map(|motd| motd.text.clone()),
+        level: motd.map(|motd| motd.level),
+    })
+    .into_response()
+}
+
+/// `ADMIN_TOKEN` 기반 시그널링 운영 엔드포인트 인증.
+/// 토큰이 설정되지 않았으면 모든 요청을 거부한다.
+fn require_admin_token(state: &AppState, headers: &HeaderMap) -> bool {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| admin_token_matches(state, token))
+}
+
+fn admin_token_matches(state: &AppState, token: &str) -> bool {
+    let expected = state.config.admin.token.as_str();
+    !expected.is_empty() && constant_time_eq(token.trim().as_bytes(), expected.as_bytes())
+}
+
+/// 만료 시각(unix 초)과 ADMIN_TOKEN 키의 HMAC으로 티켓을 만든다. 토큰이 없으면 None.
+fn sign_events_ticket(state: &AppState, expires_at: u64) -> Option<String> {
+    let signer = MessageSigner::new(&state.config.admin.token)?;
+    let sig = signer.sign(&format!("admin-events:{expires_at}"));
+    Some(format!("{expires_at}.{sig}"))
+}
+
+fn events_ticket_valid(state: &AppState, ticket: &str) -> bool {
+    let Some(expires_at) = ticket
+        .split_once('.')
+        .and_then(|(expires_at, _)| expires_at.parse::<u64>().ok())
+    else {
+        return false;
+    };
+    expires_at > unix_now()
+        && sign_events_ticket(state, expires_at)
+            .is_some_and(|expected| constant_time_eq(expected.as_bytes(), ticket.as_bytes()))
+}
+
 async fn require_admin(
     state: &AppState,
     headers: &HeaderMap,
@@ -327,3 +694,258 @@ fn unix_now() -> u64 {
         .unwrap_or_default()
         .as_secs()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::protocol::ServerMessage;
+
+    fn state_with_admin_token(token: &str) -> AppState {
+        let mut config = Config::from_env();
+        config.admin.token = token.to_string();
+        AppState::new_for_test_with_config(config)
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, format!("Bearer {token}").parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn admin_token_must_match_configured_token() {
+        let state = state_with_admin_token("secret-token");
+        assert!(require_admin_token(&state, &bearer("secret-token")));
+        assert!(!require_admin_token(&state, &bearer("wrong")));
+        assert!(!require_admin_token(&state, &HeaderMap::new()));
+    }
+
+    #[tokio::test]
+    async fn events_ticket_is_short_lived_and_not_the_admin_token() {
+        let state = Arc::new(state_with_admin_token("secret-token"));
+        let response = events_ticket(State(state.clone()), bearer("secret-token")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let issued: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let ticket = issued["ticket"].as_str().expect("ticket");
+
+        assert!(events_ticket_valid(&state, ticket));
+        assert!(!events_ticket_valid(&state, "secret-token"));
+        let expired = sign_events_ticket(&state, unix_now() - 1).unwrap();
+        assert!(!events_ticket_valid(&state, &expired));
+        // 만료 시각을 늘리면 서명이 맞지 않는다
+        let (_, sig) = ticket.split_once('.').unwrap();
+        assert!(!events_ticket_valid(&state, &format!("{}.{sig}", u64::MAX)));
+
+        let forbidden = events_ticket(State(state), HeaderMap::new()).await;
+        assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn update_motd_replaces_runtime_notice() {
+        let state = Arc::new(state_with_admin_token("secret-token"));
+
+        let response = update_motd(
+            State(state.clone()),
+            bearer("secret-token"),
+            Json(UpdateMotdRequest {
+                text: " Deploying v2 ".to_string(),
+                level: Some(NoticeLevel::Warn),
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let motd = state.motd.read().await.clone().expect("motd set");
+        assert_eq!(motd.text, "Deploying v2");
+        assert_eq!(motd.level, NoticeLevel::Warn);
+
+        update_motd(
+            State(state.clone()),
+            bearer("secret-token"),
+            Json(UpdateMotdRequest {
+                text: String::new(),
+                level: None,
+            }),
+        )
+        .await;
+        assert!(state.motd.read().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn closing_peer_removes_session_and_membership() {
+        let state = Arc::new(state_with_admin_token("secret-token"));
+        let mut alice = state.insert_test_peer("alice");
+        let mut bob = state.insert_test_peer("bob");
+        for peer in ["alice", "bob"] {
+            crate::handlers::handle_join_room(
+                state.clone(),
+                peer,
+                "room",
+                crate::protocol::PeerRole::Participant,
+                None,
+            )
+            .await;
+        }
+        crate::state::drain_messages(&mut alice);
+        crate::state::drain_messages(&mut bob);
+
+        let response = close_peer(
+            State(state.clone()),
+            Path("alice".to_string()),
+            bearer("secret-token"),
+            Some(Json(CloseRequest {
+                reason: "abuse".to_string(),
+            })),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        assert!(!state.peers.contains_key("alice"));
+        let room = state.rooms.get("room").expect("room remains for bob");
+        assert!(!room.users.read().await.contains("alice"));
+        drop(room);
+        assert!(matches!(
+            crate::state::drain_messages(&mut alice).as_slice(),
+            [ServerMessage::Error { code, message, .. }]
+                if code == "closed_by_admin" && message == "abuse"
+        ));
+        assert!(crate::state::drain_messages(&mut bob).iter().any(
+            |msg| matches!(msg, ServerMessage::UserLeft { socket_id } if socket_id == "alice")
+        ));
+
+        let missing = close_peer(
+            State(state.clone()),
+            Path("alice".to_string()),
+            bearer("secret-token"),
+            None,
+        )
+        .await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn closing_room_notifies_and_releases_members() {
+        let state = Arc::new(state_with_admin_token("secret-token"));
+        let mut alice = state.insert_test_peer("alice");
+        crate::handlers::handle_join_room(
+            state.clone(),
+            "alice",
+            "room",
+            crate::protocol::PeerRole::Participant,
+            None,
+        )
+        .await;
+        crate::state::drain_messages(&mut alice);
+
+        let forbidden = close_room(
+            State(state.clone()),
+            Path("room".to_string()),
+            bearer("wrong"),
+            None,
+        )
+        .await;
+        assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+        let response = close_room(
+            State(state.clone()),
+            Path("room".to_string()),
+            bearer("secret-token"),
+            None,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        assert!(state.rooms.get("room").is_none());
+        let session = state.peers.get("alice").expect("connection kept");
+        assert!(session.room_id.read().await.is_none());
+        assert!(matches!(
+            crate::state::drain_messages(&mut alice).as_slice(),
+            [ServerMessage::RoomClosed { room_id, reason }]
+                if room_id == "room" && reason == "Closed by administrator"
+        ));
+    }
+
+    #[tokio::test]
+    async fn draining_room_blocks_joins_and_evacuates_members() {
+        use crate::protocol::PeerRole;
+
+        let state = Arc::new(state_with_admin_token("secret-token"));
+        let mut alice = state.insert_test_peer("alice");
+        let mut bob = state.insert_test_peer("bob");
+        let participant = PeerRole::Participant;
+        crate::handlers::handle_join_room(state.clone(), "alice", "room", participant, None).await;
+        crate::state::drain_messages(&mut alice);
+
+        let response = drain_room(
+            State(state.clone()),
+            Path("room".to_string()),
+            bearer("secret-token"),
+            Some(Json(DrainRequest {
+                timeout_ms: Some(200),
+            })),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(matches!(
+            crate::state::drain_messages(&mut alice).as_slice(),
+            [ServerMessage::RoomClosing { room_id, in_ms: 200 }] if room_id == "room"
+        ));
+
+        crate::handlers::handle_join_room(state.clone(), "bob", "room", participant, None).await;
+        assert!(matches!(
+            crate::state::drain_messages(&mut bob).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "room_draining"
+        ));
+
+        tokio::time::sleep(std::time::Duration::from_millis(400)).await;
+        assert!(state.rooms.get("room").is_none());
+        assert!(state
+            .peers
+            .get("alice")
+            .unwrap()
+            .room_id
+            .read()
+            .await
+            .is_none());
+        assert!(matches!(
+            crate::state::drain_messages(&mut alice).as_slice(),
+            [ServerMessage::RoomClosed { reason, .. }] if reason == "drained"
+        ));
+    }
+
+    #[tokio::test]
+    async fn effective_config_redacts_secrets() {
+        let mut config = Config::from_env();
+        config.admin.token = "secret-token".to_string();
+        config.turn.secret = "turn-shared-secret".to_string();
+        config.auth.session_secret = "session-signing-key".to_string();
+        config.ws_path = "/signal".to_string();
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+
+        let forbidden = effective_config(State(state.clone()), HeaderMap::new()).await;
+        assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+        let response = effective_config(State(state), bearer("secret-token")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["turn"]["secret"], "<redacted>");
+        assert_eq!(json["auth"]["session_secret"], "<redacted>");
+        assert_eq!(json["admin"]["token"], "<redacted>");
+        assert_eq!(json["ws_path"], "/signal");
+        let text = String::from_utf8_lossy(&body);
+        assert!(!text.contains("turn-shared-secret"));
+        assert!(!text.contains("session-signing-key"));
+    }
+
+    #[test]
+    fn admin_token_endpoints_are_disabled_without_configured_token() {
+        let state = state_with_admin_token("");
+        assert!(!require_admin_token(&state, &bearer("")));
+    }
+}
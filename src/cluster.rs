@@ -0,0 +1,492 @@
+//! 다중 노드 중계 (CLUSTER_BACKEND)
+//!
+//! 방 브로드캐스트와 target 중계는 먼저 로컬 피어에게 전달한 뒤 백엔드로 발행한다.
+//! 다른 노드는 구독한 envelope를 자기 노드의 피어에게만 전달하며 다시 발행하지 않는다.
+//! 기본값인 `memory` 백엔드는 단일 프로세스 동작을 그대로 유지한다.
+
+#[cfg(feature = "redis")]
+mod redis;
+
+use crate::config::Config;
+use crate::protocol::{PeerRole, ServerMessage};
+use crate::state::AppState;
+use anyhow::{bail, Result};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// 노드 간에 전달되는 중계 단위
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterEnvelope {
+    /// 발행한 노드 id (자기 메시지 무시용)
+    pub origin: String,
+    pub room_id: String,
+    /// 설정되면 해당 peer_id/client_token에게만 전달
+    pub target: Option<String>,
+    /// 방 브로드캐스트에서 제외할 피어
+    pub except: Option<String>,
+    /// 설정되면 이 역할의 피어에게만 전달 (RelayToRole)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<PeerRole>,
+    /// 설정되면 이 기능을 알린 피어에게만 전달 (RelayToCapable)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capability: Option<String>,
+    pub message: ServerMessage,
+}
+
+/// 방 상태/중계 공유 백엔드
+pub trait ClusterBackend: Send + Sync {
+    fn node_id(&self) -> &str;
+
+    /// 다른 노드로 envelope를 발행한다.
+    fn publish(&self, envelope: ClusterEnvelope) -> BoxFuture<'_, Result<()>>;
+
+    /// 다른 노드가 발행한 envelope 수신 채널. 단일 노드 백엔드는 None.
+    fn subscribe(&self) -> BoxFuture<'_, Result<Option<UnboundedReceiver<ClusterEnvelope>>>>;
+
+    /// 방 참여/퇴장을 공유 저장소에 반영한다.
+    fn set_presence(
+        &self,
+        room_id: &str,
+        peer_id: &str,
+        present: bool,
+    ) -> BoxFuture<'_, Result<()>>;
+}
+
+/// 단일 프로세스 백엔드. 발행/구독/presence 모두 하지 않는다.
+pub struct LocalBackend {
+    node_id: String,
+}
+
+impl LocalBackend {
+    pub fn new() -> Self {
+        Self {
+            node_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+}
+
+impl ClusterBackend for LocalBackend {
+    fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    fn publish(&self, _envelope: ClusterEnvelope) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn subscribe(&self) -> BoxFuture<'_, Result<Option<UnboundedReceiver<ClusterEnvelope>>>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    fn set_presence(
+        &self,
+        _room_id: &str,
+        _peer_id: &str,
+        _present: bool,
+    ) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// 설정에 맞는 백엔드 생성
+pub async fn backend_from_config(config: &Config) -> Result<Arc<dyn ClusterBackend>> {
+    match config.cluster.backend.as_str() {
+        "" | "memory" => Ok(Arc::new(LocalBackend::new())),
+        #[cfg(feature = "redis")]
+        "redis" => Ok(Arc::new(
+            redis::RedisBackend::connect(
+                &config.cluster.redis_url,
+                &config.cluster.channel,
+                std::time::Duration::from_secs(config.cluster.presence_ttl_secs),
+            )
+            .await?,
+        )),
+        #[cfg(not(feature = "redis"))]
+        "redis" => bail!("CLUSTER_BACKEND=redis requires building with --features redis"),
+        other => bail!("unknown CLUSTER_BACKEND: {other}"),
+    }
+}
+
+/// 방 브로드캐스트를 다른 노드로 발행한다.
+pub async fn publish_room(
+    state: &AppState,
+    room_id: &str,
+    except: Option<&str>,
+    message: ServerMessage,
+) {
+    publish(state, room_id, None, except, None, None, message).await;
+}
+
+/// 방에서 `role` 역할인 피어에게만 보내는 브로드캐스트를 다른 노드로 발행한다.
+pub async fn publish_role(
+    state: &AppState,
+    room_id: &str,
+    except: Option<&str>,
+    role: PeerRole,
+    message: ServerMessage,
+) {
+    publish(state, room_id, None, except, Some(role), None, message).await;
+}
+
+/// 방에서 `capability`를 알린 피어에게만 보내는 브로드캐스트를 다른 노드로 발행한다.
+pub async fn publish_capable(
+    state: &AppState,
+    room_id: &str,
+    except: Option<&str>,
+    capability: &str,
+    message: ServerMessage,
+) {
+    publish(
+        state,
+        room_id,
+        None,
+        except,
+        None,
+        Some(capability),
+        message,
+    )
+    .await;
+}
+
+/// 로컬에 없는 target으로의 중계를 다른 노드로 발행한다.
+pub async fn publish_target(state: &AppState, room_id: &str, target: &str, message: ServerMessage) {
+    publish(state, room_id, Some(target), None, None, None, message).await;
+}
+
+async fn publish(
+    state: &AppState,
+    room_id: &str,
+    target: Option<&str>,
+    except: Option<&str>,
+    role: Option<PeerRole>,
+    capability: Option<&str>,
+    message: ServerMessage,
+) {
+    let envelope = ClusterEnvelope {
+        origin: state.cluster.node_id().to_string(),
+        room_id: room_id.to_string(),
+        target: target.map(str::to_string),
+        except: except.map(str::to_string),
+        role,
+        capability: capability.map(str::to_string),
+        message,
+    };
+    if let Err(err) = state.cluster.publish(envelope).await {
+        tracing::warn!(room_id = %room_id, error = %err, "Failed to publish cluster relay");
+    }
+}
+
+/// 이 노드의 방 멤버 presence를 다시 기록해 유효 시간(CLUSTER_PRESENCE_TTL_SECS)을 늘리고
+/// 기록한 수를 돌려준다. 노드가 멈추면 갱신도 멈추므로 그 노드의 피어는 유효 시간 뒤 사라진다.
+pub async fn refresh_presence(state: &AppState) -> usize {
+    let mut memberships = Vec::new();
+    for room in state.rooms.iter() {
+        for peer_id in room.users.read().await.iter() {
+            memberships.push((room.key().clone(), peer_id.clone()));
+        }
+    }
+    for (room_id, peer_id) in &memberships {
+        update_presence(state, room_id, peer_id, true).await;
+    }
+    memberships.len()
+}
+
+/// 방 참여/퇴장 presence 반영. 실패해도 로컬 동작은 계속한다.
+pub async fn update_presence(state: &AppState, room_id: &str, peer_id: &str, present: bool) {
+    if let Err(err) = state.cluster.set_presence(room_id, peer_id, present).await {
+        tracing::warn!(room_id = %room_id, peer_id = %peer_id, error = %err, "Failed to update presence");
+    }
+}
+
+/// 다른 노드에서 온 envelope를 이 노드의 피어에게 전달한다. 다시 발행하지 않는다.
+pub async fn deliver_remote(state: &AppState, envelope: ClusterEnvelope) -> usize {
+    if envelope.origin == state.cluster.node_id() {
+        return 0;
+    }
+
+    if let Some(target) = &envelope.target {
+        // target이 이 노드에서 envelope의 방에 들어 있을 때만 전달한다
+        let Some(peer_id) = state.resolve_peer_id(target) else {
+            return 0;
+        };
+        let in_room = match state.rooms.get(&envelope.room_id) {
+            Some(room) => room.users.read().await.contains(&peer_id),
+            None => false,
+        };
+        let delivered = in_room
+            && state
+                .peers
+                .get(&peer_id)
+                .is_some_and(|session| session.sender.send(envelope.message.clone()).is_ok());
+        if delivered {
+            state.touch_room(&envelope.room_id);
+        }
+        return usize::from(delivered);
+    }
+
+    let Some(room) = state.rooms.get(&envelope.room_id) else {
+        return 0;
+    };
+    let users = room.users.read().await;
+    let mut delivered = 0;
+    for peer_id in users.iter() {
+        if envelope.except.as_deref() == Some(peer_id.as_str()) {
+            continue;
+        }
+        if let Some(session) = state.peers.get(peer_id) {
+            if let Some(role) = envelope.role {
+                if *session.role.read().await != role {
+                    continue;
+                }
+            }
+            if let Some(capability) = &envelope.capability {
+                if !session.capabilities.advertises(capability) {
+                    continue;
+                }
+            }
+            if session.sender.send(envelope.message.clone()).is_ok() {
+                delivered += 1;
+            }
+        }
+    }
+    delivered
+}
+
+/// 구독 채널을 돌며 원격 envelope를 전달한다. 단일 노드 백엔드면 바로 반환한다.
+pub async fn run_subscriber(state: Arc<AppState>) -> Result<()> {
+    let Some(mut receiver) = state.cluster.subscribe().await? else {
+        return Ok(());
+    };
+    tracing::info!(node_id = %state.cluster.node_id(), "Cluster relay subscriber started");
+    while let Some(envelope) = receiver.recv().await {
+        deliver_remote(&state, envelope).await;
+    }
+    tracing::warn!("Cluster relay subscription closed");
+    Ok(())
+}
+
+/// 테스트용 인프로세스 버스. 같은 버스를 공유하는 백엔드끼리 서로 다른 노드처럼 동작한다.
+#[cfg(test)]
+pub mod test_bus {
+    use super::*;
+    use dashmap::DashSet;
+    use tokio::sync::broadcast;
+
+    pub struct BusBackend {
+        node_id: String,
+        bus: broadcast::Sender<ClusterEnvelope>,
+        pub presence: Arc<DashSet<(String, String)>>,
+    }
+
+    impl BusBackend {
+        /// 같은 버스와 presence 저장소를 공유하는 노드 두 개를 만든다.
+        pub fn pair() -> (Arc<Self>, Arc<Self>) {
+            let (bus, _) = broadcast::channel(64);
+            let presence = Arc::new(DashSet::new());
+            let node = |name: &str| {
+                Arc::new(Self {
+                    node_id: name.to_string(),
+                    bus: bus.clone(),
+                    presence: presence.clone(),
+                })
+            };
+            (node("node-a"), node("node-b"))
+        }
+    }
+
+    impl ClusterBackend for BusBackend {
+        fn node_id(&self) -> &str {
+            &self.node_id
+        }
+
+        fn publish(&self, envelope: ClusterEnvelope) -> BoxFuture<'_, Result<()>> {
+            let _ = self.bus.send(envelope);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn subscribe(&self) -> BoxFuture<'_, Result<Option<UnboundedReceiver<ClusterEnvelope>>>> {
+            let mut bus = self.bus.subscribe();
+            Box::pin(async move {
+                let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+                tokio::spawn(async move {
+                    while let Ok(envelope) = bus.recv().await {
+                        if sender.send(envelope).is_err() {
+                            break;
+                        }
+                    }
+                });
+                Ok(Some(receiver))
+            })
+        }
+
+        fn set_presence(
+            &self,
+            room_id: &str,
+            peer_id: &str,
+            present: bool,
+        ) -> BoxFuture<'_, Result<()>> {
+            let key = (room_id.to_string(), peer_id.to_string());
+            if present {
+                self.presence.insert(key);
+            } else {
+                self.presence.remove(&key);
+            }
+            Box::pin(async { Ok(()) })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_bus::BusBackend;
+    use super::*;
+    use crate::protocol::PeerRole;
+    use crate::state::drain_messages;
+    use std::time::Duration;
+
+    async fn two_nodes() -> (Arc<AppState>, Arc<AppState>, Arc<BusBackend>) {
+        let (a, b) = BusBackend::pair();
+        let node_a = Arc::new(AppState::new_for_test_with_cluster(a.clone()));
+        let node_b = Arc::new(AppState::new_for_test_with_cluster(b));
+        tokio::spawn(run_subscriber(node_a.clone()));
+        tokio::spawn(run_subscriber(node_b.clone()));
+        settle().await;
+        (node_a, node_b, a)
+    }
+
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    #[tokio::test]
+    async fn room_broadcast_reaches_peers_on_other_node() {
+        let (node_a, node_b, bus) = two_nodes().await;
+        let _alice = node_a.insert_test_peer("alice");
+        let mut bob = node_b.insert_test_peer("bob");
+        crate::handlers::handle_join_room(
+            node_a.clone(),
+            "alice",
+            "room",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        crate::handlers::handle_join_room(
+            node_b.clone(),
+            "bob",
+            "room",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        settle().await;
+        drain_messages(&mut bob);
+
+        crate::handlers::handle_offer(node_a.clone(), "alice", "room", "v=0", None, None).await;
+        settle().await;
+
+        assert!(matches!(
+            drain_messages(&mut bob).as_slice(),
+            [ServerMessage::Offer { from, .. }] if from == "alice"
+        ));
+        assert!(bus
+            .presence
+            .contains(&("room".to_string(), "alice".to_string())));
+        assert!(bus
+            .presence
+            .contains(&("room".to_string(), "bob".to_string())));
+    }
+
+    #[tokio::test]
+    async fn refresh_rewrites_presence_of_local_members() {
+        let (node_a, _node_b, bus) = two_nodes().await;
+        let _alice = node_a.insert_test_peer("alice");
+        join(&node_a, "alice", "room").await;
+        bus.presence.clear();
+
+        assert_eq!(refresh_presence(&node_a).await, 1);
+        assert!(bus
+            .presence
+            .contains(&("room".to_string(), "alice".to_string())));
+    }
+
+    async fn join(state: &Arc<AppState>, peer_id: &str, room_id: &str) {
+        let participant = PeerRole::Participant;
+        crate::handlers::handle_join_room(state.clone(), peer_id, room_id, participant, None).await;
+    }
+
+    #[tokio::test]
+    async fn targeted_relay_crosses_nodes_without_echo() {
+        let (node_a, node_b, _) = two_nodes().await;
+        let mut alice = node_a.insert_test_peer("alice");
+        let mut bob = node_b.insert_test_peer("bob");
+        join(&node_a, "alice", "room").await;
+        join(&node_b, "bob", "room").await;
+        settle().await;
+        drain_messages(&mut alice);
+        drain_messages(&mut bob);
+
+        crate::handlers::handle_answer(node_a.clone(), "alice", "room", "v=0", Some("bob"), None)
+            .await;
+        settle().await;
+
+        assert!(matches!(
+            drain_messages(&mut bob).as_slice(),
+            [ServerMessage::Answer { from, .. }] if from == "alice"
+        ));
+        assert!(drain_messages(&mut alice).is_empty());
+    }
+
+    #[tokio::test]
+    async fn targeted_relay_requires_room_membership_on_both_nodes() {
+        let (node_a, node_b, _) = two_nodes().await;
+        let mut outsider = node_a.insert_test_peer("outsider");
+        let mut bob = node_b.insert_test_peer("bob");
+        join(&node_b, "bob", "room").await;
+        settle().await;
+        drain_messages(&mut bob);
+
+        // 방에 없는 보낸 피어는 발행 전에 거부된다
+        crate::handlers::handle_answer(
+            node_a.clone(),
+            "outsider",
+            "room",
+            "v=0",
+            Some("bob"),
+            None,
+        )
+        .await;
+        settle().await;
+        assert!(drain_messages(&mut bob).is_empty());
+        assert!(matches!(
+            drain_messages(&mut outsider).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "not_in_room"
+        ));
+
+        // 받는 노드는 target이 envelope의 방에 없으면 전달하지 않는다
+        let envelope = ClusterEnvelope {
+            origin: "other-node".to_string(),
+            room_id: "elsewhere".to_string(),
+            target: Some("bob".to_string()),
+            except: None,
+            role: None,
+            capability: None,
+            message: ServerMessage::HeartbeatAck,
+        };
+        assert_eq!(deliver_remote(&node_b, envelope).await, 0);
+        assert!(drain_messages(&mut bob).is_empty());
+    }
+
+    #[tokio::test]
+    async fn local_backend_keeps_single_node_behavior() {
+        let state = AppState::new_for_test();
+        assert!(state
+            .cluster
+            .subscribe()
+            .await
+            .expect("subscribe")
+            .is_none());
+    }
+}
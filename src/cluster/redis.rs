@@ -0,0 +1,164 @@
+//! Redis 백엔드 (`--features redis`, CLUSTER_BACKEND=redis)
+//!
+//! 중계는 `<channel>` pub/sub 채널로, 방 presence는 `<channel>:room:<room_id>` sorted set으로 공유한다.
+//! presence의 score는 만료 시각(unix ms)이라 갱신이 끊긴 노드의 피어는 유효 시간 뒤 빠진다.
+
+use super::{ClusterBackend, ClusterEnvelope};
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+pub struct RedisBackend {
+    node_id: String,
+    channel: String,
+    client: redis::Client,
+    connection: ConnectionManager,
+    presence_ttl: Duration,
+}
+
+impl RedisBackend {
+    pub async fn connect(url: &str, channel: &str, presence_ttl: Duration) -> Result<Self> {
+        let client = redis::Client::open(url).context("invalid REDIS_URL")?;
+        let connection = ConnectionManager::new(client.clone())
+            .await
+            .context("failed to connect to Redis")?;
+        Ok(Self {
+            node_id: uuid::Uuid::new_v4().to_string(),
+            channel: channel.to_string(),
+            client,
+            connection,
+            presence_ttl,
+        })
+    }
+
+    fn room_key(&self, room_id: &str) -> String {
+        format!("{}:room:{}", self.channel, room_id)
+    }
+
+    /// 만료되지 않은 방 presence
+    #[cfg(test)]
+    async fn live_members(&self, room_id: &str) -> Result<Vec<String>> {
+        let mut connection = self.connection.clone();
+        let members = connection
+            .zrangebyscore(self.room_key(room_id), unix_ms(), "+inf")
+            .await?;
+        Ok(members)
+    }
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+impl ClusterBackend for RedisBackend {
+    fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    fn publish(&self, envelope: ClusterEnvelope) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let payload = serde_json::to_string(&envelope)?;
+            let mut connection = self.connection.clone();
+            let _: () = connection.publish(&self.channel, payload).await?;
+            Ok(())
+        })
+    }
+
+    fn subscribe(&self) -> BoxFuture<'_, Result<Option<UnboundedReceiver<ClusterEnvelope>>>> {
+        Box::pin(async move {
+            let mut pubsub = self
+                .client
+                .get_async_pubsub()
+                .await
+                .context("failed to open Redis pub/sub")?;
+            pubsub.subscribe(&self.channel).await?;
+
+            let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+            tokio::spawn(async move {
+                let mut messages = pubsub.into_on_message();
+                while let Some(message) = messages.next().await {
+                    let Ok(payload) = message.get_payload::<String>() else {
+                        continue;
+                    };
+                    match serde_json::from_str::<ClusterEnvelope>(&payload) {
+                        Ok(envelope) => {
+                            if sender.send(envelope).is_err() {
+                                break;
+                            }
+                        }
+                        Err(err) => {
+                            tracing::warn!(error = %err, "Ignored malformed cluster envelope")
+                        }
+                    }
+                }
+            });
+            Ok(Some(receiver))
+        })
+    }
+
+    fn set_presence(
+        &self,
+        room_id: &str,
+        peer_id: &str,
+        present: bool,
+    ) -> BoxFuture<'_, Result<()>> {
+        let key = self.room_key(room_id);
+        let peer_id = peer_id.to_string();
+        Box::pin(async move {
+            let mut connection = self.connection.clone();
+            if present {
+                // 다시 기록하면 만료 시각이 늘어난다. 만료된 항목은 이때 함께 지운다
+                let now = unix_ms();
+                let ttl_ms = self.presence_ttl.as_millis() as u64;
+                let _: () = redis::pipe()
+                    .zrembyscore(&key, "-inf", now)
+                    .ignore()
+                    .zadd(&key, peer_id, now + ttl_ms)
+                    .ignore()
+                    .pexpire(&key, ttl_ms as i64)
+                    .ignore()
+                    .query_async(&mut connection)
+                    .await?;
+            } else {
+                let _: () = connection.zrem(key, peer_id).await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// REDIS_URL(기본 redis://127.0.0.1:6379)의 Redis가 필요하다:
+    /// `cargo test --features redis -- --ignored`
+    #[tokio::test]
+    #[ignore = "needs a Redis server at REDIS_URL"]
+    async fn presence_expires_unless_refreshed() {
+        let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".into());
+        let channel = format!("ponswarp:test:{}", uuid::Uuid::new_v4());
+        let ttl = Duration::from_millis(300);
+        let backend = RedisBackend::connect(&url, &channel, ttl).await.unwrap();
+
+        backend.set_presence("room", "alive", true).await.unwrap();
+        backend.set_presence("room", "stale", true).await.unwrap();
+        assert_eq!(backend.live_members("room").await.unwrap().len(), 2);
+
+        tokio::time::sleep(ttl / 2).await;
+        backend.set_presence("room", "alive", true).await.unwrap();
+        tokio::time::sleep(ttl / 2 + Duration::from_millis(50)).await;
+
+        assert_eq!(backend.live_members("room").await.unwrap(), ["alive"]);
+
+        backend.set_presence("room", "alive", false).await.unwrap();
+        assert!(backend.live_members("room").await.unwrap().is_empty());
+    }
+}
@@ -1,38 +1,87 @@
 //! 환경 변수 기반 설정 관리
 
+use crate::protocol::NoticeLevel;
+use serde::{Serialize, Serializer};
 use std::env;
 use std::path::Path;
 
+/// Offer/Answer/IceCandidate 중계 로그 target (LOG_SIGNALING)
+pub const SIGNALING_LOG_TARGET: &str = "ponswarp::signaling";
+/// Manifest/TransferReady/TransferComplete 중계 로그 target (LOG_TRANSFER)
+pub const TRANSFER_LOG_TARGET: &str = "ponswarp::transfer";
+
 /// 서버 설정
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Config {
     pub port: u16,
     pub host: String,
+    /// WebSocket 엔드포인트 경로 (WS_PATH)
+    pub ws_path: String,
+    /// `/health`, `/ready` 등 운영 엔드포인트 앞에 붙는 경로 (BASE_PATH)
+    pub base_path: String,
     #[allow(dead_code)]
     pub cors_origins: Vec<String>,
+    /// WebSocket 업그레이드의 Origin 헤더를 cors_origins와 대조해 맞지 않으면 403으로 거부한다 (WS_ENFORCE_ORIGIN)
+    pub ws_enforce_origin: bool,
     pub database: DatabaseConfig,
     pub auth: AuthConfig,
     pub admin: AdminConfig,
     pub billing: BillingConfig,
     pub room: RoomConfig,
+    pub signaling: SignalingConfig,
+    pub limits: LimitsConfig,
+    pub cluster: ClusterConfig,
     pub turn: TurnConfig,
     pub cloud: CloudConfig,
     pub mesh: MeshConfig,
     pub log_level: String,
+    /// 시그널링 중계 로그 수준. 없으면 log_level을 따른다
+    pub log_signaling: Option<String>,
+    /// 파일 전송 중계 로그 수준. 없으면 log_level을 따른다
+    pub log_transfer: Option<String>,
+    /// 접속 직후 Notice로 보내는 안내 문구 (MOTD). 비어 있으면 보내지 않음
+    pub motd: String,
+    /// MOTD 수준 (info | warn)
+    pub motd_level: NoticeLevel,
+    /// Heartbeat에 HeartbeatAck로 응답할지 여부 (HEARTBEAT_ACK)
+    pub heartbeat_ack: bool,
+    /// 종료 시 방 메타데이터를 저장하고 기동 시 복원할 파일 경로. 비어 있으면 비활성화
+    pub persistence_path: String,
+    /// 복원한 방을 비어 있어도 남겨 두는 재접속 대기 시간 (PERSISTENCE_RECONNECT_WINDOW_MS)
+    pub persistence_reconnect_window_ms: u64,
+    /// 이 서버 인스턴스 식별자 (SERVER_INSTANCE_ID). 비어 있으면 기동 시 무작위로 정한다
+    pub instance_id: String,
+    /// 새 연결에 발급하는 peer_id 형식 (PEER_ID_FORMAT)
+    pub peer_id_format: PeerIdFormat,
+}
+
+/// peer_id 생성 방식
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PeerIdFormat {
+    #[default]
+    Uuid,
+    /// 8자리 base32 (충돌 시 재생성)
+    Short,
 }
 
 /// 관리자 접근 설정.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct AdminConfig {
     pub bootstrap_emails: Vec<String>,
+    /// 시그널링 운영 엔드포인트(`/admin/...`)용 Bearer 토큰. 비어 있으면 비활성화.
+    #[serde(serialize_with = "redact")]
+    pub token: String,
 }
 
 /// Google OAuth and browser session settings.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct AuthConfig {
     pub google_client_id: String,
+    #[serde(serialize_with = "redact")]
     pub google_client_secret: String,
     pub google_redirect_uri: String,
+    #[serde(serialize_with = "redact")]
     pub session_secret: String,
     pub session_cookie_name: String,
     pub session_ttl_seconds: u64,
@@ -41,26 +90,30 @@ pub struct AuthConfig {
 }
 
 /// 선택적 Postgres 설정
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DatabaseConfig {
+    #[serde(serialize_with = "redact_url")]
     pub url: String,
     pub max_connections: u32,
     pub run_migrations: bool,
 }
 
 /// 결제 provider 설정
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BillingConfig {
     pub default_provider: String,
+    #[serde(serialize_with = "redact")]
     pub lemonsqueezy_api_key: String,
     pub lemonsqueezy_api_base: String,
     pub lemonsqueezy_store_id: String,
+    #[serde(serialize_with = "redact")]
     pub lemonsqueezy_webhook_secret: String,
     pub lemonsqueezy_variant_drop_100gb_3d: String,
     pub lemonsqueezy_variant_drop_500gb_7d: String,
     pub lemonsqueezy_variant_drop_1tb_7d: String,
     pub lemonsqueezy_variant_pro_monthly: String,
     pub paypal_client_id: String,
+    #[serde(serialize_with = "redact")]
     pub paypal_client_secret: String,
     pub paypal_webhook_id: String,
     pub paypal_api_base: String,
@@ -70,16 +123,169 @@ pub struct BillingConfig {
 }
 
 /// 방 설정
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RoomConfig {
     pub max_size: usize,
+    /// 이 시간 동안 시그널링/Heartbeat가 없는 방을 정리한다
     pub timeout_ms: u64,
+    /// 시그널링이 이 시간 이상 없는 방의 멤버에게 KeepAlive를 보낸다 (0이면 비활성화)
+    pub keepalive_interval_ms: u64,
+    /// ROOM_TIMEOUT 정리 전에 RoomClosing을 보내고 기다리는 시간
+    pub closing_grace_ms: u64,
+    /// 방별로 보관할 최근 이벤트 수 (0이면 기록하지 않음)
+    pub history_size: usize,
+    /// 연결이 끊긴 피어를 UserLeft 없이 유지하는 시간 (0이면 즉시 퇴장)
+    pub disconnect_grace_ms: u64,
+    /// 마지막 사용자가 나간 빈 방을 삭제하지 않고 남겨 두는 시간 (0이면 즉시 삭제)
+    pub empty_room_linger_ms: u64,
+    /// 피어가 방을 나간 뒤 이 시간(ms) 안에 같은 방에 다시 입장하면 거절한다 (0이면 제한 없음)
+    pub transition_debounce_ms: u64,
+    /// 같은 IP에서 한 방에 동시에 참여할 수 있는 최대 인원 (0이면 제한 없음)
+    pub max_per_ip: usize,
+    /// 한 접속 IP가 만들어 동시에 유지할 수 있는 방 수 (0이면 제한 없음)
+    pub max_rooms_per_owner: usize,
+    /// 방 전체에서 초당 허용하는 브로드캐스트 중계 수 (0이면 제한 없음)
+    pub msg_rate_per_sec: u32,
+    pub msg_burst: u32,
+    /// 한도 초과로 버린 중계를 보낸 피어에게 room_rate_limited로 알릴지 여부
+    pub msg_rate_notify: bool,
+    /// 연결당 ListPublicRooms 초당 허용 횟수 (0이면 제한 없음)
+    pub list_rate_per_sec: u32,
+    pub list_burst: u32,
+    /// SetRoomMetadata로 저장할 수 있는 JSON 최대 크기 (bytes)
+    pub metadata_max_bytes: usize,
+    /// 분당 방 생성 수가 이 값을 넘으면 경고 로그 (전역/IP별, 0이면 감시 안 함)
+    pub create_warn_per_min: u32,
+    pub create_warn_per_ip_per_min: u32,
+    /// 제어 문자나 방향 재정의 같은 보이지 않는 문자가 든 방 id를 invalid_room_id로 거부
+    pub strict_ids: bool,
+    /// 이미 멤버인 방에 다시 JoinRoom을 보냈을 때의 처리 (DUPLICATE_JOIN_POLICY)
+    pub duplicate_join: DuplicateJoinPolicy,
+}
+
+/// 같은 방 재입장(JoinRoom 중복) 처리
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateJoinPolicy {
+    /// 처음 입장처럼 목록을 다시 보내고 다른 멤버에게도 PeerJoined를 보낸다
+    Notify,
+    /// 재입장한 피어에게만 현재 방 상태를 다시 보낸다
+    #[default]
+    Snapshot,
+    /// 아무것도 보내지 않는다
+    Ignore,
+}
+
+/// 다중 노드 중계 백엔드 설정
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterConfig {
+    /// memory (단일 노드) | redis
+    pub backend: String,
+    #[cfg_attr(not(feature = "redis"), allow(dead_code))]
+    #[serde(serialize_with = "redact_url")]
+    pub redis_url: String,
+    /// pub/sub 채널 및 presence 키 prefix
+    #[cfg_attr(not(feature = "redis"), allow(dead_code))]
+    pub channel: String,
+    /// presence 항목의 유효 시간. 노드가 주기적으로 갱신하고, 멈춘 노드의 항목은 이 시간 뒤 사라진다
+    pub presence_ttl_secs: u64,
+}
+
+/// 연결/메시지 수 제한
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitsConfig {
+    /// 동시 WebSocket 연결 수 상한 (0이면 제한 없음)
+    pub max_connections: usize,
+    /// 동시에 진행하는 WebSocket 업그레이드(연결 등록까지) 수. 넘으면 503으로 거부 (0이면 제한 없음)
+    pub max_concurrent_upgrades: usize,
+    /// 방에 들어가지 않은 피어 수 상한. 넘으면 새 연결을 roomless_peer_limit로 거부 (0이면 제한 없음)
+    pub max_roomless_peers: usize,
+    /// 방 없이 머물 수 있는 시간 (ms). `max_roomless_peers`가 켜져 있으면 넘긴 연결을 닫는다
+    pub roomless_grace_ms: u64,
+    /// listen 소켓 backlog (수락 대기 중인 연결 수)
+    pub listen_backlog: i32,
+    /// 모든 방의 멤버 수 합계 상한. 넘는 입장은 global_user_cap으로 거부 (0이면 제한 없음)
+    pub max_total_users: usize,
+    /// 서버 전체에서 동시에 처리하는 클라이언트 메시지 수 상한. 넘으면 server_overloaded로 버린다 (0이면 제한 없음)
+    pub max_concurrent_handlers: usize,
+    /// 연결당 초당 메시지 수 (0이면 제한 없음)
+    pub message_rate_per_sec: u32,
+    /// 순간적으로 허용하는 메시지 수
+    pub message_burst: u32,
+    /// WebSocket 한 프레임 전송 대기 한도. 넘기면 연결을 끊는다 (0이면 제한 없음)
+    pub send_timeout_ms: u64,
+    /// 송신 대기 메시지가 이만큼 쌓이면 낮은 우선순위 메시지(진행률, presence)를 버린다 (0이면 끔)
+    pub outbound_shed_backlog: usize,
+    /// 연결 최대 유지 시간. 넘기면 lifetime_exceeded를 보내고 끊는다 (0이면 제한 없음)
+    pub max_connection_lifetime_ms: u64,
+    /// continuation frame을 합친 WebSocket 메시지 최대 크기 (0이면 라이브러리 기본값 64 MiB)
+    pub max_message_bytes: usize,
+    /// 연결별 수신 큐 깊이. 0이면 큐 없이 수신 루프에서 바로 처리한다
+    pub inbound_queue_depth: usize,
+    /// 수신 큐가 가득 찼을 때 버릴 메시지 (INBOUND_QUEUE_POLICY)
+    pub inbound_drop_policy: InboundDropPolicy,
+    /// 정리 주기마다 트래픽이 빠져 비어 있는 상태 map 용량을 줄일지 여부
+    pub map_shrink: bool,
+    /// 수락한 연결에 TCP_NODELAY 적용 (작은 시그널링 frame을 모으지 않고 바로 보낸다)
+    pub tcp_nodelay: bool,
+    /// 수락한 연결의 SO_KEEPALIVE 유휴 시간 (초, 0이면 끔)
+    pub tcp_keepalive_secs: u64,
+    /// X-Forwarded-For를 믿을 리버스 프록시 주소 (TRUSTED_PROXIES). 비어 있으면 접속 주소만 쓴다
+    pub trusted_proxies: Vec<String>,
+}
+
+/// 수신 큐가 가득 찼을 때의 처리
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InboundDropPolicy {
+    /// 새로 들어온 메시지를 버린다
+    #[default]
+    DropNewest,
+    /// 가장 오래 기다린 메시지를 버리고 새 메시지를 넣는다
+    DropOldest,
+}
+
+/// 시그널링 중계 설정
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalingConfig {
+    /// Offer/Answer/IceCandidate에 target을 필수로 요구할지 여부
+    pub require_target: bool,
+    /// ManifestChunk를 서버에서 조립해 하나의 Manifest로 전달할지 여부
+    pub manifest_reassemble: bool,
+    /// 청크 manifest 한 건의 최대 누적 크기 (bytes)
+    pub manifest_max_bytes: usize,
+    /// 미완성 청크 묶음을 폐기하기까지의 시간
+    pub manifest_chunk_timeout_ms: u64,
+    /// target 없는 중계 한 건이 보낼 수 있는 최대 수신자 수 (0이면 제한 없음)
+    pub max_broadcast_fanout: usize,
+    /// Offer/Answer 중계 전 m= 라인 앞쪽으로 옮길 코덱 이름 (SDP_CODEC_PREFERENCE, 비어 있으면 비활성화)
+    pub sdp_codec_preference: Vec<String>,
+    /// 반대 방향 Offer가 대기 중일 때 glare로 판단하는 시간 (0이면 감지하지 않음)
+    pub glare_window_ms: u64,
+    /// 응답(Answer)을 받지 못한 target Offer를 피어당 동시에 몇 개까지 허용할지 (0이면 제한 없음)
+    pub max_pending_offers: usize,
+    /// BinaryRelay 한 건의 data 최대 크기 (bytes)
+    pub binary_relay_max_bytes: usize,
+    /// 보내는 피어당 초당 중계할 TransferProgress 수 (0이면 제한 없음)
+    pub transfer_progress_rate: u32,
+    /// 비어 있지 않으면 JSON 서버 메시지마다 HMAC-SHA256 `sig` 필드를 붙인다 (MESSAGE_SIGNING_SECRET)
+    #[serde(serialize_with = "redact")]
+    pub message_signing_secret: String,
+    /// 메트릭 label로 쓸 RelayToRole event 이름 (RELAY_METRIC_EVENTS). 나머지는 `other`로 센다
+    pub relay_metric_events: Vec<String>,
+    /// IceRestart처럼 targets 목록을 받는 중계의 최대 target 수 (0이면 제한 없음)
+    pub max_relay_targets: usize,
+    /// 알 수 없는 필드가 있는 클라이언트 메시지를 `unknown_field` 에러로 거절할지 여부 (STRICT_CLIENT_FIELDS)
+    pub strict_client_fields: bool,
+    /// RelayToRole/RelayToCapable data의 문자열 값에 걸리면 `content_blocked`로 거절할 정규식 (비어 있으면 끔)
+    pub relay_content_blocklist: String,
 }
 
 /// TURN 서버 설정
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TurnConfig {
     pub url: String,
+    #[serde(serialize_with = "redact")]
     pub secret: String,
     #[allow(dead_code)]
     pub realm: String,
@@ -88,11 +294,29 @@ pub struct TurnConfig {
     pub enable_tcp: bool,
     pub ports: TurnPorts,
     pub credential_ttl: u64,
+    /// 자격증명마다 credential_ttl에 더하거나 빼는 무작위 시간 (초). 갱신이 한꺼번에 몰리지 않게 한다
+    pub credential_ttl_jitter: u64,
+    /// 발급한 자격증명 만료 이 시간(ms) 전에 CredentialExpiringSoon을 보낸다 (0이면 끔)
+    pub expiry_reminder_ms: u64,
+    /// true이면 TURN 서버 도달 확인이 성공할 때까지 /ready가 503을 돌려준다 (REQUIRE_TURN_READY)
+    pub require_ready: bool,
     pub fallback_servers: Vec<String>,
+    /// TURN 설정과 무관하게 항상 포함하는 공개 STUN 서버 (STUN_SERVERS)
+    pub stun_servers: Vec<String>,
+    /// 설정하면 secret으로 HMAC 자격증명을 만드는 대신 이 주소에서 받아 온다
+    #[serde(serialize_with = "redact_url")]
+    pub credential_endpoint: String,
+}
+
+impl TurnConfig {
+    /// 자격증명을 만들 수단(TURN_SECRET 또는 TURN_CREDENTIAL_ENDPOINT)이 있는지
+    pub fn has_credential_source(&self) -> bool {
+        !self.secret.is_empty() || !self.credential_endpoint.is_empty()
+    }
 }
 
 /// TURN 포트 설정
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TurnPorts {
     pub udp: u16,
     pub tcp: u16,
@@ -100,13 +324,15 @@ pub struct TurnPorts {
 }
 
 /// Cloudflare R2 backed temporary file share 설정
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CloudConfig {
     pub enabled: bool,
     pub billing_enabled: bool,
     pub bucket: String,
     pub endpoint: String,
+    #[serde(serialize_with = "redact")]
     pub access_key_id: String,
+    #[serde(serialize_with = "redact")]
     pub secret_access_key: String,
     pub region: String,
     pub prefix: String,
@@ -121,7 +347,7 @@ pub struct CloudConfig {
 }
 
 /// PonsWarp AI Mesh / Workspace Coordinator settings.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct MeshConfig {
     pub enabled: bool,
     pub auto_approve_nodes: bool,
@@ -177,11 +403,18 @@ impl Config {
                 .parse()
                 .unwrap_or(5502),
             host: env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+            ws_path: normalize_route_path(&env::var("WS_PATH").unwrap_or_default())
+                .unwrap_or_else(|| "/ws".to_string()),
+            base_path: normalize_route_path(&env::var("BASE_PATH").unwrap_or_default())
+                .unwrap_or_default(),
             cors_origins: env::var("CORS_ORIGINS")
                 .unwrap_or_else(|_| "http://localhost:3500".to_string())
                 .split(',')
                 .map(|s| s.trim().to_string())
                 .collect(),
+            ws_enforce_origin: env::var("WS_ENFORCE_ORIGIN")
+                .map(|v| v == "true")
+                .unwrap_or(false),
             database: DatabaseConfig {
                 url: env::var("DATABASE_URL")
                     .or_else(|_| env::var("POSTGRES_URL"))
@@ -249,6 +482,7 @@ impl Config {
                         }
                     })
                     .collect(),
+                token: env::var("ADMIN_TOKEN").unwrap_or_default(),
             },
             billing: BillingConfig {
                 default_provider: env::var("PONSWARP_DEFAULT_PAYMENT_PROVIDER")
@@ -292,6 +526,231 @@ impl Config {
                     .unwrap_or_else(|_| "3600000".to_string())
                     .parse()
                     .unwrap_or(3600000),
+                keepalive_interval_ms: env::var("ROOM_KEEPALIVE_INTERVAL_MS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                closing_grace_ms: env::var("ROOM_CLOSING_GRACE_MS")
+                    .unwrap_or_else(|_| "5000".to_string())
+                    .parse()
+                    .unwrap_or(5000),
+                history_size: env::var("ROOM_HISTORY_SIZE")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                disconnect_grace_ms: env::var("DISCONNECT_GRACE_MS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                empty_room_linger_ms: env::var("EMPTY_ROOM_LINGER_MS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                transition_debounce_ms: env::var("ROOM_TRANSITION_DEBOUNCE_MS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                max_per_ip: env::var("MAX_PER_IP_PER_ROOM")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                max_rooms_per_owner: env::var("MAX_ROOMS_PER_OWNER")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                msg_rate_per_sec: env::var("ROOM_MSG_RATE")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                msg_burst: env::var("ROOM_MSG_BURST")
+                    .unwrap_or_else(|_| "200".to_string())
+                    .parse()
+                    .unwrap_or(200),
+                msg_rate_notify: env::var("ROOM_MSG_RATE_NOTIFY")
+                    .map(|v| v != "false")
+                    .unwrap_or(true),
+                list_rate_per_sec: env::var("PUBLIC_ROOMS_RATE")
+                    .unwrap_or_else(|_| "1".to_string())
+                    .parse()
+                    .unwrap_or(1),
+                list_burst: env::var("PUBLIC_ROOMS_BURST")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .unwrap_or(5),
+                metadata_max_bytes: env::var("ROOM_METADATA_MAX_BYTES")
+                    .unwrap_or_else(|_| "4096".to_string())
+                    .parse()
+                    .unwrap_or(4096),
+                create_warn_per_min: env::var("ROOM_CREATE_WARN_PER_MIN")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                create_warn_per_ip_per_min: env::var("ROOM_CREATE_WARN_PER_IP_PER_MIN")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                strict_ids: env::var("ROOM_ID_STRICT")
+                    .map(|v| v != "false")
+                    .unwrap_or(true),
+                duplicate_join: match env::var("DUPLICATE_JOIN_POLICY")
+                    .unwrap_or_default()
+                    .as_str()
+                {
+                    "notify" => DuplicateJoinPolicy::Notify,
+                    "ignore" => DuplicateJoinPolicy::Ignore,
+                    _ => DuplicateJoinPolicy::Snapshot,
+                },
+            },
+            cluster: ClusterConfig {
+                backend: env::var("CLUSTER_BACKEND")
+                    .unwrap_or_else(|_| "memory".to_string())
+                    .trim()
+                    .to_ascii_lowercase(),
+                redis_url: env::var("REDIS_URL")
+                    .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string()),
+                channel: env::var("CLUSTER_CHANNEL")
+                    .unwrap_or_else(|_| "ponswarp:relay".to_string()),
+                presence_ttl_secs: env::var("CLUSTER_PRESENCE_TTL_SECS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()
+                    .unwrap_or(60)
+                    .max(1),
+            },
+            limits: LimitsConfig {
+                max_connections: env::var("MAX_CONNECTIONS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                max_concurrent_upgrades: env::var("MAX_CONCURRENT_UPGRADES")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                max_roomless_peers: env::var("MAX_ROOMLESS_PEERS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                roomless_grace_ms: env::var("ROOMLESS_GRACE_MS")
+                    .unwrap_or_else(|_| "30000".to_string())
+                    .parse()
+                    .unwrap_or(30000),
+                listen_backlog: env::var("LISTEN_BACKLOG")
+                    .unwrap_or_else(|_| "1024".to_string())
+                    .parse()
+                    .unwrap_or(1024),
+                max_total_users: env::var("MAX_TOTAL_USERS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                max_concurrent_handlers: env::var("MAX_CONCURRENT_HANDLERS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                message_rate_per_sec: env::var("MESSAGE_RATE_LIMIT")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                message_burst: env::var("MESSAGE_RATE_BURST")
+                    .unwrap_or_else(|_| "50".to_string())
+                    .parse()
+                    .unwrap_or(50),
+                send_timeout_ms: env::var("SEND_TIMEOUT_MS")
+                    .unwrap_or_else(|_| "10000".to_string())
+                    .parse()
+                    .unwrap_or(10000),
+                outbound_shed_backlog: env::var("OUTBOUND_SHED_BACKLOG")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                max_connection_lifetime_ms: env::var("MAX_CONNECTION_LIFETIME_MS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                max_message_bytes: env::var("WS_MAX_MESSAGE_BYTES")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                inbound_queue_depth: env::var("INBOUND_QUEUE_DEPTH")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                inbound_drop_policy: match env::var("INBOUND_QUEUE_POLICY")
+                    .unwrap_or_default()
+                    .as_str()
+                {
+                    "drop_oldest" => InboundDropPolicy::DropOldest,
+                    _ => InboundDropPolicy::DropNewest,
+                },
+                map_shrink: env::var("MAP_SHRINK").map(|v| v == "true").unwrap_or(false),
+                tcp_nodelay: env::var("TCP_NODELAY")
+                    .map(|v| v != "false")
+                    .unwrap_or(true),
+                tcp_keepalive_secs: env::var("TCP_KEEPALIVE_SECS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                trusted_proxies: env::var("TRUSTED_PROXIES")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|proxy| proxy.trim().to_string())
+                    .filter(|proxy| !proxy.is_empty())
+                    .collect(),
+            },
+            signaling: SignalingConfig {
+                require_target: env::var("REQUIRE_SIGNALING_TARGET")
+                    .map(|v| v == "true")
+                    .unwrap_or(false),
+                manifest_reassemble: env::var("MANIFEST_REASSEMBLE")
+                    .map(|v| v == "true")
+                    .unwrap_or(false),
+                manifest_max_bytes: env::var("MANIFEST_MAX_BYTES")
+                    .unwrap_or_else(|_| "8388608".to_string())
+                    .parse()
+                    .unwrap_or(8 * 1024 * 1024),
+                manifest_chunk_timeout_ms: env::var("MANIFEST_CHUNK_TIMEOUT_MS")
+                    .unwrap_or_else(|_| "30000".to_string())
+                    .parse()
+                    .unwrap_or(30000),
+                max_broadcast_fanout: env::var("MAX_BROADCAST_FANOUT")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                sdp_codec_preference: env::var("SDP_CODEC_PREFERENCE")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|codec| codec.trim().to_string())
+                    .filter(|codec| !codec.is_empty())
+                    .collect(),
+                glare_window_ms: env::var("GLARE_WINDOW_MS")
+                    .unwrap_or_else(|_| "5000".to_string())
+                    .parse()
+                    .unwrap_or(5000),
+                max_pending_offers: env::var("MAX_PENDING_OFFERS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                binary_relay_max_bytes: env::var("BINARY_RELAY_MAX_BYTES")
+                    .unwrap_or_else(|_| "16384".to_string())
+                    .parse()
+                    .unwrap_or(16384),
+                transfer_progress_rate: env::var("TRANSFER_PROGRESS_RATE")
+                    .unwrap_or_else(|_| "4".to_string())
+                    .parse()
+                    .unwrap_or(4),
+                message_signing_secret: env::var("MESSAGE_SIGNING_SECRET").unwrap_or_default(),
+                max_relay_targets: env::var("MAX_RELAY_TARGETS")
+                    .unwrap_or_else(|_| "64".to_string())
+                    .parse()
+                    .unwrap_or(64),
+                relay_metric_events: env::var("RELAY_METRIC_EVENTS")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|event| event.trim().to_string())
+                    .filter(|event| !event.is_empty())
+                    .collect(),
+                strict_client_fields: env::var("STRICT_CLIENT_FIELDS")
+                    .map(|v| v == "true")
+                    .unwrap_or(false),
+                relay_content_blocklist: env::var("RELAY_CONTENT_BLOCKLIST").unwrap_or_default(),
             },
             turn: TurnConfig {
                 url: env::var("TURN_SERVER_URL").unwrap_or_default(),
@@ -324,12 +783,33 @@ impl Config {
                     .unwrap_or_else(|_| "3600".to_string())
                     .parse()
                     .unwrap_or(3600),
+                credential_ttl_jitter: env::var("TURN_CREDENTIAL_TTL_JITTER")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                expiry_reminder_ms: env::var("TURN_EXPIRY_REMINDER_MS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .unwrap_or(0),
+                require_ready: env::var("REQUIRE_TURN_READY")
+                    .map(|v| v == "true")
+                    .unwrap_or(false),
                 fallback_servers: env::var("TURN_FALLBACK_SERVERS")
                     .unwrap_or_default()
                     .split(',')
                     .filter(|s| !s.is_empty())
                     .map(|s| s.trim().to_string())
                     .collect(),
+                stun_servers: env::var("STUN_SERVERS")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect(),
+                credential_endpoint: env::var("TURN_CREDENTIAL_ENDPOINT")
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
             },
             cloud: CloudConfig {
                 enabled: cloud_enabled,
@@ -380,10 +860,168 @@ impl Config {
                     .unwrap_or(10 * 1024 * 1024 * 1024),
             },
             log_level: env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
+            log_signaling: env::var("LOG_SIGNALING")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            log_transfer: env::var("LOG_TRANSFER")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            motd: env::var("MOTD").unwrap_or_default().trim().to_string(),
+            motd_level: match env::var("MOTD_LEVEL").unwrap_or_default().as_str() {
+                "warn" => NoticeLevel::Warn,
+                _ => NoticeLevel::Info,
+            },
+            heartbeat_ack: env::var("HEARTBEAT_ACK")
+                .map(|v| v != "false")
+                .unwrap_or(true),
+            persistence_path: env::var("PERSISTENCE_PATH")
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            persistence_reconnect_window_ms: env::var("PERSISTENCE_RECONNECT_WINDOW_MS")
+                .unwrap_or_else(|_| "300000".to_string())
+                .parse()
+                .unwrap_or(300000),
+            instance_id: env::var("SERVER_INSTANCE_ID")
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            peer_id_format: match env::var("PEER_ID_FORMAT").unwrap_or_default().as_str() {
+                "short" => PeerIdFormat::Short,
+                _ => PeerIdFormat::Uuid,
+            },
+        }
+    }
+}
+
+impl Config {
+    /// LOG_LEVEL에 메시지 종류별 수준을 덧붙인 tracing EnvFilter 지시문
+    pub fn log_filter(&self) -> String {
+        let mut directives = vec![self.log_level.clone()];
+        if let Some(level) = &self.log_signaling {
+            directives.push(format!("{SIGNALING_LOG_TARGET}={}", level.trim()));
+        }
+        if let Some(level) = &self.log_transfer {
+            directives.push(format!("{TRANSFER_LOG_TARGET}={}", level.trim()));
+        }
+        directives.join(",")
+    }
+
+    /// 기동 전에 알 수 있는 설정 오류를 모두 모아 반환한다.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if self.room.max_size == 0 {
+            errors.push("MAX_ROOM_SIZE must be greater than 0".to_string());
+        }
+        if let Err(err) = tracing_subscriber::EnvFilter::try_new(self.log_filter()) {
+            errors.push(format!("invalid log filter {:?}: {err}", self.log_filter()));
+        }
+        if !self.turn.url.is_empty() && !self.turn.has_credential_source() {
+            errors.push(
+                "TURN_SERVER_URL requires TURN_SECRET or TURN_CREDENTIAL_ENDPOINT".to_string(),
+            );
+        }
+
+        for proxy in &self.limits.trusted_proxies {
+            if proxy.parse::<std::net::IpAddr>().is_err() {
+                errors.push(format!("invalid TRUSTED_PROXIES address: {proxy}"));
+            }
+        }
+
+        let blocklist = &self.signaling.relay_content_blocklist;
+        if !blocklist.is_empty() {
+            if let Err(err) = regex_lite::Regex::new(blocklist) {
+                errors.push(format!("invalid RELAY_CONTENT_BLOCKLIST: {err}"));
+            }
+        }
+
+        let cloud = &self.cloud;
+        if cloud.enabled
+            && (cloud.bucket.is_empty()
+                || cloud.endpoint.is_empty()
+                || cloud.access_key_id.is_empty()
+                || cloud.secret_access_key.is_empty())
+        {
+            errors
+                .push("PONSWARP_CLOUD_ENABLED=true requires complete R2 configuration".to_string());
+        }
+        if cloud.billing_enabled {
+            if self.database.url.trim().is_empty() {
+                errors.push(
+                    "PONSWARP_BILLING_ENABLED=true requires DATABASE_URL or POSTGRES_URL"
+                        .to_string(),
+                );
+            }
+            if self.billing.public_app_url.trim().is_empty() {
+                errors.push(
+                    "PONSWARP_BILLING_ENABLED=true requires PONSWARP_PUBLIC_APP_URL".to_string(),
+                );
+            }
+        }
+
+        match self.cluster.backend.as_str() {
+            "" | "memory" => {}
+            "redis" if !cfg!(feature = "redis") => errors
+                .push("CLUSTER_BACKEND=redis requires building with --features redis".to_string()),
+            "redis" if self.cluster.redis_url.is_empty() => {
+                errors.push("CLUSTER_BACKEND=redis requires REDIS_URL".to_string())
+            }
+            "redis" => {}
+            other => errors.push(format!("unknown CLUSTER_BACKEND: {other}")),
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
     }
 }
 
+/// 비밀 값은 설정 여부만 드러낸다.
+fn redact<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    if value.is_empty() {
+        serializer.serialize_str("")
+    } else {
+        serializer.serialize_str("<redacted>")
+    }
+}
+
+/// 접속 URL은 user:password 부분과 query 값(apiKey 등)을 가린다.
+fn redact_url<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&redact_url_credentials(value))
+}
+
+fn redact_url_credentials(url: &str) -> String {
+    let (base, query) = match url.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (url, None),
+    };
+    let mut redacted = match base.split_once("://") {
+        Some((scheme, rest)) => {
+            let authority_end = rest.find('/').unwrap_or(rest.len());
+            match rest[..authority_end].rfind('@') {
+                Some(at) => format!("{scheme}://<redacted>{}", &rest[at..]),
+                None => base.to_string(),
+            }
+        }
+        None => base.to_string(),
+    };
+    if let Some(query) = query {
+        let pairs: Vec<String> = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) if !value.is_empty() => format!("{key}=<redacted>"),
+                _ => pair.to_string(),
+            })
+            .collect();
+        redacted.push('?');
+        redacted.push_str(&pairs.join("&"));
+    }
+    redacted
+}
+
 #[cfg(test)]
 impl Config {
     pub fn from_env_with_mesh(mesh: MeshConfig) -> Self {
@@ -393,6 +1031,16 @@ impl Config {
     }
 }
 
+/// 라우트 경로를 `/a/b` 형태로 정규화한다. 비어 있거나 `/`뿐이면 None.
+fn normalize_route_path(raw: &str) -> Option<String> {
+    let trimmed = raw.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(format!("/{trimmed}"))
+    }
+}
+
 fn load_env_files() {
     dotenvy::from_filename(".env").ok();
 
@@ -418,3 +1066,126 @@ fn load_env_file_if_exists(path: &str) {
         dotenvy::from_filename_override(path).ok();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing::{Event, Level, Subscriber};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+    /// 필터를 통과한 이벤트의 (target, level)을 기록하는 테스트 layer
+    struct CaptureLayer(Arc<Mutex<Vec<(String, Level)>>>);
+
+    impl<S: Subscriber> Layer<S> for CaptureLayer {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let metadata = event.metadata();
+            self.0
+                .lock()
+                .unwrap()
+                .push((metadata.target().to_string(), *metadata.level()));
+        }
+    }
+
+    #[test]
+    fn log_filter_appends_per_category_levels() {
+        let mut config = Config::from_env();
+        config.log_level = "info".to_string();
+        config.log_signaling = Some("debug".to_string());
+        config.log_transfer = None;
+
+        assert_eq!(config.log_filter(), "info,ponswarp::signaling=debug");
+    }
+
+    #[test]
+    fn per_category_levels_filter_relay_events() {
+        let mut config = Config::from_env();
+        config.log_level = "info".to_string();
+        config.log_signaling = Some("debug".to_string());
+        config.log_transfer = Some("warn".to_string());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_subscriber::EnvFilter::new(config.log_filter()))
+            .with(CaptureLayer(events.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(target: SIGNALING_LOG_TARGET, "Relayed offer");
+            tracing::info!(target: TRANSFER_LOG_TARGET, "Relayed manifest");
+            tracing::warn!(target: TRANSFER_LOG_TARGET, "Dropped manifest chunks");
+        });
+
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events.as_slice(),
+            &[
+                (SIGNALING_LOG_TARGET.to_string(), Level::DEBUG),
+                (TRANSFER_LOG_TARGET.to_string(), Level::WARN),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_collects_every_problem() {
+        let mut config = Config::from_env();
+        config.room.max_size = 0;
+        config.turn.url = "turn:turn.example.com".to_string();
+        config.turn.secret = String::new();
+        config.cluster.backend = "etcd".to_string();
+        config.signaling.relay_content_blocklist = "(unclosed".to_string();
+
+        let errors = config.validate().unwrap_err();
+
+        assert_eq!(errors.len(), 4, "{errors:?}");
+        assert!(errors.iter().any(|e| e.contains("CLUSTER_BACKEND")));
+        assert!(errors.iter().any(|e| e.contains("RELAY_CONTENT_BLOCKLIST")));
+    }
+
+    #[test]
+    fn serialized_config_redacts_secrets() {
+        let mut config = Config::from_env();
+        config.turn.secret = "turn-secret".to_string();
+        config.admin.token = "admin-token".to_string();
+        config.database.url = "postgres://user:pw@db.internal:5432/app".to_string();
+
+        let json = serde_json::to_string(&config).unwrap();
+
+        assert!(!json.contains("turn-secret"));
+        assert!(!json.contains("admin-token"));
+        assert!(!json.contains("user:pw"));
+        assert!(json.contains("postgres://<redacted>@db.internal:5432/app"));
+    }
+
+    #[test]
+    fn credential_endpoint_query_values_are_redacted() {
+        let mut config = Config::from_env();
+        config.turn.credential_endpoint =
+            "https://turn.example.com/credentials?apiKey=sk-live-1&region=eu".to_string();
+
+        let json = serde_json::to_string(&config).unwrap();
+
+        assert!(!json.contains("sk-live-1"));
+        assert!(json
+            .contains("https://turn.example.com/credentials?apiKey=<redacted>&region=<redacted>"));
+    }
+
+    #[test]
+    fn turn_url_with_only_credential_endpoint_is_valid() {
+        let mut config = Config::from_env();
+        config.turn.url = "turn.example.com".to_string();
+        config.turn.secret.clear();
+        config.turn.credential_endpoint = "https://turn.example.com/credentials".to_string();
+        let turn_errors = |config: &Config| {
+            config
+                .validate()
+                .err()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|error| error.contains("TURN_SERVER_URL"))
+                .count()
+        };
+        assert_eq!(turn_errors(&config), 0);
+
+        config.turn.credential_endpoint.clear();
+        assert_eq!(turn_errors(&config), 1);
+    }
+}
@@ -1496,7 +1496,7 @@ fn verify_password_hash(state: &AppState, password: &str, expected: &str) -> boo
         return false;
     }
     let candidate = keyed_digest(state, &format!("{}:{password}", parts[2]));
-    constant_time_eq(candidate.as_bytes(), parts[3].as_bytes())
+    crate::crypto::constant_time_eq(candidate.as_bytes(), parts[3].as_bytes())
 }
 
 fn token_hash(state: &AppState, token: &str) -> String {
@@ -1526,16 +1526,6 @@ fn hex(bytes: &[u8]) -> String {
     out
 }
 
-fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
-    if left.len() != right.len() {
-        return false;
-    }
-    left.iter()
-        .zip(right.iter())
-        .fold(0_u8, |diff, (a, b)| diff | (a ^ b))
-        == 0
-}
-
 fn reject_expired(manifest: &CloudShareManifest) -> Result<(), CloudShareError> {
     if manifest.expires_at <= unix_now() {
         return Err(CloudShareError::gone("Share has expired"));
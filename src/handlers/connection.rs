@@ -1,48 +1,1298 @@
 //! 연결 핸들러
 
-use crate::protocol::ServerMessage;
-use crate::state::{AppState, PeerSession};
+use crate::admin::AdminEvent;
+use crate::config::PeerIdFormat;
+use crate::handlers::send_error;
+use crate::i18n::Locale;
+use crate::protocol::{PeerRole, ServerMessage};
+use crate::rate_limit::TokenBucket;
+use crate::state::{AppState, AwayPeer, Capabilities, PeerSession, PeerStats};
+use dashmap::mapref::entry::Entry;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::{mpsc::UnboundedSender, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc::UnboundedSender, Notify, RwLock};
 use uuid::Uuid;
 
+const MAX_CLIENT_TOKEN_LEN: usize = 128;
+
+/// 연결 수 초과 시 권장 대기 시간의 기본값과 상한
+const CAPACITY_RETRY_BASE_MS: u64 = 1_000;
+const CAPACITY_RETRY_MAX_MS: u64 = 30_000;
+
+/// 연결이 끝날 때까지 쥐고 있는 연결 슬롯. drop되면 슬롯을 돌려준다.
+#[derive(Debug)]
+#[must_use]
+pub struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// MAX_CONNECTIONS, MAX_ROOMLESS_PEERS 확인. 통과하면 슬롯을 원자적으로 확보해 돌려주므로
+/// 동시에 들어온 연결이 한도를 넘지 않는다. 초과 시 부하에 비례한 `retry_after_ms`를 담은 에러를 반환한다.
+pub fn check_capacity(state: &AppState) -> Result<ConnectionSlot, Box<ServerMessage>> {
+    check_roomless_peers(state)?;
+    let max_connections = state.config.limits.max_connections;
+    let reserved =
+        state
+            .connection_slots
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (max_connections == 0 || active < max_connections).then_some(active + 1)
+            });
+    let active = match reserved {
+        Ok(_) => return Ok(ConnectionSlot(state.connection_slots.clone())),
+        Err(active) => active,
+    };
+
+    // 초과분이 클수록 오래 기다리게 하고, 동시에 몰리지 않도록 지터를 더한다.
+    let overload = (active - max_connections + 1) as u64;
+    let retry_after_ms = (CAPACITY_RETRY_BASE_MS * overload).min(CAPACITY_RETRY_MAX_MS)
+        + rand::random::<u64>() % CAPACITY_RETRY_BASE_MS;
+    tracing::warn!(
+        active,
+        max_connections,
+        retry_after_ms,
+        "Server at capacity, rejected connection"
+    );
+    Err(Box::new(ServerMessage::Error {
+        code: "server_at_capacity".to_string(),
+        message: "Server is at capacity, retry later".to_string(),
+        retry_after_ms: Some(retry_after_ms),
+        correlation_id: None,
+    }))
+}
+
+/// 방 없이 머무는 피어가 MAX_ROOMLESS_PEERS 이상이면 새 연결을 받지 않는다.
+/// 피어가 방에 들어가거나 끊기면 다시 받는다.
+fn check_roomless_peers(state: &AppState) -> Result<(), Box<ServerMessage>> {
+    let limits = &state.config.limits;
+    if limits.max_roomless_peers == 0 {
+        return Ok(());
+    }
+    let roomless = state.roomless_peers.load(Ordering::Acquire);
+    state.metrics.connections.set_roomless_peers(roomless);
+    if roomless < limits.max_roomless_peers {
+        return Ok(());
+    }
+    tracing::warn!(
+        roomless,
+        max_roomless_peers = limits.max_roomless_peers,
+        "Too many peers without a room, rejected connection"
+    );
+    Err(Box::new(ServerMessage::Error {
+        code: "roomless_peer_limit".to_string(),
+        message: "Too many connections without a room, retry later".to_string(),
+        retry_after_ms: Some(
+            CAPACITY_RETRY_BASE_MS + rand::random::<u64>() % CAPACITY_RETRY_BASE_MS,
+        ),
+        correlation_id: None,
+    }))
+}
+
+/// ROOMLESS_GRACE_MS가 지나도록 방에 들어가지 않은 피어에게 `roomless_timeout`을 보내고
+/// 연결을 닫는다. 닫은 수를 돌려준다.
+pub fn disconnect_roomless_peers(state: &AppState) -> usize {
+    let grace = Duration::from_millis(state.config.limits.roomless_grace_ms);
+    let idle = state.roomless_past(grace);
+    for peer_id in &idle {
+        if let Some(session) = state.peers.get(peer_id) {
+            let _ = session.sender.send(ServerMessage::Error {
+                code: "roomless_timeout".to_string(),
+                message: "No room joined in time, connection closed".to_string(),
+                retry_after_ms: None,
+                correlation_id: None,
+            });
+            session.close_signal.notify_one();
+        }
+    }
+    if !idle.is_empty() {
+        tracing::info!(closed = idle.len(), "Closed peers that never joined a room");
+    }
+    idle.len()
+}
+
+/// MAX_CONNECTION_LIFETIME_MS 초과 안내. 클라이언트는 바로 재접속하면 된다.
+pub fn lifetime_exceeded_error() -> ServerMessage {
+    ServerMessage::Error {
+        code: "lifetime_exceeded".to_string(),
+        message: "Connection lifetime exceeded, please reconnect".to_string(),
+        retry_after_ms: None,
+        correlation_id: None,
+    }
+}
+
+/// 수신 큐가 가득 차 메시지를 버렸을 때 보내는 에러
+pub fn inbound_dropped_error() -> ServerMessage {
+    ServerMessage::Error {
+        code: "inbound_queue_full".to_string(),
+        message: "Too many pending messages, one was dropped".to_string(),
+        retry_after_ms: None,
+        correlation_id: None,
+    }
+}
+
+/// 서버 전체 처리 한도(MAX_CONCURRENT_HANDLERS)에 걸려 버린 메시지 응답
+pub fn server_overloaded_error() -> ServerMessage {
+    ServerMessage::Error {
+        code: "server_overloaded".to_string(),
+        message: "Server is busy, message was dropped".to_string(),
+        retry_after_ms: None,
+        correlation_id: None,
+    }
+}
+
+/// 해석할 수 없는 바이너리 frame 응답
+pub fn invalid_binary_frame_error(reason: &str) -> ServerMessage {
+    ServerMessage::Error {
+        code: "invalid_binary_frame".to_string(),
+        message: format!("Malformed binary frame: {reason}"),
+        retry_after_ms: None,
+        correlation_id: None,
+    }
+}
+
+/// 엄격 모드(STRICT_CLIENT_FIELDS)에서 알 수 없는 필드가 있는 메시지 응답
+pub fn unknown_field_error(field: &str) -> ServerMessage {
+    ServerMessage::Error {
+        code: "unknown_field".to_string(),
+        message: format!("Unknown field `{field}`"),
+        retry_after_ms: None,
+        correlation_id: None,
+    }
+}
+
+/// 메시지 속도 제한 초과 응답
+pub fn rate_limited_error(retry_after_ms: u64) -> ServerMessage {
+    ServerMessage::Error {
+        code: "rate_limited".to_string(),
+        message: "Too many messages, slow down".to_string(),
+        retry_after_ms: Some(retry_after_ms.max(1)),
+        correlation_id: None,
+    }
+}
+
 /// 새 연결 처리
+///
+/// `client_token`이 주어지면 해당 토큰을 이번 세션의 peer_id로 다시 매핑하고,
+/// 없거나 형식이 잘못되었거나 아직 연결 중인 피어가 쓰고 있으면 새 토큰을 발급한다.
+/// 재접속 유예 중인 세션의 `resume_token`을 넘기면 이전 peer_id와 방을 그대로 이어받는다.
+/// `client_token`은 누구나 알 수 있는 값이므로 그것만으로는 이어받지 않는다.
 pub async fn handle_connection(
     state: Arc<AppState>,
     sender: UnboundedSender<ServerMessage>,
+    client_token: Option<String>,
+    remote_ip: Option<IpAddr>,
+    resume_token: Option<String>,
 ) -> String {
-    let peer_id = Uuid::new_v4().to_string();
+    let requested = client_token
+        .map(|token| token.trim().to_string())
+        .filter(|token| is_valid_client_token(token));
+    let resumed = resume_token
+        .and_then(|token| state.away_peers.remove(token.trim()))
+        .map(|(_, away)| away);
+
+    let (peer_id, room_id, role, requested) = match &resumed {
+        Some(away) => (
+            away.peer_id.clone(),
+            Some(away.room_id.clone()),
+            away.role,
+            Some(away.client_token.clone()),
+        ),
+        None => (new_peer_id(&state), None, PeerRole::default(), requested),
+    };
+    let client_token = claim_client_token(&state, requested, &peer_id);
+    let resume_token = Uuid::new_v4().to_string();
+    let roomless = room_id.is_none();
 
     let session = PeerSession {
         id: peer_id.clone(),
-        room_id: RwLock::new(None),
+        client_token: client_token.clone(),
+        resume_token: resume_token.clone(),
+        remote_ip,
+        room_id: RwLock::new(room_id),
+        role: RwLock::new(role),
         sender: sender.clone(),
         connected_at: Instant::now(),
+        stats: Arc::new(PeerStats::default()),
+        room_list_limiter: TokenBucket::for_room_list(&state.config.room)
+            .map(std::sync::Mutex::new),
+        progress_limiter: TokenBucket::for_transfer_progress(&state.config.signaling)
+            .map(std::sync::Mutex::new),
+        close_signal: Arc::new(Notify::new()),
+        subscriptions: Arc::default(),
+        capabilities: Capabilities::default(),
+        turn_expires_at_ms: AtomicU64::new(0),
+        locale: Arc::default(),
+        last_room_leave: std::sync::Mutex::new(None),
+        completed_transfers: std::sync::Mutex::default(),
     };
 
+    state.track_roomless(false, roomless);
     state.peers.insert(peer_id.clone(), session);
+    state.emit_admin_event(AdminEvent::PeerConnected {
+        peer_id: peer_id.clone(),
+    });
 
     let _ = sender.send(ServerMessage::Connected {
         socket_id: peer_id.clone(),
+        client_token,
+        resume_token,
+        server_version: crate::version::VERSION.to_string(),
+        instance_id: state.instance_id.clone(),
     });
+    if let Some(motd) = state.motd.read().await.clone() {
+        let _ = sender.send(ServerMessage::Notice {
+            text: motd.text,
+            level: motd.level,
+        });
+    }
 
-    tracing::info!(peer_id = %peer_id, "New connection established");
+    if let Some(away) = resumed {
+        tracing::info!(
+            peer_id = %peer_id,
+            room_id = %away.room_id,
+            away_ms = away.since.elapsed().as_millis() as u64,
+            "Connection resumed within grace period"
+        );
+    } else {
+        tracing::info!(peer_id = %peer_id, "New connection established");
+    }
     peer_id
 }
 
+/// 요청한 토큰을 `peer_id`에 매핑한다. 연결 중인 다른 피어의 토큰이면 빼앗지 않고 새 토큰을 발급한다.
+fn claim_client_token(state: &AppState, requested: Option<String>, peer_id: &str) -> String {
+    if let Some(token) = requested {
+        match state.client_tokens.entry(token.clone()) {
+            Entry::Occupied(entry) if state.peers.contains_key(entry.get()) => {
+                tracing::warn!(peer_id = %peer_id, holder = %entry.get(), "Client token in use by a live peer, issued a new one");
+            }
+            Entry::Occupied(mut entry) => {
+                entry.insert(peer_id.to_string());
+                return token;
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(peer_id.to_string());
+                return token;
+            }
+        }
+    }
+    let token = Uuid::new_v4().to_string();
+    state
+        .client_tokens
+        .insert(token.clone(), peer_id.to_string());
+    token
+}
+
+/// PEER_ID_FORMAT에 맞는 peer_id 발급. short는 사용 중인 id와 겹치면 다시 뽑는다.
+fn new_peer_id(state: &AppState) -> String {
+    match state.config.peer_id_format {
+        PeerIdFormat::Uuid => Uuid::new_v4().to_string(),
+        PeerIdFormat::Short => loop {
+            let id = short_id();
+            let in_use = state.peers.contains_key(&id)
+                || state.away_peers.iter().any(|away| away.peer_id == id);
+            if !in_use {
+                break id;
+            }
+        },
+    }
+}
+
+/// 40비트 난수를 8자리 소문자 base32(RFC 4648 알파벳)로 인코딩
+fn short_id() -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let bits: u64 = rand::random::<u64>() & ((1 << 40) - 1);
+    (0..8)
+        .map(|i| ALPHABET[((bits >> (35 - i * 5)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+fn is_valid_client_token(token: &str) -> bool {
+    !token.is_empty()
+        && token.len() <= MAX_CLIENT_TOKEN_LEN
+        && token
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+/// 연결 종료 시 남기는 세션 요약
+#[derive(Debug)]
+pub struct SessionSummary {
+    pub peer_id: String,
+    pub room_id: Option<String>,
+    pub duration_ms: u64,
+    pub messages_received: u64,
+    pub messages_sent: u64,
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+}
+
+impl SessionSummary {
+    fn from_session(session: &PeerSession, room_id: Option<String>) -> Self {
+        let stats = &session.stats;
+        Self {
+            peer_id: session.id.clone(),
+            room_id,
+            duration_ms: session.connected_at.elapsed().as_millis() as u64,
+            messages_received: stats.messages_received.load(Ordering::Relaxed),
+            messages_sent: stats.messages_sent.load(Ordering::Relaxed),
+            bytes_received: stats.bytes_received.load(Ordering::Relaxed),
+            bytes_sent: stats.bytes_sent.load(Ordering::Relaxed),
+        }
+    }
+}
+
 /// 연결 해제 처리
-pub async fn handle_disconnect(state: Arc<AppState>, peer_id: &str) {
-    if let Some((_, session)) = state.peers.remove(peer_id) {
-        let room_id = session.room_id.read().await.clone();
-        if let Some(room_id) = room_id {
-            crate::handlers::room::leave_room_internal(&state, peer_id, &room_id).await;
+pub async fn handle_disconnect(state: Arc<AppState>, peer_id: &str) -> Option<SessionSummary> {
+    let (_, session) = state.peers.remove(peer_id)?;
+    // 이미 다른 세션이 같은 토큰으로 재접속했다면 매핑을 건드리지 않는다.
+    state
+        .client_tokens
+        .remove_if(&session.client_token, |_, current| current == peer_id);
+    state
+        .manifest_chunks
+        .retain(|(from, _), _| from.as_str() != peer_id);
+    state
+        .pending_offers
+        .retain(|(from, to), _| from.as_str() != peer_id && to.as_str() != peer_id);
+    state
+        .offer_generations
+        .retain(|(from, to), _| from.as_str() != peer_id && to.as_str() != peer_id);
+
+    let room_id = session.room_id.read().await.clone();
+    state.track_roomless(room_id.is_none(), false);
+    if let Some(room_id) = &room_id {
+        let grace_ms = disconnect_grace_ms(&state, room_id);
+        if grace_ms > 0 {
+            let role = *session.role.read().await;
+            start_disconnect_grace(&state, &session, peer_id, room_id, role, grace_ms);
+        } else {
+            crate::handlers::room::leave_room_internal(&state, peer_id, room_id).await;
         }
     }
-    tracing::info!(peer_id = %peer_id, "Connection closed");
+
+    state.emit_admin_event(AdminEvent::PeerDisconnected {
+        peer_id: peer_id.to_string(),
+    });
+    let summary = SessionSummary::from_session(&session, room_id);
+    tracing::info!(
+        peer_id = %summary.peer_id,
+        room_id = ?summary.room_id,
+        duration_ms = summary.duration_ms,
+        messages_received = summary.messages_received,
+        messages_sent = summary.messages_sent,
+        bytes_received = summary.bytes_received,
+        bytes_sent = summary.bytes_sent,
+        "Connection closed"
+    );
+    Some(summary)
+}
+
+/// 관리자 요청으로 피어 연결을 끊는다. 사유를 `closed_by_admin` 에러로 보낸 뒤
+/// 재접속 유예 없이 방에서 내보내고 세션을 제거한다. 세션이 없으면 false.
+pub async fn close_peer(state: Arc<AppState>, peer_id: &str, reason: &str) -> bool {
+    let room_id = {
+        let Some(session) = state.peers.get(peer_id) else {
+            return false;
+        };
+        let _ = session.sender.send(ServerMessage::Error {
+            code: "closed_by_admin".to_string(),
+            message: reason.to_string(),
+            retry_after_ms: None,
+            correlation_id: None,
+        });
+        session.close_signal.notify_one();
+        let room_id = session.room_id.write().await.take();
+        state.track_roomless(room_id.is_none(), true);
+        room_id
+    };
+
+    if let Some(room_id) = &room_id {
+        crate::handlers::room::leave_room_internal(&state, peer_id, room_id).await;
+    }
+    handle_disconnect(state, peer_id).await;
+    tracing::info!(peer_id = %peer_id, room_id = ?room_id, reason = %reason, "Peer closed by admin");
+    true
+}
+
+/// 방이 만들 때 지정한 재접속 유예의 상한
+const MAX_ROOM_RECONNECT_GRACE_MS: u64 = 10 * 60 * 1000;
+
+/// 방에 `reconnect_grace_ms`가 있으면 전역 DISCONNECT_GRACE_MS 대신 사용한다.
+fn disconnect_grace_ms(state: &AppState, room_id: &str) -> u64 {
+    state
+        .rooms
+        .get(room_id)
+        .and_then(|room| room.options.reconnect_grace_ms)
+        .map_or(state.config.room.disconnect_grace_ms, |grace_ms| {
+            grace_ms.min(MAX_ROOM_RECONNECT_GRACE_MS)
+        })
+}
+
+/// 피어를 away 상태로 두고, 유예 시간 안에 재접속하지 않으면 방에서 내보낸다.
+/// away 항목은 세션의 `resume_token`으로 찾는다.
+fn start_disconnect_grace(
+    state: &Arc<AppState>,
+    session: &PeerSession,
+    peer_id: &str,
+    room_id: &str,
+    role: PeerRole,
+    grace_ms: u64,
+) {
+    state.away_peers.insert(
+        session.resume_token.clone(),
+        AwayPeer {
+            peer_id: peer_id.to_string(),
+            client_token: session.client_token.clone(),
+            room_id: room_id.to_string(),
+            role,
+            since: Instant::now(),
+        },
+    );
+    tracing::debug!(peer_id = %peer_id, room_id = %room_id, grace_ms, "Peer marked away");
+
+    let state = state.clone();
+    let resume_token = session.resume_token.clone();
+    let peer_id = peer_id.to_string();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(grace_ms)).await;
+        let expired = state
+            .away_peers
+            .remove_if(&resume_token, |_, away| away.peer_id == peer_id);
+        if let Some((_, away)) = expired {
+            tracing::info!(peer_id = %peer_id, room_id = %away.room_id, "Disconnect grace expired");
+            crate::handlers::room::leave_room_internal(&state, &peer_id, &away.room_id).await;
+        }
+    });
+}
+
+/// 송신 실패한 피어를 정리 대기열에 넣는다. 방 lock을 놓은 뒤 호출해야 한다.
+pub fn enqueue_dead_peers(state: &AppState, peer_ids: Vec<String>) {
+    if peer_ids.is_empty() {
+        return;
+    }
+    for peer_id in peer_ids {
+        tracing::debug!(peer_id = %peer_id, "Queued dead peer for cleanup");
+        state.dead_peers.insert(peer_id);
+    }
+    state.dead_peers_notify.notify_one();
+}
+
+/// 정리 대기열의 피어 중 채널이 실제로 닫힌 세션을 연결 해제 처리한다.
+pub async fn reap_dead_peers(state: &Arc<AppState>) -> usize {
+    let queued: Vec<String> = state.dead_peers.iter().map(|id| id.clone()).collect();
+    let mut reaped = 0;
+    for peer_id in queued {
+        state.dead_peers.remove(&peer_id);
+        let closed = state
+            .peers
+            .get(&peer_id)
+            .is_some_and(|session| session.sender.is_closed());
+        if closed && handle_disconnect(state.clone(), &peer_id).await.is_some() {
+            reaped += 1;
+        }
+    }
+    if reaped > 0 {
+        tracing::info!(reaped, "Reaped dead peers");
+    }
+    reaped
+}
+
+/// 특정 피어 온라인 여부 조회. 방 정보는 같은 방에 있을 때만 공개한다.
+pub async fn handle_peer_status_query(state: &AppState, requester_id: &str, peer_id: &str) {
+    let target_room = match state.peers.get(peer_id) {
+        Some(session) => Some(session.room_id.read().await.clone()),
+        None => None,
+    };
+    let requester_room = match state.peers.get(requester_id) {
+        Some(session) => session.room_id.read().await.clone(),
+        None => None,
+    };
+
+    let online = target_room.is_some();
+    let room_id = target_room
+        .flatten()
+        .filter(|room_id| requester_room.as_deref() == Some(room_id.as_str()));
+
+    if let Some(session) = state.peers.get(requester_id) {
+        let _ = session.sender.send(ServerMessage::PeerStatus {
+            peer_id: peer_id.to_string(),
+            online,
+            room_id,
+        });
+    }
 }
 
-/// Heartbeat 처리
-pub fn handle_heartbeat(sender: &UnboundedSender<ServerMessage>) {
-    let _ = sender.send(ServerMessage::HeartbeatAck);
+/// WhoAmI 처리. 재접속 후 실제 적용된 peer_id와 방을 알려준다.
+pub async fn handle_whoami(state: &AppState, peer_id: &str) {
+    let Some(session) = state.peers.get(peer_id) else {
+        return;
+    };
+    let room_id = session.room_id.read().await.clone();
+    let role = *session.role.read().await;
+    let _ = session.sender.send(ServerMessage::Identity {
+        peer_id: peer_id.to_string(),
+        room_id,
+        role,
+    });
+}
+
+/// Subscribe 처리. 이후 이 피어에게는 `types`에 있는 ServerMessage만 보낸다.
+pub fn handle_subscribe(state: &AppState, peer_id: &str, types: Vec<String>) {
+    if let Some(session) = state.peers.get(peer_id) {
+        tracing::debug!(peer_id = %peer_id, types = ?types, "Updated message subscriptions");
+        session.subscriptions.set(types);
+    }
+}
+
+/// Hello 처리. 지원하는 확장만 켜고 `HelloAck`로 알려준다.
+pub fn handle_hello(state: &AppState, peer_id: &str, capabilities: Vec<String>) {
+    if let Some(session) = state.peers.get(peer_id) {
+        let capabilities = session.capabilities.negotiate(capabilities);
+        tracing::debug!(peer_id = %peer_id, capabilities = ?capabilities, "Negotiated capabilities");
+        let ack = ServerMessage::HelloAck { capabilities };
+        let _ = session.sender.send(ack);
+    }
+}
+
+/// SetCapabilities 처리. 이후 RelayToCapable은 여기서 알린 기능으로 수신자를 고른다.
+pub fn handle_set_capabilities(state: &AppState, peer_id: &str, capabilities: Vec<String>) {
+    if let Some(session) = state.peers.get(peer_id) {
+        session.capabilities.advertise(capabilities);
+    }
+}
+
+/// SetLocale 처리. 이후 이 연결로 가는 에러 문구를 `lang`으로 보낸다.
+pub fn handle_set_locale(state: &AppState, peer_id: &str, lang: &str) {
+    let Some(locale) = Locale::parse(lang) else {
+        send_error(
+            state,
+            peer_id,
+            "unsupported_locale",
+            "Unsupported locale, supported: en, ko",
+        );
+        return;
+    };
+    if let Some(session) = state.peers.get(peer_id) {
+        session.locale.set(locale);
+    }
+}
+
+/// Heartbeat 처리. HEARTBEAT_ACK=false이면 수신 시각만 갱신하고 응답하지 않는다.
+/// 방 멤버의 Heartbeat는 방 활동으로도 센다 (KeepAlive 응답).
+pub async fn handle_heartbeat(state: &AppState, peer_id: &str) {
+    let Some(session) = state.peers.get(peer_id) else {
+        return;
+    };
+    session.stats.record_heartbeat();
+    if state.config.heartbeat_ack {
+        let _ = session.sender.send(ServerMessage::HeartbeatAck);
+    }
+    let room_id = session.room_id.read().await.clone();
+    drop(session);
+    if let Some(room_id) = room_id {
+        state.touch_room(&room_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::NoticeLevel;
+    use crate::state::drain_messages;
+
+    async fn set_room(state: &AppState, peer_id: &str, room_id: &str) {
+        let session = state.peers.get(peer_id).expect("peer exists");
+        *session.room_id.write().await = Some(room_id.to_string());
+    }
+
+    fn peer_status(messages: Vec<ServerMessage>) -> (bool, Option<String>) {
+        match messages.as_slice() {
+            [ServerMessage::PeerStatus {
+                online, room_id, ..
+            }] => (*online, room_id.clone()),
+            other => panic!("unexpected messages: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn client_token_follows_reconnect_to_new_peer_id() {
+        let state = Arc::new(AppState::new_for_test());
+        let (first_tx, _first_rx) = tokio::sync::mpsc::unbounded_channel();
+        let first = handle_connection(
+            state.clone(),
+            first_tx,
+            Some("device-1".to_string()),
+            None,
+            None,
+        )
+        .await;
+        assert_eq!(
+            state.resolve_peer_id("device-1").as_deref(),
+            Some(first.as_str())
+        );
+
+        handle_disconnect(state.clone(), &first).await;
+        assert!(state.resolve_peer_id("device-1").is_none());
+
+        let (second_tx, mut second_rx) = tokio::sync::mpsc::unbounded_channel();
+        let second = handle_connection(
+            state.clone(),
+            second_tx,
+            Some("device-1".to_string()),
+            None,
+            None,
+        )
+        .await;
+        assert_ne!(first, second);
+        assert_eq!(
+            state.resolve_peer_id("device-1").as_deref(),
+            Some(second.as_str())
+        );
+        assert!(matches!(
+            drain_messages(&mut second_rx).as_slice(),
+            [ServerMessage::Connected { client_token, .. }] if client_token == "device-1"
+        ));
+    }
+
+    async fn state_with_grace(grace_ms: u64) -> Arc<AppState> {
+        let mut config = crate::config::Config::from_env();
+        config.room.disconnect_grace_ms = grace_ms;
+        Arc::new(AppState::new_for_test_with_config(config))
+    }
+
+    fn has_user_left(messages: &[ServerMessage]) -> bool {
+        messages
+            .iter()
+            .any(|message| matches!(message, ServerMessage::UserLeft { .. }))
+    }
+
+    #[tokio::test]
+    async fn reconnect_within_grace_resumes_without_user_left() {
+        let state = state_with_grace(200).await;
+        let mut other = state.insert_test_peer("other");
+        let (tx, mut first_rx) = tokio::sync::mpsc::unbounded_channel();
+        let peer =
+            handle_connection(state.clone(), tx, Some("device-1".to_string()), None, None).await;
+        let resume_token = connected_resume_token(&mut first_rx);
+        crate::handlers::handle_join_room(
+            state.clone(),
+            "other",
+            "room",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        crate::handlers::handle_join_room(
+            state.clone(),
+            &peer,
+            "room",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        drain_messages(&mut other);
+
+        handle_disconnect(state.clone(), &peer).await;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let resumed = handle_connection(state.clone(), tx, None, None, Some(resume_token)).await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        assert_eq!(resumed, peer);
+        assert!(matches!(
+            drain_messages(&mut rx).as_slice(),
+            [ServerMessage::Connected { socket_id, client_token, .. }]
+                if socket_id == &peer && client_token == "device-1"
+        ));
+        assert!(!has_user_left(&drain_messages(&mut other)));
+        let session = state.peers.get(&peer).expect("resumed session");
+        assert_eq!(session.room_id.read().await.as_deref(), Some("room"));
+        drop(session);
+        let room = state.rooms.get("room").expect("room kept");
+        assert!(room.users.read().await.contains(&peer));
+    }
+
+    fn connected_resume_token(
+        rx: &mut tokio::sync::mpsc::UnboundedReceiver<ServerMessage>,
+    ) -> String {
+        match drain_messages(rx).as_slice() {
+            [ServerMessage::Connected { resume_token, .. }] => resume_token.clone(),
+            other => panic!("unexpected messages: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn client_token_alone_does_not_resume_away_session() {
+        let state = state_with_grace(5_000).await;
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let peer =
+            handle_connection(state.clone(), tx, Some("device-1".to_string()), None, None).await;
+        crate::handlers::handle_join_room(
+            state.clone(),
+            &peer,
+            "room",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        handle_disconnect(state.clone(), &peer).await;
+
+        // 토큰만 아는 다른 클라이언트는 away 세션과 방을 가져가지 못한다
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let intruder = handle_connection(
+            state.clone(),
+            tx,
+            Some("device-1".to_string()),
+            None,
+            Some("guessed".to_string()),
+        )
+        .await;
+        assert_ne!(intruder, peer);
+        let session = state.peers.get(&intruder).unwrap();
+        assert!(session.room_id.read().await.is_none());
+        drop(session);
+        assert_eq!(state.away_peers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn disconnect_beyond_grace_fires_user_left() {
+        let state = state_with_grace(50).await;
+        let mut other = state.insert_test_peer("other");
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let peer =
+            handle_connection(state.clone(), tx, Some("device-1".to_string()), None, None).await;
+        crate::handlers::handle_join_room(
+            state.clone(),
+            "other",
+            "room",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        crate::handlers::handle_join_room(
+            state.clone(),
+            &peer,
+            "room",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        drain_messages(&mut other);
+
+        handle_disconnect(state.clone(), &peer).await;
+        assert!(!has_user_left(&drain_messages(&mut other)));
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(has_user_left(&drain_messages(&mut other)));
+        assert!(state.away_peers.is_empty());
+    }
+
+    async fn join_with_grace(state: &Arc<AppState>, reconnect_grace_ms: u64) -> String {
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let peer =
+            handle_connection(state.clone(), tx, Some("device-1".to_string()), None, None).await;
+        let options = crate::protocol::RoomOptions {
+            reconnect_grace_ms: Some(reconnect_grace_ms),
+            ..Default::default()
+        };
+        crate::handlers::handle_join_room(
+            state.clone(),
+            &peer,
+            "room",
+            PeerRole::Participant,
+            Some(options),
+        )
+        .await;
+        peer
+    }
+
+    #[tokio::test]
+    async fn room_grace_holds_member_when_global_grace_is_off() {
+        let state = state_with_grace(0).await;
+        let peer = join_with_grace(&state, 200).await;
+        let mut other = state.insert_test_peer("other");
+        crate::handlers::handle_join_room(
+            state.clone(),
+            "other",
+            "room",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        drain_messages(&mut other);
+
+        handle_disconnect(state.clone(), &peer).await;
+        assert!(!has_user_left(&drain_messages(&mut other)));
+        assert_eq!(state.away_peers.len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(has_user_left(&drain_messages(&mut other)));
+    }
+
+    #[tokio::test]
+    async fn room_without_grace_overrides_global_grace() {
+        let state = state_with_grace(5_000).await;
+        let peer = join_with_grace(&state, 0).await;
+        let mut other = state.insert_test_peer("other");
+        crate::handlers::handle_join_room(
+            state.clone(),
+            "other",
+            "room",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        drain_messages(&mut other);
+
+        handle_disconnect(state.clone(), &peer).await;
+
+        assert!(has_user_left(&drain_messages(&mut other)));
+        assert!(state.away_peers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn connected_reports_server_version() {
+        let state = Arc::new(AppState::new_for_test());
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        handle_connection(state, tx, None, None, None).await;
+
+        match drain_messages(&mut rx).as_slice() {
+            [ServerMessage::Connected { server_version, .. }] => {
+                assert_eq!(server_version, env!("CARGO_PKG_VERSION"));
+            }
+            other => panic!("unexpected messages: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn connected_carries_stable_instance_id() {
+        let mut config = crate::config::Config::from_env();
+        config.instance_id = String::new();
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        assert!(!state.instance_id.is_empty());
+
+        for _ in 0..2 {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            handle_connection(state.clone(), tx, None, None, None).await;
+            match drain_messages(&mut rx).as_slice() {
+                [ServerMessage::Connected { instance_id, .. }] => {
+                    assert_eq!(instance_id, &state.instance_id);
+                }
+                other => panic!("unexpected messages: {other:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn short_peer_ids_are_base32_and_unique() {
+        let mut config = crate::config::Config::from_env();
+        config.peer_id_format = PeerIdFormat::Short;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+
+        let mut ids = std::collections::HashSet::new();
+        for _ in 0..2_000 {
+            let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+            let id = handle_connection(state.clone(), tx, None, None, None).await;
+            assert_eq!(id.len(), 8);
+            assert!(id
+                .chars()
+                .all(|ch| ch.is_ascii_lowercase() || ('2'..='7').contains(&ch)));
+            assert!(ids.insert(id));
+        }
+        assert_eq!(state.peers.len(), 2_000);
+    }
+
+    #[tokio::test]
+    async fn whoami_reflects_current_session() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut rx = state.insert_test_peer("a");
+        crate::handlers::handle_join_room(state.clone(), "a", "room", PeerRole::Observer, None)
+            .await;
+        drain_messages(&mut rx);
+
+        handle_whoami(&state, "a").await;
+
+        match drain_messages(&mut rx).as_slice() {
+            [ServerMessage::Identity {
+                peer_id,
+                room_id,
+                role,
+            }] => {
+                assert_eq!(peer_id, "a");
+                assert_eq!(room_id.as_deref(), Some("room"));
+                assert_eq!(*role, PeerRole::Observer);
+            }
+            other => panic!("unexpected messages: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn capacity_rejection_carries_retry_hint() {
+        let mut config = crate::config::Config::from_env();
+        config.limits.max_connections = 1;
+        let state = AppState::new_for_test_with_config(config);
+        assert!(check_capacity(&state).is_ok());
+
+        let _a = state.insert_test_peer("a");
+        match check_capacity(&state).map_err(|rejection| *rejection) {
+            Err(ServerMessage::Error {
+                code,
+                retry_after_ms: Some(retry_after_ms),
+                ..
+            }) => {
+                assert_eq!(code, "server_at_capacity");
+                assert!(retry_after_ms > 0);
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn concurrent_capacity_checks_never_exceed_limit() {
+        let mut config = crate::config::Config::from_env();
+        config.limits.max_connections = 4;
+        config.limits.max_roomless_peers = 0;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+
+        // 등록 전에 동시에 들어온 연결도 한도만큼만 슬롯을 얻는다
+        let handles = (0..16)
+            .map(|_| {
+                let state = state.clone();
+                std::thread::spawn(move || check_capacity(&state).ok())
+            })
+            .collect::<Vec<_>>();
+        let slots = handles
+            .into_iter()
+            .filter_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(slots.len(), 4);
+        assert!(check_capacity(&state).is_err());
+
+        drop(slots);
+        assert_eq!(state.connection_slots.load(Ordering::Acquire), 0);
+        assert!(check_capacity(&state).is_ok());
+    }
+
+    #[tokio::test]
+    async fn roomless_peer_cap_releases_when_peers_join_or_leave() {
+        let mut config = crate::config::Config::from_env();
+        config.limits.max_roomless_peers = 2;
+        config.limits.roomless_grace_ms = 0;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let rejected = |state: &AppState| {
+            matches!(
+                check_capacity(state).map_err(|rejection| *rejection),
+                Err(ServerMessage::Error { code, .. }) if code == "roomless_peer_limit"
+            )
+        };
+        let _a = state.insert_test_peer("a");
+        assert!(check_capacity(&state).is_ok());
+        let _b = state.insert_test_peer("b");
+        assert!(rejected(&state));
+        assert!(state
+            .metrics
+            .render(0)
+            .contains("ponswarp_roomless_peers 2"));
+
+        crate::handlers::handle_join_room(state.clone(), "a", "room", PeerRole::Participant, None)
+            .await;
+        assert!(check_capacity(&state).is_ok());
+
+        let _c = state.insert_test_peer("c");
+        assert!(rejected(&state));
+        handle_disconnect(state.clone(), "c").await;
+        assert!(check_capacity(&state).is_ok());
+    }
+
+    #[tokio::test]
+    async fn peers_roomless_past_grace_are_disconnected() {
+        let mut config = crate::config::Config::from_env();
+        config.limits.max_roomless_peers = 10;
+        config.limits.roomless_grace_ms = 0;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut idle = state.insert_test_peer("idle");
+        let mut member = state.insert_test_peer("member");
+        crate::handlers::handle_join_room(
+            state.clone(),
+            "member",
+            "room",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        drain_messages(&mut member);
+        let closed = state.peers.get("idle").unwrap().close_signal.clone();
+
+        assert_eq!(disconnect_roomless_peers(&state), 1);
+
+        assert!(matches!(
+            drain_messages(&mut idle).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "roomless_timeout"
+        ));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), closed.notified())
+                .await
+                .is_ok()
+        );
+        assert!(drain_messages(&mut member).is_empty());
+    }
+
+    #[test]
+    fn rate_limited_error_carries_positive_retry_hint() {
+        let mut bucket = crate::rate_limit::TokenBucket::new(1, 1);
+        assert!(bucket.try_acquire().is_ok());
+        let retry_after_ms = bucket.try_acquire().expect_err("limited");
+
+        let value = serde_json::to_value(rate_limited_error(retry_after_ms)).expect("serialize");
+        assert_eq!(value["payload"]["code"], "rate_limited");
+        assert!(
+            value["payload"]["retry_after_ms"]
+                .as_u64()
+                .unwrap_or_default()
+                > 0
+        );
+    }
+
+    fn state_with_heartbeat_ack(heartbeat_ack: bool) -> AppState {
+        let mut config = crate::config::Config::from_env();
+        config.heartbeat_ack = heartbeat_ack;
+        AppState::new_for_test_with_config(config)
+    }
+
+    fn last_heartbeat_ms(state: &AppState, peer_id: &str) -> u64 {
+        let session = state.peers.get(peer_id).expect("peer exists");
+        session.stats.last_heartbeat_ms.load(Ordering::Relaxed)
+    }
+
+    #[tokio::test]
+    async fn suppressed_heartbeat_updates_liveness_without_ack() {
+        let state = state_with_heartbeat_ack(false);
+        let mut rx = state.insert_test_peer("a");
+        assert_eq!(last_heartbeat_ms(&state, "a"), 0);
+
+        handle_heartbeat(&state, "a").await;
+
+        assert!(last_heartbeat_ms(&state, "a") > 0);
+        assert!(drain_messages(&mut rx).is_empty());
+    }
+
+    #[tokio::test]
+    async fn heartbeat_is_acked_by_default() {
+        let state = state_with_heartbeat_ack(true);
+        let mut rx = state.insert_test_peer("a");
+
+        handle_heartbeat(&state, "a").await;
+
+        assert!(last_heartbeat_ms(&state, "a") > 0);
+        assert!(matches!(
+            drain_messages(&mut rx).as_slice(),
+            [ServerMessage::HeartbeatAck]
+        ));
+    }
+
+    #[tokio::test]
+    async fn configured_motd_is_sent_after_connected() {
+        let mut config = crate::config::Config::from_env();
+        config.motd = "Maintenance at 03:00 UTC".to_string();
+        config.motd_level = NoticeLevel::Warn;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        handle_connection(state, tx, None, None, None).await;
+
+        match drain_messages(&mut rx).as_slice() {
+            [ServerMessage::Connected { .. }, ServerMessage::Notice { text, level }] => {
+                assert_eq!(text, "Maintenance at 03:00 UTC");
+                assert_eq!(*level, NoticeLevel::Warn);
+            }
+            other => panic!("unexpected messages: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn no_notice_is_sent_without_motd() {
+        let mut config = crate::config::Config::from_env();
+        config.motd = String::new();
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        handle_connection(state, tx, None, None, None).await;
+
+        assert!(matches!(
+            drain_messages(&mut rx).as_slice(),
+            [ServerMessage::Connected { .. }]
+        ));
+    }
+
+    #[tokio::test]
+    async fn live_token_is_not_taken_over_by_a_new_connection() {
+        let state = Arc::new(AppState::new_for_test());
+        let token = || Some("device-1".to_string());
+        let (first_tx, _first_rx) = tokio::sync::mpsc::unbounded_channel();
+        let first = handle_connection(state.clone(), first_tx, token(), None, None).await;
+        let (second_tx, mut second_rx) = tokio::sync::mpsc::unbounded_channel();
+        let second = handle_connection(state.clone(), second_tx, token(), None, None).await;
+
+        // 연결 중인 피어의 토큰은 그대로 두고 새 연결에는 다른 토큰을 준다
+        assert_eq!(
+            state.resolve_peer_id("device-1").as_deref(),
+            Some(first.as_str())
+        );
+        assert!(matches!(
+            drain_messages(&mut second_rx).as_slice(),
+            [ServerMessage::Connected { client_token, .. }] if client_token != "device-1"
+        ));
+        assert_ne!(first, second);
+
+        // 이전 피어가 끊긴 뒤에는 같은 토큰으로 다시 접속할 수 있다
+        handle_disconnect(state.clone(), &first).await;
+        let (third_tx, _third_rx) = tokio::sync::mpsc::unbounded_channel();
+        let third = handle_connection(state.clone(), third_tx, token(), None, None).await;
+        assert_eq!(
+            state.resolve_peer_id("device-1").as_deref(),
+            Some(third.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_client_token_is_replaced_with_issued_token() {
+        let state = Arc::new(AppState::new_for_test());
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        handle_connection(
+            state.clone(),
+            tx,
+            Some("bad token!".to_string()),
+            None,
+            None,
+        )
+        .await;
+
+        match drain_messages(&mut rx).as_slice() {
+            [ServerMessage::Connected { client_token, .. }] => {
+                assert!(Uuid::parse_str(client_token).is_ok());
+            }
+            other => panic!("unexpected messages: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn disconnect_summary_reports_session_counters() {
+        let state = Arc::new(AppState::new_for_test());
+        let _rx = state.insert_test_peer("peer");
+        {
+            let session = state.peers.get("peer").expect("peer exists");
+            session.stats.record_received(10);
+            session.stats.record_received(5);
+            session.stats.record_sent(42);
+        }
+        set_room(&state, "peer", "room").await;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        let summary = handle_disconnect(state.clone(), "peer")
+            .await
+            .expect("summary for known peer");
+
+        assert_eq!(summary.peer_id, "peer");
+        assert_eq!(summary.room_id.as_deref(), Some("room"));
+        assert!(summary.duration_ms >= 5);
+        assert_eq!(summary.messages_received, 2);
+        assert_eq!(summary.bytes_received, 15);
+        assert_eq!(summary.messages_sent, 1);
+        assert_eq!(summary.bytes_sent, 42);
+        assert!(handle_disconnect(state, "peer").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn broadcast_queues_dead_recipients_for_cleanup() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+        drop(state.insert_test_peer("dead"));
+        for peer in ["a", "b", "dead"] {
+            crate::handlers::handle_join_room(
+                state.clone(),
+                peer,
+                "room",
+                PeerRole::Participant,
+                None,
+            )
+            .await;
+        }
+        drain_messages(&mut a);
+        drain_messages(&mut b);
+
+        crate::handlers::handle_offer(state.clone(), "a", "room", "v=0", None, None).await;
+
+        assert!(matches!(
+            drain_messages(&mut b).as_slice(),
+            [ServerMessage::Offer { .. }]
+        ));
+        assert!(state.dead_peers.contains("dead"));
+
+        assert_eq!(reap_dead_peers(&state).await, 1);
+        assert!(state.dead_peers.is_empty());
+        assert!(!state.peers.contains_key("dead"));
+        let room = state.rooms.get("room").expect("room exists");
+        assert!(!room.users.read().await.contains("dead"));
+        drop(room);
+        assert!(drain_messages(&mut b).iter().any(|message| matches!(
+            message,
+            ServerMessage::UserLeft { socket_id } if socket_id == "dead"
+        )));
+    }
+
+    #[tokio::test]
+    async fn reaper_ignores_queued_peers_that_are_still_alive() {
+        let state = Arc::new(AppState::new_for_test());
+        let _alive = state.insert_test_peer("alive");
+        enqueue_dead_peers(&state, vec!["alive".to_string()]);
+
+        assert_eq!(reap_dead_peers(&state).await, 0);
+        assert!(state.peers.contains_key("alive"));
+    }
+
+    #[tokio::test]
+    async fn peer_status_reveals_room_when_sharing_room() {
+        let state = AppState::new_for_test();
+        let mut requester = state.insert_test_peer("requester");
+        let _target = state.insert_test_peer("target");
+        set_room(&state, "requester", "room").await;
+        set_room(&state, "target", "room").await;
+
+        handle_peer_status_query(&state, "requester", "target").await;
+
+        assert_eq!(
+            peer_status(drain_messages(&mut requester)),
+            (true, Some("room".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn peer_status_hides_room_of_peer_in_other_room() {
+        let state = AppState::new_for_test();
+        let mut requester = state.insert_test_peer("requester");
+        let _target = state.insert_test_peer("target");
+        set_room(&state, "requester", "room-a").await;
+        set_room(&state, "target", "room-b").await;
+
+        handle_peer_status_query(&state, "requester", "target").await;
+
+        assert_eq!(peer_status(drain_messages(&mut requester)), (true, None));
+    }
+
+    #[tokio::test]
+    async fn peer_status_reports_offline_peer() {
+        let state = AppState::new_for_test();
+        let mut requester = state.insert_test_peer("requester");
+
+        handle_peer_status_query(&state, "requester", "missing").await;
+
+        assert_eq!(peer_status(drain_messages(&mut requester)), (false, None));
+    }
 }
@@ -1,70 +1,379 @@
 //! 방 관리 핸들러
 
-use crate::protocol::ServerMessage;
-use crate::state::{AppState, Room};
+use crate::admin::AdminEvent;
+use crate::cluster;
+use crate::config::DuplicateJoinPolicy;
+use crate::handlers::{send_error, send_request_error, RequestContext};
+use crate::protocol::{
+    PeerRole, PublicRoomInfo, RoomEvent, RoomOptions, RoomSnapshotData, ServerMessage,
+};
+use crate::state::{AppState, PeerSession, Room, CAP_ROOM_SNAPSHOT};
+use dashmap::mapref::entry::Entry;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
 
-/// 방 참여 처리
-pub async fn handle_join_room(state: Arc<AppState>, peer_id: &str, room_id: &str) {
+/// correlation_id 없이 입장한다 (테스트용)
+#[cfg(test)]
+pub async fn handle_join_room(
+    state: Arc<AppState>,
+    peer_id: &str,
+    room_id: &str,
+    role: PeerRole,
+    options: Option<RoomOptions>,
+) {
+    let context = RequestContext::default();
+    handle_join_room_with(state, peer_id, room_id, role, options, &context).await;
+}
+
+/// 방 참여 처리. `options`는 이 입장으로 방이 만들어질 때만 적용되고, 만든 피어가 owner가 된다.
+///
+/// 메시지 순서는 항상 같다. 입장한 피어는 `RoomUsers`(자신 제외) → `JoinedRoom` →
+/// (`RoomHistory`) → `RoomUsersDelta`, 기존 참여자는 `PeerJoined` → `RoomUsersDelta`를 받는다.
+/// `room_snapshot` 확장을 켠 피어는 `RoomUsers` + `JoinedRoom` 대신 `RoomSnapshot`을 받는다.
+/// 입장한 피어는 자기 자신에 대한 `PeerJoined`를 받지 않는다.
+/// 요청의 `correlation_id`는 `JoinedRoom`/`RoomSnapshot`과 거절 응답(`Error`, `RoomFull`)에 그대로 돌려준다.
+pub async fn handle_join_room_with(
+    state: Arc<AppState>,
+    peer_id: &str,
+    room_id: &str,
+    role: PeerRole,
+    options: Option<RoomOptions>,
+    context: &RequestContext,
+) {
     let room_id = room_id.trim().to_string();
     let max_size = state.config.room.max_size;
+    let history_size = state.config.room.history_size;
+    let max_per_ip = state.config.room.max_per_ip;
+
+    if state.config.room.strict_ids && !is_printable_room_id(&room_id) {
+        tracing::warn!(peer_id = %peer_id, room_id = ?room_id, "Rejected room id");
+        send_request_error(
+            &state,
+            peer_id,
+            context,
+            "invalid_room_id",
+            "Room id contains control or invisible characters",
+        );
+        return;
+    }
 
     tracing::info!(peer_id = %peer_id, room_id = %room_id, "handle_join_room started");
 
+    // 입장/퇴장을 빠르게 반복하면 방 알림과 생성/삭제가 몰리므로 잠시 받지 않는다
+    if let Some(retry_after_ms) = transition_too_soon(&state, peer_id, &room_id).await {
+        if let Some(session) = state.peers.get(peer_id) {
+            let _ = session.sender.send(ServerMessage::Error {
+                code: "room_transition_too_fast".to_string(),
+                message: "Joining and leaving rooms too quickly, retry shortly".to_string(),
+                retry_after_ms: Some(retry_after_ms),
+                correlation_id: context.correlation_id.clone(),
+            });
+        }
+        tracing::debug!(peer_id = %peer_id, room_id = %room_id, "Join debounced after recent transition");
+        return;
+    }
+
+    let (owner_token, joiner_ip) = match state.peers.get(peer_id) {
+        Some(session) => (Some(session.client_token.clone()), session.remote_ip),
+        None => (None, None),
+    };
+    // MAX_ROOMS_PER_OWNER는 클라이언트가 고를 수 없는 접속 IP로 센다
+    let owner_ip = joiner_ip.filter(|_| state.config.room.max_rooms_per_owner > 0);
+
+    // 다른 방에 있던 피어는 새 방에 들어간 뒤 이전 방에서 나간다
+    let previous_room = match state.peers.get(peer_id) {
+        Some(session) => session
+            .room_id
+            .read()
+            .await
+            .clone()
+            .filter(|current| current != &room_id),
+        None => None,
+    };
+    let moving = match previous_room
+        .as_deref()
+        .and_then(|prev| state.rooms.get(prev))
+    {
+        Some(room) => room.users.read().await.contains(peer_id),
+        None => false,
+    };
+
+    // 전체 멤버 수 상한 (MAX_TOTAL_USERS). 거부된 입장이 빈 방을 남기지 않도록 방을 만들기 전에
+    // 자리를 잡고, 입장하지 못하면 돌려준다. 재입장은 자리를 새로 차지하지 않고,
+    // 방을 옮기는 피어는 이전 방에서 나가며 돌려줄 자리를 미리 넘겨받는다
+    let rejoining = match state.rooms.get(&room_id) {
+        Some(room) => room.users.read().await.contains(peer_id),
+        None => false,
+    };
+    if moving && !rejoining {
+        state.room_users.fetch_add(1, Ordering::AcqRel);
+    } else if !rejoining && !state.reserve_room_user() {
+        send_request_error(
+            &state,
+            peer_id,
+            context,
+            "global_user_cap",
+            "Server has reached its total user limit",
+        );
+        tracing::warn!(room_id = %room_id, "Global user cap reached, rejected join");
+        return;
+    }
+    let release_slot = || {
+        if !rejoining {
+            state.release_room_users(1);
+        }
+    };
+
     // 방 가져오기 또는 생성 및 로직 처리 (스코프 제한으로 Deadlock 방지)
-    let updated_users = {
+    let (newly_added, user_count, evicted) = {
         tracing::info!(room_id = %room_id, "Acquiring room lock...");
-        let room = state.rooms.entry(room_id.clone()).or_insert_with(|| {
-            tracing::info!(room_id = %room_id, "Room created");
-            Room::new(room_id.clone())
-        });
+        let mut created = false;
+        let mut room = match state.rooms.entry(room_id.clone()) {
+            Entry::Occupied(entry) => entry.into_ref(),
+            Entry::Vacant(entry) => {
+                // 방을 만드는 입장만 owner별 방 수를 센다. entry lock 안에서 세어 동시 생성도 막는다
+                if !state.claim_owner_room(owner_ip) {
+                    drop(entry);
+                    send_request_error(
+                        &state,
+                        peer_id,
+                        context,
+                        "owner_room_limit",
+                        "Too many rooms created from this address",
+                    );
+                    tracing::warn!(peer_id = %peer_id, room_id = %room_id, "Owner room limit reached, rejected join");
+                    release_slot();
+                    return;
+                }
+                tracing::info!(room_id = %room_id, owner = %peer_id, "Room created");
+                created = true;
+                entry.insert(Room {
+                    owner: Some(peer_id.to_string()),
+                    owner_token: owner_token.clone(),
+                    owner_ip,
+                    options: options.unwrap_or_default(),
+                    ..Room::with_config(room_id.clone(), &state.config.room)
+                })
+            }
+        };
         tracing::info!(room_id = %room_id, "Room lock acquired");
+        // drain 중인 방은 기존 멤버의 재입장만 받는다
+        if room.draining.load(Ordering::Acquire) && !room.users.read().await.contains(peer_id) {
+            send_request_error(
+                &state,
+                peer_id,
+                context,
+                "room_draining",
+                "Room is being drained for maintenance",
+            );
+            release_slot();
+            return;
+        }
+        if created {
+            state.emit_admin_event(AdminEvent::RoomCreated {
+                room_id: room_id.clone(),
+            });
+            let creator_ip = state
+                .peers
+                .get(peer_id)
+                .and_then(|session| session.remote_ip);
+            state.metrics.rooms.record_created(
+                creator_ip,
+                state.config.room.create_warn_per_min,
+                state.config.room.create_warn_per_ip_per_min,
+            );
+        }
 
         // 방 인원 제한 확인 (이미 방에 있는 유저가 재접속하는 경우는 허용)
-        {
+        // Observer는 인원에 포함되지 않으므로 제한 없이 입장한다.
+        // `evict_oldest_on_full` 방은 거부하는 대신 내보낼 참여자를 고르고, 입장이 확정된 뒤 내보낸다.
+        let mut victim = None;
+        if role == PeerRole::Participant {
             let users = room.users.read().await;
-            // !users.contains(peer_id) 조건을 통해,
-            // 이미 방 목록에 내 ID가 있다면(재접속 등) RoomFull을 띄우지 않음
-            if users.len() >= max_size && !users.contains(peer_id) {
+            // 이미 방 목록에 내 ID가 있다면(재접속 등) RoomFull을 띄우지 않음.
+            // 단, 관전자가 참여자로 다시 입장하면 새로 자리를 차지하므로 인원에 센다
+            let takes_seat = !users.contains(peer_id)
+                || match state.peers.get(peer_id) {
+                    Some(session) => *session.role.read().await == PeerRole::Observer,
+                    None => false,
+                };
+            let full = takes_seat && participant_count(&state, &users).await >= max_size;
+            if full && room.options.evict_oldest_on_full {
+                victim = idle_victim(&state, &room, &users).await;
+            }
+            if full && victim.is_none() {
                 if let Some(session) = state.peers.get(peer_id) {
                     let _ = session.sender.send(ServerMessage::RoomFull {
                         room_id: room_id.clone(),
+                        correlation_id: context.correlation_id.clone(),
                     });
                 }
                 tracing::warn!(room_id = %room_id, "Room full, rejected join");
+                release_slot();
                 return;
             }
         }
 
-        // 기존 사용자 목록
-        let existing_users: Vec<String> = room.users.read().await.iter().cloned().collect();
+        // 같은 IP의 방 참여 인원 제한 (재접속은 허용)
+        if max_per_ip > 0 {
+            let joiner_ip = state
+                .peers
+                .get(peer_id)
+                .and_then(|session| session.remote_ip);
+            if let Some(ip) = joiner_ip {
+                let users = room.users.read().await;
+                let same_ip = users
+                    .iter()
+                    .filter(|user| user.as_str() != peer_id)
+                    .filter(|user| {
+                        state
+                            .peers
+                            .get(user.as_str())
+                            .is_some_and(|session| session.remote_ip == Some(ip))
+                    })
+                    .count();
+                if same_ip >= max_per_ip && !users.contains(peer_id) {
+                    send_request_error(
+                        &state,
+                        peer_id,
+                        context,
+                        "ip_limit",
+                        "Too many connections from this address in the room",
+                    );
+                    tracing::warn!(room_id = %room_id, ip = %ip, "Per-IP room limit reached, rejected join");
+                    release_slot();
+                    return;
+                }
+            }
+        }
+
+        // 자리가 확정되었으니 가장 오래 조용했던 참여자를 내보낸다. 알림은 lock을 놓은 뒤 보낸다
+        if let Some(victim) = &victim {
+            room.users.write().await.remove(victim);
+            state.release_room_users(1);
+            room.record_event(
+                RoomEvent::UserLeft {
+                    socket_id: victim.clone(),
+                    timestamp: unix_now(),
+                },
+                history_size,
+            )
+            .await;
+        }
+
+        // 예약된 방은 처음 들어온 Participant가 owner가 된다. 보존 기한이 지나면 일반 방처럼 정리된다
+        if room.reserved_until.is_some() && room.owner.is_none() && role == PeerRole::Participant {
+            room.owner = Some(peer_id.to_string());
+            room.owner_token = owner_token.clone();
+            room.touch();
+            created = true;
+            tracing::info!(room_id = %room_id, owner = %peer_id, "Reserved room claimed");
+        }
+
+        // 방을 만든 클라이언트가 재시작 뒤 같은 client_token으로 돌아오면 owner를 되찾는다
+        if owner_token.is_some()
+            && room.owner_token == owner_token
+            && room.owner.as_deref() != Some(peer_id)
+        {
+            room.owner = Some(peer_id.to_string());
+            tracing::info!(room_id = %room_id, owner = %peer_id, "Room owner reclaimed");
+        }
+
+        // 기존 사용자 목록 (재입장이면 자기 자신은 뺀다)
+        let existing_users: Vec<String> = room
+            .users
+            .read()
+            .await
+            .iter()
+            .filter(|user| user.as_str() != peer_id)
+            .cloned()
+            .collect();
         tracing::info!(room_id = %room_id, existing_users = ?existing_users, "Got existing users");
 
         // 방에 참여
-        room.users.write().await.insert(peer_id.to_string());
+        let newly_added = room.users.write().await.insert(peer_id.to_string());
+        if !newly_added {
+            // 자리를 잡은 사이 다른 연결 경로로 이미 들어와 있었다
+            release_slot();
+        }
         tracing::info!(room_id = %room_id, peer_id = %peer_id, "User inserted into room");
 
         // 피어 세션 업데이트
         if let Some(session) = state.peers.get(peer_id) {
-            *session.room_id.write().await = Some(room_id.clone());
+            let mut current = session.room_id.write().await;
+            state.track_roomless(current.is_none(), false);
+            *current = Some(room_id.clone());
+            drop(current);
+            *session.role.write().await = role;
         }
 
         let user_count = room.users.read().await.len();
+        // 이미 멤버인 피어의 JoinRoom (DUPLICATE_JOIN_POLICY)
+        let duplicate_join = state.config.room.duplicate_join;
+        let notify_members = newly_added || duplicate_join == DuplicateJoinPolicy::Notify;
+        let reply_to_joiner = newly_added || duplicate_join != DuplicateJoinPolicy::Ignore;
 
-        // 새 사용자에게 기존 사용자 목록 전송
-        if let Some(session) = state.peers.get(peer_id) {
-            let _ = session.sender.send(ServerMessage::RoomUsers {
-                users: existing_users.clone(),
-            });
-            let _ = session.sender.send(ServerMessage::JoinedRoom {
-                room_id: room_id.clone(),
-                socket_id: peer_id.to_string(),
-                user_count,
-            });
+        // 새 사용자에게 기존 사용자 목록 전송 (room_snapshot 확장이면 한 메시지로)
+        if let Some(session) = state.peers.get(peer_id).filter(|_| reply_to_joiner) {
+            if session.capabilities.has(CAP_ROOM_SNAPSHOT) {
+                let snapshot = RoomSnapshotData {
+                    room_id: room_id.clone(),
+                    you: peer_id.to_string(),
+                    users: room.users.read().await.iter().cloned().collect(),
+                    owner: room.owner.clone(),
+                    metadata: room.metadata.read().await.clone(),
+                    name: room.name.read().await.clone(),
+                    user_count,
+                    max_size,
+                    policy: room.options.policy(),
+                    created,
+                    correlation_id: context.correlation_id.clone(),
+                };
+                let _ = session
+                    .sender
+                    .send(ServerMessage::RoomSnapshot(Box::new(snapshot)));
+            } else {
+                let _ = session.sender.send(ServerMessage::RoomUsers {
+                    users: existing_users.clone(),
+                    correlation_id: None,
+                });
+                let _ = session.sender.send(ServerMessage::JoinedRoom {
+                    room_id: room_id.clone(),
+                    socket_id: peer_id.to_string(),
+                    user_count,
+                    policy: room.options.policy(),
+                    metadata: room.metadata.read().await.clone(),
+                    name: room.name.read().await.clone(),
+                    created,
+                    correlation_id: context.correlation_id.clone(),
+                });
+            }
+            if history_size > 0 {
+                let events = room.history.read().await.iter().cloned().collect();
+                let _ = session.sender.send(ServerMessage::RoomHistory { events });
+            }
             tracing::info!(peer_id = %peer_id, "Sent JoinedRoom to new user");
         }
 
+        if !notify_members {
+            tracing::debug!(room_id = %room_id, peer_id = %peer_id, "Duplicate join, members not notified");
+            return;
+        }
+
+        room.record_event(
+            RoomEvent::PeerJoined {
+                socket_id: peer_id.to_string(),
+                timestamp: unix_now(),
+            },
+            history_size,
+        )
+        .await;
+
         // 기존 사용자들에게 새 사용자 알림
         for existing_peer_id in &existing_users {
             if let Some(session) = state.peers.get(existing_peer_id) {
@@ -76,58 +385,197 @@ pub async fn handle_join_room(state: Arc<AppState>, peer_id: &str, room_id: &str
             }
         }
 
-        // 업데이트된 사용자 목록 반환
-        let users_list = room
-            .users
-            .read()
-            .await
-            .iter()
-            .cloned()
-            .collect::<Vec<String>>();
-        users_list
+        (newly_added, user_count, victim)
     }; // 여기서 room (DashMap RefMut)이 드롭되어 락이 해제됨
 
-    tracing::info!(room_id = %room_id, "Room lock released, broadcasting RoomUsers");
+    if let Some(victim) = &evicted {
+        finish_eviction(&state, &room_id, victim, peer_id).await;
+    }
 
-    let user_count = updated_users.len();
+    if let Some(previous_room) = &previous_room {
+        if leave_room_internal(&state, peer_id, previous_room).await {
+            if let Some(session) = state.peers.get(peer_id) {
+                record_room_leave(&session, previous_room);
+            }
+        }
+    }
 
-    // 모든 사용자에게 업데이트된 목록 브로드캐스트 (락 해제 후 호출)
-    broadcast_to_room(
+    tracing::info!(room_id = %room_id, "Room lock released, broadcasting RoomUsersDelta");
+
+    cluster::update_presence(&state, &room_id, peer_id, true).await;
+    cluster::publish_room(
         &state,
         &room_id,
-        ServerMessage::RoomUsers {
-            users: updated_users,
+        Some(peer_id),
+        ServerMessage::PeerJoined {
+            socket_id: peer_id.to_string(),
+            room_id: room_id.clone(),
         },
     )
     .await;
 
+    // 모든 사용자에게 변경분 브로드캐스트 (락 해제 후 호출). 재입장은 목록이 그대로다.
+    if newly_added {
+        state.emit_admin_event(AdminEvent::PeerJoined {
+            room_id: room_id.clone(),
+            peer_id: peer_id.to_string(),
+        });
+        broadcast_to_room(
+            &state,
+            &room_id,
+            ServerMessage::RoomUsersDelta {
+                added: vec![peer_id.to_string()],
+                removed: Vec::new(),
+            },
+        )
+        .await;
+    }
+
     tracing::info!(room_id = %room_id, "handle_join_room completed");
 
     tracing::info!(
         peer_id = %peer_id,
         room_id = %room_id,
         user_count = user_count,
+        role = ?role,
         "User joined room"
     );
 }
 
-/// 방 나가기 내부 로직
-pub async fn leave_room_internal(state: &AppState, peer_id: &str, room_id: &str) {
+/// 방 인원 제한에 포함되는 참여자 수 (Observer 제외)
+async fn participant_count(state: &AppState, users: &HashSet<String>) -> usize {
+    let mut count = 0;
+    for user in users {
+        let is_observer = match state.peers.get(user) {
+            Some(session) => *session.role.read().await == PeerRole::Observer,
+            None => false,
+        };
+        if !is_observer {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// `evict_oldest_on_full` 방에서 내보낼 참여자. 가장 오래 활동(Heartbeat, 없으면 접속)이 없던
+/// 참여자를 고르며, Observer와 owner는 고르지 않는다.
+async fn idle_victim(state: &AppState, room: &Room, users: &HashSet<String>) -> Option<String> {
+    let mut victim: Option<(Duration, String)> = None;
+    for user in users {
+        if room.owner.as_deref() == Some(user.as_str()) {
+            continue;
+        }
+        let Some(session) = state.peers.get(user) else {
+            continue;
+        };
+        if *session.role.read().await == PeerRole::Observer {
+            continue;
+        }
+        let idle = idle_since_last_activity(&session);
+        if victim.as_ref().is_none_or(|(oldest, _)| idle > *oldest) {
+            victim = Some((idle, user.clone()));
+        }
+    }
+    victim.map(|(_, peer_id)| peer_id)
+}
+
+/// 입장 처리 중 방에서 뺀 참여자의 퇴장을 알리고 `Evicted`를 보낸다. 방 lock을 놓은 뒤 호출한다.
+async fn finish_eviction(state: &AppState, room_id: &str, victim: &str, joiner: &str) {
+    cluster::update_presence(state, room_id, victim, false).await;
+    state.emit_admin_event(AdminEvent::PeerLeft {
+        room_id: room_id.to_string(),
+        peer_id: victim.to_string(),
+    });
+    if let Some(session) = state.peers.get(victim) {
+        let mut current = session.room_id.write().await;
+        if current.as_deref() == Some(room_id) {
+            *current = None;
+            state.track_roomless(false, true);
+        }
+        drop(current);
+        let _ = session.sender.send(ServerMessage::Evicted {
+            room_id: room_id.to_string(),
+        });
+    }
+    broadcast_to_room(
+        state,
+        room_id,
+        ServerMessage::UserLeft {
+            socket_id: victim.to_string(),
+        },
+    )
+    .await;
+    broadcast_to_room(
+        state,
+        room_id,
+        ServerMessage::RoomUsersDelta {
+            added: Vec::new(),
+            removed: vec![victim.to_string()],
+        },
+    )
+    .await;
+    tracing::info!(room_id = %room_id, evicted = %victim, joiner = %joiner, "Evicted idle member from full room");
+}
+
+/// 마지막 Heartbeat(없으면 접속) 이후 경과 시간
+fn idle_since_last_activity(session: &PeerSession) -> Duration {
+    let connected = session.connected_at.elapsed();
+    match session.stats.last_heartbeat_ms.load(Ordering::Relaxed) {
+        0 => connected,
+        heartbeat_ms => {
+            let now_ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+            Duration::from_millis(now_ms.saturating_sub(heartbeat_ms)).min(connected)
+        }
+    }
+}
+
+/// 방 나가기 내부 로직. 실제로 방에서 제거되었으면 true를 반환한다.
+pub async fn leave_room_internal(state: &AppState, peer_id: &str, room_id: &str) -> bool {
     // DashMap room guard를 잡은 상태에서 await/broadcast_to_room을 호출하면
     // broadcast_to_room이 같은 DashMap shard를 다시 조회하면서 런타임 전체가
     // 멈출 수 있다. 먼저 필요한 상태만 복사하고 guard를 명시적으로 drop한 뒤
     // 네트워크/채널 작업을 수행한다.
-    let Some((remaining, updated_users, should_delete)) = (if let Some(room) = state.rooms.get(room_id) {
-        room.users.write().await.remove(peer_id);
-        let updated_users: Vec<String> = room.users.read().await.iter().cloned().collect();
-        let remaining = updated_users.len();
-        Some((remaining, updated_users, remaining == 0))
+    let Some((remaining, should_delete, closing)) = (if let Some(room) = state.rooms.get(room_id) {
+        if !room.users.write().await.remove(peer_id) {
+            return false;
+        }
+        state.release_room_users(1);
+        let closing = room.options.close_on_owner_leave && room.owner.as_deref() == Some(peer_id);
+        room.record_event(
+            RoomEvent::UserLeft {
+                socket_id: peer_id.to_string(),
+                timestamp: unix_now(),
+            },
+            state.config.room.history_size,
+        )
+        .await;
+        let remaining = room.users.read().await.len();
+        if remaining == 0 {
+            // 빈 방 유예(EMPTY_ROOM_LINGER_MS)는 마지막 퇴장 시각부터 센다
+            room.touch();
+        }
+        Some((remaining, remaining == 0 && !room.is_reserved(), closing))
     } else {
         None
     }) else {
-        return;
+        return false;
     };
 
+    cluster::update_presence(state, room_id, peer_id, false).await;
+    state.emit_admin_event(AdminEvent::PeerLeft {
+        room_id: room_id.to_string(),
+        peer_id: peer_id.to_string(),
+    });
+
+    if closing && remaining > 0 {
+        tracing::info!(peer_id = %peer_id, room_id = %room_id, "Owner left, closing room");
+        close_room(state, room_id, "owner_left").await;
+        return true;
+    }
+
     // 다른 사용자들에게 알림 (room guard 해제 후)
     broadcast_to_room(
         state,
@@ -142,8 +590,9 @@ pub async fn leave_room_internal(state: &AppState, peer_id: &str, room_id: &str)
         broadcast_to_room(
             state,
             room_id,
-            ServerMessage::RoomUsers {
-                users: updated_users,
+            ServerMessage::RoomUsersDelta {
+                added: Vec::new(),
+                removed: vec![peer_id.to_string()],
             },
         )
         .await;
@@ -156,58 +605,2086 @@ pub async fn leave_room_internal(state: &AppState, peer_id: &str, room_id: &str)
         "User left room"
     );
 
-    if should_delete {
-        state.rooms.remove(room_id);
+    if should_delete && state.config.room.empty_room_linger_ms == 0 {
+        if let Some((_, room)) = state.rooms.remove(room_id) {
+            state.release_owner_room(&room);
+        }
+        state.emit_admin_event(AdminEvent::RoomDeleted {
+            room_id: room_id.to_string(),
+        });
         tracing::info!(room_id = %room_id, "Room deleted");
     }
+    true
+}
+
+/// 방금 나간 방에 ROOM_TRANSITION_DEBOUNCE_MS 안에 다시 들어오려 하면 남은 시간(ms)을 돌려준다.
+/// 다른 방으로 옮기는 입장은 막지 않는다.
+async fn transition_too_soon(state: &AppState, peer_id: &str, room_id: &str) -> Option<u64> {
+    let window = Duration::from_millis(state.config.room.transition_debounce_ms);
+    if window.is_zero() {
+        return None;
+    }
+    let session = state.peers.get(peer_id)?;
+    let last_leave = session.last_room_leave.lock().unwrap();
+    let (left_room, left_at) = last_leave.as_ref()?;
+    if left_room != room_id {
+        return None;
+    }
+    let remaining = window.checked_sub(left_at.elapsed())?;
+    Some((remaining.as_millis() as u64).max(1))
+}
+
+fn record_room_leave(session: &PeerSession, room_id: &str) {
+    *session.last_room_leave.lock().unwrap() = Some((room_id.to_string(), Instant::now()));
 }
 
 /// 방 나가기 처리
-pub async fn handle_leave_room(state: Arc<AppState>, peer_id: &str) {
-    let room_id = if let Some(session) = state.peers.get(peer_id) {
-        session.room_id.read().await.clone()
-    } else {
-        None
+///
+/// `room_id`가 없으면 현재 방을 나간다. 해당 방에 없으면 `not_in_room` 에러로 응답하며,
+/// 여러 번 호출해도 상태가 바뀌지 않는다.
+pub async fn handle_leave_room(state: Arc<AppState>, peer_id: &str, room_id: Option<&str>) {
+    let current_room = match state.peers.get(peer_id) {
+        Some(session) => session.room_id.read().await.clone(),
+        None => return,
+    };
+    let Some(room_id) = room_id
+        .map(|room_id| room_id.trim().to_string())
+        .or(current_room.clone())
+    else {
+        send_error(&state, peer_id, "not_in_room", "Not in any room");
+        return;
     };
 
-    if let Some(room_id) = room_id {
-        leave_room_internal(&state, peer_id, &room_id).await;
-        if let Some(session) = state.peers.get(peer_id) {
-            *session.room_id.write().await = None;
+    let left = leave_room_internal(&state, peer_id, &room_id).await;
+    if let Some(session) = state.peers.get(peer_id) {
+        // 세션의 room_id가 가리키던 방이면 (stale 포함) 비운다.
+        if current_room.as_deref() == Some(room_id.as_str()) {
+            let mut current = session.room_id.write().await;
+            state.track_roomless(current.is_none(), true);
+            *current = None;
+        }
+        if left {
+            record_room_leave(&session, &room_id);
+        }
+    }
+    if !left {
+        send_error(&state, peer_id, "not_in_room", "Not a member of this room");
+        tracing::debug!(peer_id = %peer_id, room_id = %room_id, "Leave ignored, not in room");
+    }
+}
+
+/// 공개 방 목록 조회. 연결마다 PUBLIC_ROOMS_RATE로 제한한다.
+pub async fn handle_list_public_rooms(state: Arc<AppState>, peer_id: &str) {
+    let Some(session) = state.peers.get(peer_id) else {
+        return;
+    };
+    let limited = session.room_list_limiter.as_ref().and_then(|limiter| {
+        limiter
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .try_acquire()
+            .err()
+    });
+    if let Some(retry_after_ms) = limited {
+        let _ = session
+            .sender
+            .send(crate::handlers::rate_limited_error(retry_after_ms));
+        return;
+    }
+    let sender = session.sender.clone();
+    drop(session);
+
+    // DashMap 순회 중에는 await하지 않도록 id만 먼저 모은다.
+    let public: Vec<String> = state
+        .rooms
+        .iter()
+        .filter(|room| room.options.public)
+        .map(|room| room.id.clone())
+        .collect();
+    let mut rooms = Vec::with_capacity(public.len());
+    for room_id in public {
+        let Some(room) = state.rooms.get(&room_id) else {
+            continue;
+        };
+        let users = room.users.read().await;
+        rooms.push(PublicRoomInfo {
+            id: room_id,
+            user_count: participant_count(&state, &users).await,
+            max_size: state.config.room.max_size,
+        });
+    }
+    rooms.sort_by(|a, b| a.id.cmp(&b.id));
+    let _ = sender.send(ServerMessage::PublicRooms { rooms });
+}
+
+/// 참여 중인 방의 전체 RoomUsers를 다시 보낸다.
+pub async fn handle_resync_room(
+    state: Arc<AppState>,
+    peer_id: &str,
+    room_id: &str,
+    context: &RequestContext,
+) {
+    let room_id = room_id.trim();
+    let users = match state.rooms.get(room_id) {
+        Some(room) => {
+            // 입장 때의 RoomUsers와 같이 자기 자신은 뺀다
+            let users = room.users.read().await;
+            users.contains(peer_id).then(|| {
+                users
+                    .iter()
+                    .filter(|user| user.as_str() != peer_id)
+                    .cloned()
+                    .collect::<Vec<String>>()
+            })
+        }
+        None => None,
+    };
+    let Some(users) = users else {
+        let message = "Not a member of this room";
+        send_request_error(&state, peer_id, context, "not_in_room", message);
+        return;
+    };
+    if let Some(session) = state.peers.get(peer_id) {
+        let _ = session.sender.send(ServerMessage::RoomUsers {
+            users,
+            correlation_id: context.correlation_id.clone(),
+        });
+    }
+}
+
+/// 방 owner 조회. 멤버가 아니면 `not_in_room` 에러.
+pub async fn handle_get_room_owner(state: Arc<AppState>, peer_id: &str, room_id: &str) {
+    let room_id = room_id.trim();
+    let owner = match state.rooms.get(room_id) {
+        Some(room) if room.users.read().await.contains(peer_id) => Some(room.owner.clone()),
+        _ => None,
+    };
+    let Some(owner) = owner else {
+        send_error(&state, peer_id, "not_in_room", "Not a member of this room");
+        return;
+    };
+    if let Some(session) = state.peers.get(peer_id) {
+        let _ = session.sender.send(ServerMessage::RoomOwner {
+            room_id: room_id.to_string(),
+            owner,
+        });
+    }
+}
+
+/// 방 metadata 설정. owner만 바꿀 수 있고, 바뀐 값은 owner를 포함한 방 전체에 전달한다.
+pub async fn handle_set_room_metadata(
+    state: Arc<AppState>,
+    peer_id: &str,
+    room_id: &str,
+    metadata: serde_json::Value,
+) {
+    let room_id = room_id.trim();
+    let size = serde_json::to_vec(&metadata).map_or(usize::MAX, |bytes| bytes.len());
+    if size > state.config.room.metadata_max_bytes {
+        send_error(
+            &state,
+            peer_id,
+            "metadata_too_large",
+            "Room metadata exceeds the size limit",
+        );
+        return;
+    }
+
+    {
+        let Some(room) = owned_room(&state, peer_id, room_id).await else {
+            return;
+        };
+        *room.metadata.write().await = Some(metadata.clone());
+    }
+
+    tracing::debug!(room_id = %room_id, peer_id = %peer_id, size, "Room metadata updated");
+    broadcast_to_room(
+        &state,
+        room_id,
+        ServerMessage::RoomMetadataUpdated { metadata },
+    )
+    .await;
+}
+
+/// 방 표시 이름 최대 길이 (문자 수)
+const MAX_ROOM_NAME_CHARS: usize = 64;
+
+/// 제어 문자(NUL 포함)와 화면에 보이지 않는 서식 문자(방향 재정의, 폭 없는 문자, BOM)가 없는 id만 허용
+pub(crate) fn is_printable_room_id(room_id: &str) -> bool {
+    room_id.chars().all(|ch| {
+        let invisible = matches!(
+            ch,
+            '\u{200B}'..='\u{200F}'
+                | '\u{202A}'..='\u{202E}'
+                | '\u{2060}'..='\u{2069}'
+                | '\u{FEFF}'
+        );
+        !ch.is_control() && !invisible
+    })
+}
+
+/// 방 표시 이름 변경. 제어 문자를 지우고 앞뒤 공백을 자른 뒤 저장하며, 빈 이름은 이름을 지운다.
+pub async fn handle_set_room_name(state: Arc<AppState>, peer_id: &str, room_id: &str, name: &str) {
+    let room_id = room_id.trim();
+    let name: String = name.chars().filter(|ch| !ch.is_control()).collect();
+    let name = name.trim();
+    if name.chars().count() > MAX_ROOM_NAME_CHARS {
+        send_error(
+            &state,
+            peer_id,
+            "room_name_too_long",
+            "Room name exceeds the length limit",
+        );
+        return;
+    }
+    let name = (!name.is_empty()).then(|| name.to_string());
+
+    {
+        let Some(room) = owned_room(&state, peer_id, room_id).await else {
+            return;
+        };
+        *room.name.write().await = name.clone();
+    }
+
+    broadcast_to_room(&state, room_id, ServerMessage::RoomNameChanged { name }).await;
+}
+
+/// CoordinateIceRestart 처리. 방의 epoch를 올리고 요청한 피어를 포함한 멤버 전원에게 알린다.
+pub async fn handle_coordinate_ice_restart(state: Arc<AppState>, peer_id: &str, room_id: &str) {
+    let epoch = match state.rooms.get(room_id) {
+        Some(room) if room.users.read().await.contains(peer_id) => {
+            room.touch();
+            room.ice_restart_epoch.fetch_add(1, Ordering::AcqRel) + 1
+        }
+        _ => {
+            send_error(&state, peer_id, "not_in_room", "Not a member of this room");
+            return;
         }
+    };
+
+    broadcast_to_room(&state, room_id, ServerMessage::IceRestartEpoch { epoch }).await;
+    tracing::debug!(room_id = %room_id, from = %peer_id, epoch, "Coordinated ICE restart");
+}
+
+/// owner 전용 요청의 대상 방. 멤버가 아니거나 owner가 아니면 에러를 보내고 None.
+async fn owned_room<'a>(
+    state: &'a AppState,
+    peer_id: &str,
+    room_id: &str,
+) -> Option<dashmap::mapref::one::Ref<'a, String, Room>> {
+    let Some(room) = state.rooms.get(room_id) else {
+        send_error(state, peer_id, "not_in_room", "Not a member of this room");
+        return None;
+    };
+    if !room.users.read().await.contains(peer_id) {
+        send_error(state, peer_id, "not_in_room", "Not a member of this room");
+        return None;
+    }
+    if room.owner.as_deref() != Some(peer_id) {
+        send_error(
+            state,
+            peer_id,
+            "not_room_owner",
+            "Only the room owner can change room settings",
+        );
+        return None;
     }
+    Some(room)
 }
 
 /// 방에 메시지 브로드캐스트
 async fn broadcast_to_room(state: &AppState, room_id: &str, message: ServerMessage) {
-    if let Some(room) = state.rooms.get(room_id) {
-        let users = room.users.read().await;
-        for peer_id in users.iter() {
-            if let Some(session) = state.peers.get(peer_id) {
-                let _ = session.sender.send(message.clone());
+    let recipients = match state.rooms.get(room_id) {
+        Some(room) => snapshot_recipients(state, &room, None, None).await,
+        None => Vec::new(),
+    };
+    fan_out(state, recipients, &message);
+    // 참여자 목록은 노드별로 관리하므로 다른 노드로 보내지 않는다.
+    if !matches!(
+        message,
+        ServerMessage::RoomUsers { .. } | ServerMessage::RoomUsersDelta { .. }
+    ) {
+        cluster::publish_room(state, room_id, None, message).await;
+    }
+}
+
+/// 브로드캐스트 수신자와 송신 채널을 복사한다. 전송은 방/세션 lock을 놓은 뒤 `fan_out`으로 한다.
+/// `role`이 있으면 그 역할의 피어만 고른다.
+pub async fn snapshot_recipients(
+    state: &AppState,
+    room: &Room,
+    except_peer_id: Option<&str>,
+    role: Option<PeerRole>,
+) -> Vec<(String, UnboundedSender<ServerMessage>)> {
+    let users = room.users.read().await;
+    let mut recipients = Vec::with_capacity(users.len());
+    for peer_id in users.iter() {
+        if Some(peer_id.as_str()) == except_peer_id || state.dead_peers.contains(peer_id) {
+            continue;
+        }
+        let Some(session) = state.peers.get(peer_id) else {
+            continue;
+        };
+        if let Some(role) = role {
+            if *session.role.read().await != role {
+                continue;
             }
         }
+        recipients.push((peer_id.clone(), session.sender.clone()));
     }
+    recipients
 }
 
-/// 오래된 방 정리
-pub async fn cleanup_old_rooms(state: Arc<AppState>) {
-    let timeout_ms = state.config.room.timeout_ms;
-    let now = Instant::now();
-    let mut deleted = 0;
+/// 복사해 둔 수신자에게 전송한다. 송신이 실패한 피어는 정리 대기열로 보낸다.
+pub fn fan_out(
+    state: &AppState,
+    recipients: Vec<(String, UnboundedSender<ServerMessage>)>,
+    message: &ServerMessage,
+) {
+    let failed = recipients
+        .into_iter()
+        .filter(|(_, sender)| sender.send(message.clone()).is_err())
+        .map(|(peer_id, _)| peer_id)
+        .collect();
+    crate::handlers::enqueue_dead_peers(state, failed);
+}
 
-    state.rooms.retain(|room_id, room| {
-        let age = now.duration_since(room.created_at).as_millis() as u64;
-        if age > timeout_ms {
-            tracing::info!(room_id = %room_id, age_ms = age, "Cleaned up old room");
-            deleted += 1;
-            false
+/// 방 ping 진단 결과
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomPingReport {
+    pub room_id: String,
+    pub nonce: String,
+    pub reachable: Vec<String>,
+    pub unreachable: Vec<String>,
+}
+
+/// 방의 모든 멤버에게 Ping을 보내고 채널이 수락했는지 집계한다.
+/// 클라이언트 협조 없이 송신 경로만 점검하므로 응답(pong)은 기다리지 않는다.
+pub async fn ping_room(state: &AppState, room_id: &str) -> Option<RoomPingReport> {
+    let members: Vec<String> = {
+        let room = state.rooms.get(room_id)?;
+        let users = room.users.read().await;
+        users.iter().cloned().collect()
+    };
+
+    let nonce = Uuid::new_v4().to_string();
+    let server_ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let mut reachable = Vec::new();
+    let mut unreachable = Vec::new();
+
+    for peer_id in members {
+        let delivered = state.peers.get(&peer_id).is_some_and(|session| {
+            session
+                .sender
+                .send(ServerMessage::Ping {
+                    nonce: nonce.clone(),
+                    server_ts,
+                })
+                .is_ok()
+        });
+        if delivered {
+            reachable.push(peer_id);
         } else {
-            true
+            unreachable.push(peer_id);
         }
+    }
+
+    tracing::info!(
+        room_id = %room_id,
+        reachable = reachable.len(),
+        unreachable = unreachable.len(),
+        "Room ping completed"
+    );
+
+    Some(RoomPingReport {
+        room_id: room_id.to_string(),
+        nonce,
+        reachable,
+        unreachable,
+    })
+}
+
+/// 방을 닫는다 (관리자 요청, close_on_owner_leave). 멤버 전원에게 `RoomClosed`를 보내고
+/// 멤버십을 해제한 뒤 방을 삭제한다. 연결은 유지되므로 클라이언트는 다른 방에 다시 입장할 수 있다.
+/// 방이 없으면 None, 있으면 내보낸 멤버 수.
+pub async fn close_room(state: &AppState, room_id: &str, reason: &str) -> Option<usize> {
+    let message = ServerMessage::RoomClosed {
+        room_id: room_id.to_string(),
+        reason: reason.to_string(),
+    };
+    let members = evacuate_room(state, room_id, message).await?;
+    tracing::info!(
+        room_id = %room_id,
+        members,
+        reason = %reason,
+        "Room closed"
+    );
+    Some(members)
+}
+
+/// EndSession 처리 (owner 전용). 멤버 전원에게 `SessionEnded`를 보내고 방을 삭제한다.
+pub async fn handle_end_session(state: Arc<AppState>, peer_id: &str, room_id: &str) {
+    let room_id = room_id.trim();
+    if owned_room(&state, peer_id, room_id).await.is_none() {
+        return;
+    }
+    let message = ServerMessage::SessionEnded {
+        reason: "ended_by_owner".to_string(),
+    };
+    if let Some(members) = evacuate_room(&state, room_id, message).await {
+        tracing::info!(room_id = %room_id, owner = %peer_id, members, "Session ended by owner");
+    }
+}
+
+/// 방을 삭제하고 멤버 전원의 멤버십을 해제한 뒤 `message`를 보낸다. 내보낸 멤버 수를 돌려준다.
+async fn evacuate_room(state: &AppState, room_id: &str, message: ServerMessage) -> Option<usize> {
+    evacuate_room_if(state, room_id, message, |_| true).await
+}
+
+/// `evacuate_room`과 같되 맵에서 꺼내는 순간 `should_remove`가 참일 때만 삭제한다.
+async fn evacuate_room_if(
+    state: &AppState,
+    room_id: &str,
+    message: ServerMessage,
+    should_remove: impl FnOnce(&Room) -> bool,
+) -> Option<usize> {
+    let (_, room) = state
+        .rooms
+        .remove_if(room_id, |_, room| should_remove(room))?;
+    state.release_owner_room(&room);
+    state.emit_admin_event(AdminEvent::RoomDeleted {
+        room_id: room_id.to_string(),
     });
+    let members: Vec<String> = room.users.write().await.drain().collect();
+    state.release_room_users(members.len());
+    // 유예 중인 피어가 닫힌 방으로 복귀하지 않도록 함께 정리한다.
+    state.away_peers.retain(|_, away| away.room_id != room_id);
 
-    if deleted > 0 {
-        tracing::info!(deleted_rooms = deleted, "Cleanup completed");
+    for peer_id in &members {
+        if let Some(session) = state.peers.get(peer_id) {
+            let mut current = session.room_id.write().await;
+            if current.as_deref() == Some(room_id) {
+                *current = None;
+                state.track_roomless(false, true);
+            }
+            let _ = session.sender.send(message.clone());
+        }
+        cluster::update_presence(state, room_id, peer_id, false).await;
+    }
+    Some(members.len())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// 시그널링이 ROOM_KEEPALIVE_INTERVAL_MS 이상 없었던 방의 멤버에게 KeepAlive를 보낸다.
+/// P2P 연결만 쓰는 동안에도 클라이언트의 Heartbeat 응답으로 방이 정리되지 않게 한다.
+pub async fn nudge_idle_rooms(state: &AppState) -> usize {
+    let interval = Duration::from_millis(state.config.room.keepalive_interval_ms);
+    let idle_rooms: Vec<String> = state
+        .rooms
+        .iter()
+        .filter(|room| room.idle_for() >= interval)
+        .map(|room| room.key().clone())
+        .collect();
+
+    let mut nudged = 0;
+    for room_id in idle_rooms {
+        let recipients = match state.rooms.get(&room_id) {
+            Some(room) => snapshot_recipients(state, &room, None, None).await,
+            None => continue,
+        };
+        if recipients.is_empty() {
+            continue;
+        }
+        fan_out(
+            state,
+            recipients,
+            &ServerMessage::KeepAlive {
+                room_id: room_id.clone(),
+            },
+        );
+        nudged += 1;
+    }
+    if nudged > 0 {
+        tracing::debug!(rooms = nudged, "Sent keepalive to idle rooms");
+    }
+    nudged
+}
+
+/// 관리자 drain. 새 입장을 막고 멤버에게 `RoomClosing`을 보낸 뒤, 모두 나가거나
+/// `timeout`이 지나면 남은 멤버를 `RoomClosed { reason: "drained" }`로 내보내고 방을 삭제한다.
+/// 방이 없으면 None, 있으면 drain을 시작할 때의 멤버 수.
+pub async fn drain_room(state: Arc<AppState>, room_id: &str, timeout: Duration) -> Option<usize> {
+    let recipients = {
+        let room = state.rooms.get(room_id)?;
+        room.draining.store(true, Ordering::Release);
+        snapshot_recipients(&state, &room, None, None).await
+    };
+    let members = recipients.len();
+    fan_out(
+        &state,
+        recipients,
+        &ServerMessage::RoomClosing {
+            room_id: room_id.to_string(),
+            in_ms: timeout.as_millis() as u64,
+        },
+    );
+    tracing::info!(room_id = %room_id, members, timeout_ms = timeout.as_millis() as u64, "Draining room");
+
+    let room_id = room_id.to_string();
+    tokio::spawn(async move {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while tokio::time::Instant::now() < deadline {
+            let empty = match state.rooms.get(&room_id) {
+                Some(room) => room.users.read().await.is_empty(),
+                None => return,
+            };
+            if empty {
+                break;
+            }
+            tokio::time::sleep(DRAIN_POLL_INTERVAL.min(timeout)).await;
+        }
+        close_room(&state, &room_id, "drained").await;
+    });
+    Some(members)
+}
+
+/// 관리자 예약. 아무도 없는 방을 미리 만들어 `ttl` 동안 정리되지 않게 하고,
+/// 처음 들어온 Participant를 owner로 삼는다. 이미 있는 방이면 false.
+pub fn reserve_room(state: &AppState, room_id: &str, ttl: Duration) -> bool {
+    let mut created = false;
+    state.rooms.entry(room_id.to_string()).or_insert_with(|| {
+        created = true;
+        Room {
+            reserved_until: Some(Instant::now() + ttl),
+            ..Room::with_config(room_id.to_string(), &state.config.room)
+        }
+    });
+    if created {
+        tracing::info!(room_id = %room_id, ttl_ms = ttl.as_millis() as u64, "Room reserved");
+        state.emit_admin_event(AdminEvent::RoomCreated {
+            room_id: room_id.to_string(),
+        });
+    }
+    created
+}
+
+/// drain 중인 방이 비었는지 확인하는 주기
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 오래된 방 정리
+/// ROOM_TIMEOUT 동안 시그널링 중계나 멤버 Heartbeat가 없었던 방을 정리한다.
+/// 멤버에게 먼저 RoomClosing을 보내고 ROOM_CLOSING_GRACE_MS 뒤에도 조용한 방만 삭제한다.
+pub async fn cleanup_old_rooms(state: Arc<AppState>) {
+    let timeout_ms = state.config.room.timeout_ms;
+    let grace_ms = state.config.room.closing_grace_ms;
+    let aged: HashSet<String> = state
+        .rooms
+        .iter()
+        .filter(|room| !room.held_empty() && room.idle_for().as_millis() as u64 > timeout_ms)
+        .map(|room| room.key().clone())
+        .collect();
+    if aged.is_empty() {
+        return;
+    }
+
+    for room_id in &aged {
+        let recipients = match state.rooms.get(room_id) {
+            Some(room) => snapshot_recipients(&state, &room, None, None).await,
+            None => continue,
+        };
+        fan_out(
+            &state,
+            recipients,
+            &ServerMessage::RoomClosing {
+                room_id: room_id.clone(),
+                in_ms: grace_ms,
+            },
+        );
+    }
+    if grace_ms > 0 {
+        tokio::time::sleep(Duration::from_millis(grace_ms)).await;
+    }
+
+    let mut deleted = 0;
+    for room_id in &aged {
+        // 유예 중 활동이 있었으면 남긴다. 삭제는 다른 종료와 같이 멤버십·유예 피어·presence까지 정리한다
+        let message = ServerMessage::RoomClosed {
+            room_id: room_id.clone(),
+            reason: "timeout".to_string(),
+        };
+        let still_aged =
+            |room: &Room| !room.held_empty() && room.idle_for().as_millis() as u64 > timeout_ms;
+        let Some(members) = evacuate_room_if(&state, room_id, message, still_aged).await else {
+            continue;
+        };
+        tracing::info!(room_id = %room_id, members, "Cleaned up idle room");
+        deleted += 1;
+    }
+
+    if deleted > 0 {
+        tracing::info!(deleted_rooms = deleted, "Cleanup completed");
+    }
+}
+
+/// 비어 있는 채로 `EMPTY_ROOM_LINGER_MS`가 지난 방을 삭제하고 삭제한 수를 돌려준다.
+pub fn reap_empty_rooms(state: &AppState) -> usize {
+    let linger = Duration::from_millis(state.config.room.empty_room_linger_ms);
+    let mut deleted = 0;
+
+    state.rooms.retain(|room_id, room| {
+        // 입장 처리 중이라 lock이 잡혀 있으면 비어 있지 않은 것으로 본다
+        let empty = room.users.try_read().is_ok_and(|users| users.is_empty());
+        if empty && !room.held_empty() && room.idle_for() >= linger {
+            tracing::info!(room_id = %room_id, "Room deleted after empty linger");
+            state.release_owner_room(room);
+            state.emit_admin_event(AdminEvent::RoomDeleted {
+                room_id: room_id.clone(),
+            });
+            deleted += 1;
+            false
+        } else {
+            true
+        }
+    });
+    deleted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::drain_messages;
+    use std::time::Instant;
+
+    type UnboundedReceiver = tokio::sync::mpsc::UnboundedReceiver<ServerMessage>;
+
+    #[tokio::test]
+    async fn ping_room_reports_live_and_dead_members() {
+        let state = AppState::new_for_test();
+        let mut live = state.insert_test_peer("live");
+        drop(state.insert_test_peer("dead"));
+        let room = Room::new("room".to_string());
+        room.users
+            .write()
+            .await
+            .extend(["live".to_string(), "dead".to_string()]);
+        state.rooms.insert("room".to_string(), room);
+
+        let report = ping_room(&state, "room").await.expect("room exists");
+
+        assert_eq!(report.reachable, vec!["live".to_string()]);
+        assert_eq!(report.unreachable, vec!["dead".to_string()]);
+        match drain_messages(&mut live).as_slice() {
+            [ServerMessage::Ping { nonce, server_ts }] => {
+                assert_eq!(nonce, &report.nonce);
+                assert!(*server_ts > 0);
+            }
+            other => panic!("unexpected messages: {other:?}"),
+        }
+    }
+
+    /// 받은 RoomUsers/RoomUsersDelta를 클라이언트처럼 적용한다.
+    fn apply_membership(list: &mut HashSet<String>, messages: &[ServerMessage]) {
+        for message in messages {
+            match message {
+                ServerMessage::RoomUsers { users, .. } => {
+                    *list = users.iter().cloned().collect();
+                }
+                ServerMessage::RoomUsersDelta { added, removed } => {
+                    list.extend(added.iter().cloned());
+                    for peer in removed {
+                        list.remove(peer);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn membership_deltas_track_room_users() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut watcher = state.insert_test_peer("a");
+        let _peers: Vec<_> = ["b", "c", "d"]
+            .into_iter()
+            .map(|peer| state.insert_test_peer(peer))
+            .collect();
+        let mut list = HashSet::new();
+
+        handle_join_room(state.clone(), "a", "room", PeerRole::Participant, None).await;
+        handle_join_room(state.clone(), "b", "room", PeerRole::Participant, None).await;
+        handle_join_room(state.clone(), "c", "room", PeerRole::Participant, None).await;
+        handle_leave_room(state.clone(), "b", None).await;
+        handle_join_room(state.clone(), "d", "room", PeerRole::Participant, None).await;
+        handle_join_room(state.clone(), "d", "room", PeerRole::Participant, None).await;
+        handle_leave_room(state.clone(), "c", Some("room")).await;
+
+        let messages = drain_messages(&mut watcher);
+        let full_lists = messages
+            .iter()
+            .filter(|message| matches!(message, ServerMessage::RoomUsers { .. }))
+            .count();
+        assert_eq!(full_lists, 1, "only the join sends a full list");
+        apply_membership(&mut list, &messages);
+
+        let actual = state.rooms.get("room").unwrap().users.read().await.clone();
+        assert_eq!(list, actual);
+        assert_eq!(list, HashSet::from(["a".to_string(), "d".to_string()]));
+    }
+
+    /// 메시지 종류만 뽑아 순서를 비교한다.
+    fn kinds(messages: &[ServerMessage]) -> Vec<&'static str> {
+        messages
+            .iter()
+            .map(|message| match message {
+                ServerMessage::RoomUsers { .. } => "RoomUsers",
+                ServerMessage::JoinedRoom { .. } => "JoinedRoom",
+                ServerMessage::PeerJoined { .. } => "PeerJoined",
+                ServerMessage::RoomUsersDelta { .. } => "RoomUsersDelta",
+                _ => "other",
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn join_sequence_is_deterministic_for_joiner_and_members() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut member = state.insert_test_peer("member");
+        let mut joiner = state.insert_test_peer("joiner");
+        handle_join_room(state.clone(), "member", "room", PeerRole::Participant, None).await;
+        drain_messages(&mut member);
+
+        handle_join_room(state.clone(), "joiner", "room", PeerRole::Participant, None).await;
+        // 재입장해도 자기 자신에 대한 PeerJoined는 오지 않고, 기존 멤버에게도 다시 알리지 않는다.
+        handle_join_room(state.clone(), "joiner", "room", PeerRole::Participant, None).await;
+
+        let joiner_messages = drain_messages(&mut joiner);
+        assert_eq!(
+            kinds(&joiner_messages),
+            [
+                "RoomUsers",
+                "JoinedRoom",
+                "RoomUsersDelta",
+                "RoomUsers",
+                "JoinedRoom"
+            ]
+        );
+        assert!(matches!(
+            &joiner_messages[0],
+            ServerMessage::RoomUsers { users, .. } if users == &["member".to_string()]
+        ));
+        assert!(matches!(
+            &joiner_messages[3],
+            ServerMessage::RoomUsers { users, .. } if users == &["member".to_string()]
+        ));
+
+        let member_messages = drain_messages(&mut member);
+        assert_eq!(kinds(&member_messages), ["PeerJoined", "RoomUsersDelta"]);
+        assert!(member_messages.iter().all(|message| match message {
+            ServerMessage::PeerJoined { socket_id, .. } => socket_id == "joiner",
+            _ => true,
+        }));
+    }
+
+    #[tokio::test]
+    async fn duplicate_join_does_not_notify_members_when_configured() {
+        for (policy, expected) in [
+            (
+                DuplicateJoinPolicy::Snapshot,
+                vec!["RoomUsers", "JoinedRoom"],
+            ),
+            (DuplicateJoinPolicy::Ignore, vec![]),
+        ] {
+            let mut config = crate::config::Config::from_env();
+            config.room.duplicate_join = policy;
+            let state = Arc::new(AppState::new_for_test_with_config(config));
+            let mut member = state.insert_test_peer("member");
+            let mut joiner = state.insert_test_peer("joiner");
+            let participant = PeerRole::Participant;
+            handle_join_room(state.clone(), "member", "room", participant, None).await;
+            handle_join_room(state.clone(), "joiner", "room", participant, None).await;
+            drain_messages(&mut member);
+            drain_messages(&mut joiner);
+
+            for _ in 0..3 {
+                handle_join_room(state.clone(), "joiner", "room", participant, None).await;
+            }
+
+            assert!(drain_messages(&mut member).is_empty(), "{policy:?}");
+            let replies = drain_messages(&mut joiner);
+            let expected: Vec<&str> = expected
+                .iter()
+                .cycle()
+                .take(expected.len() * 3)
+                .copied()
+                .collect();
+            assert_eq!(kinds(&replies), expected, "{policy:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn force_relay_room_delivers_policy_on_join() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let mut other = state.insert_test_peer("other");
+        let options = RoomOptions {
+            force_relay: true,
+            ..RoomOptions::default()
+        };
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "host", "relay", participant, Some(options)).await;
+        handle_join_room(state.clone(), "guest", "relay", participant, None).await;
+        handle_join_room(state.clone(), "other", "open", participant, None).await;
+
+        let force_relay = |messages: Vec<ServerMessage>| {
+            messages.into_iter().find_map(|message| match message {
+                ServerMessage::JoinedRoom { policy, .. } => Some(policy.force_relay),
+                _ => None,
+            })
+        };
+        assert_eq!(force_relay(drain_messages(&mut host)), Some(true));
+        assert_eq!(force_relay(drain_messages(&mut guest)), Some(true));
+        assert_eq!(force_relay(drain_messages(&mut other)), Some(false));
+    }
+
+    #[tokio::test]
+    async fn owner_metadata_is_broadcast_and_given_to_late_joiners() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "host", "room", participant, None).await;
+        handle_join_room(state.clone(), "guest", "room", participant, None).await;
+        drain_messages(&mut host);
+        drain_messages(&mut guest);
+
+        let metadata = serde_json::json!({"name": "photos", "files": 12});
+        handle_set_room_metadata(state.clone(), "host", "room", metadata.clone()).await;
+        for peer in [&mut host, &mut guest] {
+            assert!(matches!(
+                drain_messages(peer).as_slice(),
+                [ServerMessage::RoomMetadataUpdated { metadata: received }] if *received == metadata
+            ));
+        }
+
+        let mut late = state.insert_test_peer("late");
+        handle_join_room(state.clone(), "late", "room", participant, None).await;
+        let joined = drain_messages(&mut late)
+            .into_iter()
+            .find_map(|message| match message {
+                ServerMessage::JoinedRoom { metadata, .. } => Some(metadata),
+                _ => None,
+            });
+        assert_eq!(joined, Some(Some(metadata)));
+    }
+
+    #[tokio::test]
+    async fn metadata_requires_owner_and_size_limit() {
+        let mut config = crate::config::Config::from_env();
+        config.room.metadata_max_bytes = 32;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let _host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "host", "room", participant, None).await;
+        handle_join_room(state.clone(), "guest", "room", participant, None).await;
+        drain_messages(&mut guest);
+
+        handle_set_room_metadata(state.clone(), "guest", "room", serde_json::json!({})).await;
+        let oversized = serde_json::json!({"name": "x".repeat(64)});
+        handle_set_room_metadata(state.clone(), "host", "room", oversized).await;
+
+        assert!(matches!(
+            drain_messages(&mut guest).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "not_room_owner"
+        ));
+        let room = state.rooms.get("room").unwrap();
+        assert!(room.metadata.read().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn owner_sets_sanitized_room_name_for_members_and_late_joiners() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "host", "room", participant, None).await;
+        handle_join_room(state.clone(), "guest", "room", participant, None).await;
+        drain_messages(&mut host);
+        drain_messages(&mut guest);
+
+        handle_set_room_name(state.clone(), "host", "room", "  Team\u{7}\nSync  ").await;
+        for peer in [&mut host, &mut guest] {
+            assert!(matches!(
+                drain_messages(peer).as_slice(),
+                [ServerMessage::RoomNameChanged { name: Some(name) }] if name == "TeamSync"
+            ));
+        }
+
+        let mut late = state.insert_test_peer("late");
+        handle_join_room(state.clone(), "late", "room", participant, None).await;
+        let joined = drain_messages(&mut late)
+            .into_iter()
+            .find_map(|message| match message {
+                ServerMessage::JoinedRoom { name, .. } => Some(name),
+                _ => None,
+            });
+        assert_eq!(joined, Some(Some("TeamSync".to_string())));
+    }
+
+    #[tokio::test]
+    async fn joined_room_reports_whether_the_room_was_created() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut first = state.insert_test_peer("first");
+        let mut second = state.insert_test_peer("second");
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "first", "room", participant, None).await;
+        handle_join_room(state.clone(), "second", "room", participant, None).await;
+
+        let created = |rx: &mut UnboundedReceiver| {
+            drain_messages(rx)
+                .into_iter()
+                .find_map(|message| match message {
+                    ServerMessage::JoinedRoom { created, .. } => Some(created),
+                    _ => None,
+                })
+        };
+        assert_eq!(created(&mut first), Some(true));
+        assert_eq!(created(&mut second), Some(false));
+    }
+
+    #[tokio::test]
+    async fn snapshot_capable_joiner_gets_full_room_state_in_one_message() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "host", "room", participant, None).await;
+        handle_set_room_name(state.clone(), "host", "room", "Team sync").await;
+        let metadata = serde_json::json!({ "topic": "demo" });
+        handle_set_room_metadata(state.clone(), "host", "room", metadata.clone()).await;
+        drain_messages(&mut host);
+
+        let requested = vec!["room_snapshot".to_string(), "unknown".to_string()];
+        let accepted = state
+            .peers
+            .get("guest")
+            .unwrap()
+            .capabilities
+            .negotiate(requested);
+        assert_eq!(accepted, ["room_snapshot"]);
+        handle_join_room(state.clone(), "guest", "room", participant, None).await;
+
+        let messages = drain_messages(&mut guest);
+        assert!(!messages.iter().any(|message| matches!(
+            message,
+            ServerMessage::RoomUsers { .. } | ServerMessage::JoinedRoom { .. }
+        )));
+        match messages.first() {
+            Some(ServerMessage::RoomSnapshot(snapshot)) => {
+                assert_eq!(snapshot.room_id, "room");
+                assert_eq!(snapshot.you, "guest");
+                let users: HashSet<&str> = snapshot.users.iter().map(String::as_str).collect();
+                assert_eq!(users, HashSet::from(["host", "guest"]));
+                assert_eq!(snapshot.owner.as_deref(), Some("host"));
+                assert_eq!(snapshot.metadata.as_ref(), Some(&metadata));
+                assert_eq!(snapshot.name.as_deref(), Some("Team sync"));
+                assert_eq!(snapshot.user_count, 2);
+                assert_eq!(snapshot.max_size, state.config.room.max_size);
+                assert!(!snapshot.policy.force_relay);
+                assert!(!snapshot.created);
+            }
+            other => panic!("unexpected first message: {other:?}"),
+        }
+
+        // 확장을 켜지 않은 기존 참여자는 그대로 PeerJoined를 받는다
+        assert!(drain_messages(&mut host)
+            .iter()
+            .any(|message| matches!(message, ServerMessage::PeerJoined { .. })));
+    }
+
+    #[tokio::test]
+    async fn room_id_with_hidden_characters_is_rejected() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut peer = state.insert_test_peer("peer");
+        let participant = PeerRole::Participant;
+
+        for room_id in ["ro\0om", "room\u{7}", "abc\u{202E}fdp", "ro\u{200B}om"] {
+            handle_join_room(state.clone(), "peer", room_id, participant, None).await;
+            assert!(matches!(
+                drain_messages(&mut peer).as_slice(),
+                [ServerMessage::Error { code, .. }] if code == "invalid_room_id"
+            ));
+        }
+        assert!(state.rooms.is_empty());
+
+        handle_join_room(state.clone(), "peer", "  방-123  ", participant, None).await;
+        assert!(drain_messages(&mut peer)
+            .iter()
+            .any(|message| matches!(message, ServerMessage::JoinedRoom { .. })));
+        assert!(state.rooms.contains_key("방-123"));
+    }
+
+    #[tokio::test]
+    async fn over_length_or_non_owner_room_name_is_rejected() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "host", "room", participant, None).await;
+        handle_join_room(state.clone(), "guest", "room", participant, None).await;
+        drain_messages(&mut host);
+        drain_messages(&mut guest);
+
+        let long_name = "방".repeat(MAX_ROOM_NAME_CHARS + 1);
+        handle_set_room_name(state.clone(), "host", "room", &long_name).await;
+        handle_set_room_name(state.clone(), "guest", "room", "mine").await;
+
+        assert!(matches!(
+            drain_messages(&mut host).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "room_name_too_long"
+        ));
+        assert!(matches!(
+            drain_messages(&mut guest).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "not_room_owner"
+        ));
+        let room = state.rooms.get("room").unwrap();
+        assert!(room.name.read().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn silent_room_gets_keepalive_and_survives_cleanup() {
+        let mut config = crate::config::Config::from_env();
+        config.room.keepalive_interval_ms = 100;
+        config.room.timeout_ms = 1_000;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut member = state.insert_test_peer("member");
+        handle_join_room(state.clone(), "member", "busy", PeerRole::Participant, None).await;
+        handle_join_room(
+            state.clone(),
+            "member",
+            "quiet",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        drain_messages(&mut member);
+        let silent_since = Instant::now() - Duration::from_millis(900);
+        *state
+            .rooms
+            .get("quiet")
+            .unwrap()
+            .last_activity
+            .lock()
+            .unwrap() = silent_since;
+
+        assert_eq!(nudge_idle_rooms(&state).await, 1);
+        assert!(matches!(
+            drain_messages(&mut member).as_slice(),
+            [ServerMessage::KeepAlive { room_id }] if room_id == "quiet"
+        ));
+
+        // 클라이언트가 Heartbeat로 응답하면 방 활동이 갱신된다.
+        crate::handlers::handle_heartbeat(&state, "member").await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        cleanup_old_rooms(state.clone()).await;
+        assert!(state.rooms.get("quiet").is_some());
+    }
+
+    #[tokio::test]
+    async fn room_silent_past_timeout_is_cleaned_up() {
+        let mut config = crate::config::Config::from_env();
+        config.room.timeout_ms = 1_000;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let _member = state.insert_test_peer("member");
+        handle_join_room(state.clone(), "member", "room", PeerRole::Participant, None).await;
+        let silent_since = Instant::now() - Duration::from_millis(1_500);
+        *state
+            .rooms
+            .get("room")
+            .unwrap()
+            .last_activity
+            .lock()
+            .unwrap() = silent_since;
+
+        cleanup_old_rooms(state.clone()).await;
+
+        assert!(state.rooms.get("room").is_none());
+        assert_eq!(state.room_users.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn members_are_warned_before_timed_out_room_is_deleted() {
+        let mut config = crate::config::Config::from_env();
+        config.room.timeout_ms = 1_000;
+        config.room.closing_grace_ms = 300;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut member = state.insert_test_peer("member");
+        handle_join_room(state.clone(), "member", "room", PeerRole::Participant, None).await;
+        drain_messages(&mut member);
+        let silent_since = Instant::now() - Duration::from_millis(1_500);
+        *state
+            .rooms
+            .get("room")
+            .unwrap()
+            .last_activity
+            .lock()
+            .unwrap() = silent_since;
+
+        let cleanup = tokio::spawn(cleanup_old_rooms(state.clone()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(
+            drain_messages(&mut member).as_slice(),
+            [ServerMessage::RoomClosing { room_id, in_ms: 300 }] if room_id == "room"
+        ));
+        assert!(state.rooms.get("room").is_some());
+
+        cleanup.await.unwrap();
+        assert!(state.rooms.get("room").is_none());
+        assert!(matches!(
+            drain_messages(&mut member).as_slice(),
+            [ServerMessage::RoomClosed { room_id, reason }] if room_id == "room" && reason == "timeout"
+        ));
+        let session = state.peers.get("member").unwrap();
+        assert!(session.room_id.read().await.is_none());
+        assert_eq!(state.room_users.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn coordinated_ice_restart_broadcasts_increasing_epoch() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut receivers = [state.insert_test_peer("a"), state.insert_test_peer("b")];
+        let mut outsider = state.insert_test_peer("outsider");
+        for peer_id in ["a", "b"] {
+            handle_join_room(state.clone(), peer_id, "room", PeerRole::Participant, None).await;
+        }
+        for rx in &mut receivers {
+            drain_messages(rx);
+        }
+
+        for expected in [1, 2] {
+            handle_coordinate_ice_restart(state.clone(), "a", "room").await;
+            for rx in &mut receivers {
+                assert!(matches!(
+                    drain_messages(rx).as_slice(),
+                    [ServerMessage::IceRestartEpoch { epoch }] if *epoch == expected
+                ));
+            }
+        }
+
+        handle_coordinate_ice_restart(state.clone(), "outsider", "room").await;
+        assert!(matches!(
+            drain_messages(&mut outsider).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "not_in_room"
+        ));
+        let room = state.rooms.get("room").unwrap();
+        assert_eq!(room.ice_restart_epoch.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn full_kiosk_room_evicts_least_recently_active_participant() {
+        let mut config = crate::config::Config::from_env();
+        config.room.max_size = 3;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut receivers: Vec<_> = ["host", "a", "b", "c", "d"]
+            .into_iter()
+            .map(|peer_id| state.insert_test_peer(peer_id))
+            .collect();
+        let kiosk = RoomOptions {
+            evict_oldest_on_full: true,
+            ..RoomOptions::default()
+        };
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "host", "kiosk", participant, Some(kiosk)).await;
+        handle_join_room(state.clone(), "a", "kiosk", participant, None).await;
+        handle_join_room(state.clone(), "b", "kiosk", participant, None).await;
+        // owner는 가장 조용해도 내보내지 않는다.
+        // a는 먼저 접속했지만 최근 Heartbeat가 있고, b는 접속 이후 조용하다
+        let now = Instant::now();
+        state.peers.get_mut("host").unwrap().connected_at = now - Duration::from_secs(60);
+        state.peers.get_mut("a").unwrap().connected_at = now - Duration::from_secs(20);
+        state.peers.get_mut("b").unwrap().connected_at = now - Duration::from_secs(10);
+        state.peers.get("a").unwrap().stats.record_heartbeat();
+        for rx in &mut receivers {
+            drain_messages(rx);
+        }
+
+        handle_join_room(state.clone(), "c", "kiosk", participant, None).await;
+        assert!(matches!(
+            drain_messages(&mut receivers[2]).as_slice(),
+            [ServerMessage::Evicted { room_id }] if room_id == "kiosk"
+        ));
+        assert!(state
+            .peers
+            .get("b")
+            .unwrap()
+            .room_id
+            .try_read()
+            .unwrap()
+            .is_none());
+        assert!(drain_messages(&mut receivers[0]).iter().any(
+            |message| matches!(message, ServerMessage::UserLeft { socket_id } if socket_id == "b")
+        ));
+
+        // 이제 a(Heartbeat 이후 경과)가 c(방금 접속)보다 오래 조용하다
+        state
+            .peers
+            .get("a")
+            .unwrap()
+            .stats
+            .last_heartbeat_ms
+            .store(1, Ordering::Relaxed);
+        handle_join_room(state.clone(), "d", "kiosk", participant, None).await;
+        assert!(drain_messages(&mut receivers[1])
+            .iter()
+            .any(|message| matches!(message, ServerMessage::Evicted { .. })));
+
+        let room = state.rooms.get("kiosk").unwrap();
+        let mut members: Vec<_> = room.users.try_read().unwrap().iter().cloned().collect();
+        members.sort();
+        assert_eq!(members, vec!["c", "d", "host"]);
+        assert_eq!(state.room_users.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn rejected_join_does_not_evict_from_full_kiosk_room() {
+        let mut config = crate::config::Config::from_env();
+        config.room.max_size = 2;
+        config.room.max_per_ip = 1;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut receivers: Vec<_> = ["host", "member", "joiner"]
+            .into_iter()
+            .map(|peer_id| state.insert_test_peer(peer_id))
+            .collect();
+        let shared_ip: std::net::IpAddr = "203.0.113.9".parse().unwrap();
+        for peer_id in ["member", "joiner"] {
+            state.peers.get_mut(peer_id).unwrap().remote_ip = Some(shared_ip);
+        }
+        let kiosk = RoomOptions {
+            evict_oldest_on_full: true,
+            ..RoomOptions::default()
+        };
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "host", "kiosk", participant, Some(kiosk)).await;
+        handle_join_room(state.clone(), "member", "kiosk", participant, None).await;
+        drain_messages(&mut receivers[1]);
+
+        handle_join_room(state.clone(), "joiner", "kiosk", participant, None).await;
+
+        assert!(matches!(
+            drain_messages(&mut receivers[2]).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "ip_limit"
+        ));
+        assert!(drain_messages(&mut receivers[1]).is_empty());
+        let room = state.rooms.get("kiosk").unwrap();
+        assert!(room.users.try_read().unwrap().contains("member"));
+    }
+
+    #[tokio::test]
+    async fn owner_room_limit_blocks_new_rooms_until_one_is_freed() {
+        let mut config = crate::config::Config::from_env();
+        config.room.max_rooms_per_owner = 2;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut owner = state.insert_test_peer("owner");
+        let mut alias = state.insert_test_peer("alias");
+        let _guest = state.insert_test_peer("guest");
+        let owner_ip: std::net::IpAddr = "203.0.113.7".parse().unwrap();
+        for peer_id in ["owner", "alias"] {
+            state.peers.get_mut(peer_id).unwrap().remote_ip = Some(owner_ip);
+        }
+        state.peers.get_mut("guest").unwrap().remote_ip = Some("198.51.100.2".parse().unwrap());
+        let participant = PeerRole::Participant;
+        // owner가 다음 방으로 옮겨도 남은 멤버가 있으면 방은 유지된다
+        for (room_id, keeper) in [("one", "k1"), ("two", "k2")] {
+            let _ = state.insert_test_peer(keeper);
+            handle_join_room(state.clone(), "owner", room_id, participant, None).await;
+            handle_join_room(state.clone(), keeper, room_id, participant, None).await;
+        }
+        drain_messages(&mut owner);
+
+        handle_join_room(state.clone(), "owner", "three", participant, None).await;
+        assert!(matches!(
+            drain_messages(&mut owner).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "owner_room_limit"
+        ));
+        assert!(state.rooms.get("three").is_none());
+        // client_token을 바꿔 다시 접속해도 같은 IP로 센다
+        handle_join_room(state.clone(), "alias", "three", participant, None).await;
+        assert!(matches!(
+            drain_messages(&mut alias).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "owner_room_limit"
+        ));
+        assert!(state.rooms.get("three").is_none());
+
+        // 다른 클라이언트가 만든 방, 이미 있는 방 입장은 제한과 무관하다
+        handle_join_room(state.clone(), "guest", "three", participant, None).await;
+        handle_join_room(state.clone(), "owner", "three", participant, None).await;
+        assert!(state
+            .rooms
+            .get("three")
+            .unwrap()
+            .users
+            .try_read()
+            .unwrap()
+            .contains("owner"));
+
+        close_room(&state, "one", "test").await;
+        drain_messages(&mut owner);
+        handle_join_room(state.clone(), "owner", "four", participant, None).await;
+        assert!(state.rooms.get("four").is_some());
+        assert_eq!(
+            state.owner_rooms.get(&owner_ip).map(|owned| *owned),
+            Some(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn owner_ending_session_evacuates_every_member() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut receivers: Vec<_> = ["host", "a", "b"]
+            .into_iter()
+            .map(|peer_id| state.insert_test_peer(peer_id))
+            .collect();
+        for peer_id in ["host", "a", "b"] {
+            handle_join_room(state.clone(), peer_id, "room", PeerRole::Participant, None).await;
+        }
+        for rx in &mut receivers {
+            drain_messages(rx);
+        }
+
+        handle_end_session(state.clone(), "a", "room").await;
+        assert!(matches!(
+            drain_messages(&mut receivers[1]).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "not_room_owner"
+        ));
+        assert!(state.rooms.get("room").is_some());
+
+        handle_end_session(state.clone(), "host", "room").await;
+        assert!(state.rooms.get("room").is_none());
+        assert_eq!(state.room_users.load(Ordering::Relaxed), 0);
+        for (peer_id, rx) in ["host", "a", "b"].into_iter().zip(&mut receivers) {
+            assert!(matches!(
+                drain_messages(rx).as_slice(),
+                [ServerMessage::SessionEnded { reason }] if reason == "ended_by_owner"
+            ));
+            let session = state.peers.get(peer_id).unwrap();
+            assert!(session.room_id.read().await.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn global_user_cap_blocks_joins_until_someone_leaves() {
+        let mut config = crate::config::Config::from_env();
+        config.limits.max_total_users = 2;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut late = state.insert_test_peer("late");
+        let participant = PeerRole::Participant;
+        for peer_id in ["a", "b"] {
+            let _ = state.insert_test_peer(peer_id);
+        }
+        handle_join_room(state.clone(), "a", "one", participant, None).await;
+        handle_join_room(state.clone(), "b", "two", participant, None).await;
+        // 이미 들어가 있는 방에 다시 입장하는 것은 자리를 더 쓰지 않는다
+        handle_join_room(state.clone(), "a", "one", participant, None).await;
+        assert_eq!(state.room_users.load(Ordering::Relaxed), 2);
+
+        handle_join_room(state.clone(), "late", "one", participant, None).await;
+        assert!(matches!(
+            drain_messages(&mut late).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "global_user_cap"
+        ));
+
+        handle_leave_room(state.clone(), "b", Some("two")).await;
+        handle_join_room(state.clone(), "late", "one", participant, None).await;
+        assert!(drain_messages(&mut late)
+            .iter()
+            .any(|message| matches!(message, ServerMessage::JoinedRoom { .. })));
+        assert_eq!(state.room_users.load(Ordering::Relaxed), 2);
+
+        close_room(&state, "one", "test").await;
+        assert_eq!(state.room_users.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn global_user_cap_rejection_leaves_no_empty_room() {
+        let mut config = crate::config::Config::from_env();
+        config.limits.max_total_users = 1;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let participant = PeerRole::Participant;
+        let _a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+        handle_join_room(state.clone(), "a", "one", participant, None).await;
+
+        handle_join_room(state.clone(), "b", "two", participant, None).await;
+
+        assert!(matches!(
+            drain_messages(&mut b).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "global_user_cap"
+        ));
+        assert!(state.rooms.get("two").is_none());
+        assert_eq!(state.room_users.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn hopping_between_rooms_holds_a_single_slot() {
+        let mut config = crate::config::Config::from_env();
+        config.limits.max_total_users = 2;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let participant = PeerRole::Participant;
+        let _hopper = state.insert_test_peer("hopper");
+        let _other = state.insert_test_peer("other");
+        handle_join_room(state.clone(), "other", "lobby", participant, None).await;
+        handle_join_room(state.clone(), "hopper", "lobby", participant, None).await;
+
+        for room_id in ["one", "two", "three"] {
+            handle_join_room(state.clone(), "hopper", room_id, participant, None).await;
+        }
+
+        assert_eq!(state.room_users.load(Ordering::Relaxed), 2);
+        let lobby = state.rooms.get("lobby").unwrap();
+        assert!(!lobby.users.read().await.contains("hopper"));
+        drop(lobby);
+        assert!(state.rooms.get("one").is_none());
+        assert!(state.rooms.get("three").is_some());
+    }
+
+    fn state_with_linger(empty_room_linger_ms: u64) -> Arc<AppState> {
+        let mut config = crate::config::Config::from_env();
+        config.room.empty_room_linger_ms = empty_room_linger_ms;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let _ = state.insert_test_peer("member");
+        state
+    }
+
+    #[tokio::test]
+    async fn rapid_join_leave_cycling_notifies_once_per_window() {
+        let mut config = crate::config::Config::from_env();
+        config.room.transition_debounce_ms = 60_000;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut watcher = state.insert_test_peer("watcher");
+        let mut flapper = state.insert_test_peer("flapper");
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "watcher", "room", participant, None).await;
+        drain_messages(&mut watcher);
+
+        for _ in 0..5 {
+            handle_join_room(state.clone(), "flapper", "room", participant, None).await;
+            handle_leave_room(state.clone(), "flapper", Some("room")).await;
+        }
+
+        let joined = drain_messages(&mut watcher)
+            .iter()
+            .filter(|message| matches!(message, ServerMessage::PeerJoined { .. }))
+            .count();
+        assert_eq!(joined, 1);
+        assert!(!state
+            .rooms
+            .get("room")
+            .unwrap()
+            .users
+            .read()
+            .await
+            .contains("flapper"));
+        assert!(drain_messages(&mut flapper).iter().any(|message| matches!(
+            message,
+            ServerMessage::Error { code, retry_after_ms: Some(_), .. }
+                if code == "room_transition_too_fast"
+        )));
+    }
+
+    #[tokio::test]
+    async fn debounce_allows_switching_to_another_room() {
+        let mut config = crate::config::Config::from_env();
+        config.room.transition_debounce_ms = 60_000;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut hopper = state.insert_test_peer("hopper");
+        let participant = PeerRole::Participant;
+
+        handle_join_room(state.clone(), "hopper", "a", participant, None).await;
+        handle_join_room(state.clone(), "hopper", "b", participant, None).await;
+        handle_leave_room(state.clone(), "hopper", Some("b")).await;
+        handle_join_room(state.clone(), "hopper", "c", participant, None).await;
+
+        assert!(!drain_messages(&mut hopper).iter().any(|message| matches!(
+            message,
+            ServerMessage::Error { code, .. } if code == "room_transition_too_fast"
+        )));
+        let session = state.peers.get("hopper").unwrap();
+        assert_eq!(session.room_id.read().await.as_deref(), Some("c"));
+        drop(session);
+
+        handle_join_room(state.clone(), "hopper", "b", participant, None).await;
+        assert!(drain_messages(&mut hopper).iter().any(|message| matches!(
+            message,
+            ServerMessage::Error { code, .. } if code == "room_transition_too_fast"
+        )));
+    }
+
+    #[tokio::test]
+    async fn rejoin_within_empty_linger_finds_same_room() {
+        let state = state_with_linger(60_000);
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "member", "room", participant, None).await;
+        let created_at = state.rooms.get("room").unwrap().created_at;
+        leave_room_internal(&state, "member", "room").await;
+
+        assert_eq!(reap_empty_rooms(&state), 0);
+        handle_join_room(state.clone(), "member", "room", participant, None).await;
+
+        let room = state.rooms.get("room").expect("room kept while lingering");
+        assert_eq!(room.created_at, created_at);
+        assert!(room.users.read().await.contains("member"));
+    }
+
+    #[tokio::test]
+    async fn rejoin_after_empty_linger_creates_new_room() {
+        let state = state_with_linger(1_000);
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "member", "room", participant, None).await;
+        let created_at = state.rooms.get("room").unwrap().created_at;
+        leave_room_internal(&state, "member", "room").await;
+        let emptied_at = Instant::now() - Duration::from_millis(1_500);
+        *state
+            .rooms
+            .get("room")
+            .unwrap()
+            .last_activity
+            .lock()
+            .unwrap() = emptied_at;
+
+        assert_eq!(reap_empty_rooms(&state), 1);
+        handle_join_room(state.clone(), "member", "room", participant, None).await;
+
+        let room = state.rooms.get("room").expect("room recreated");
+        assert!(room.created_at > created_at);
+    }
+
+    #[tokio::test]
+    async fn reserved_room_survives_cleanup_and_first_joiner_becomes_owner() {
+        let mut config = crate::config::Config::from_env();
+        config.room.timeout_ms = 0;
+        config.room.closing_grace_ms = 0;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut member = state.insert_test_peer("member");
+        let _later = state.insert_test_peer("later");
+
+        assert!(reserve_room(&state, "room", Duration::from_secs(60)));
+        assert!(!reserve_room(&state, "room", Duration::from_secs(60)));
+        *state
+            .rooms
+            .get("room")
+            .unwrap()
+            .last_activity
+            .lock()
+            .unwrap() = Instant::now() - Duration::from_secs(5);
+        cleanup_old_rooms(state.clone()).await;
+        assert_eq!(reap_empty_rooms(&state), 0);
+        assert!(state.rooms.get("room").unwrap().owner.is_none());
+
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "member", "room", participant, None).await;
+        handle_join_room(state.clone(), "later", "room", participant, None).await;
+
+        let room = state.rooms.get("room").expect("reserved room kept");
+        assert_eq!(room.owner.as_deref(), Some("member"));
+        assert!(room.is_reserved());
+        assert_eq!(room.users.read().await.len(), 2);
+        assert!(drain_messages(&mut member)
+            .iter()
+            .any(|message| matches!(message, ServerMessage::JoinedRoom { created: true, .. })));
+    }
+
+    #[tokio::test]
+    async fn only_reserved_rooms_persist_when_emptied() {
+        let state = Arc::new(AppState::new_for_test());
+        let _a = state.insert_test_peer("a");
+        let _b = state.insert_test_peer("b");
+        let participant = PeerRole::Participant;
+        assert!(reserve_room(&state, "kept", Duration::from_secs(60)));
+
+        handle_join_room(state.clone(), "a", "implicit", participant, None).await;
+        handle_join_room(state.clone(), "b", "kept", participant, None).await;
+        leave_room_internal(&state, "a", "implicit").await;
+        leave_room_internal(&state, "b", "kept").await;
+
+        assert!(state.rooms.get("implicit").is_none());
+        assert!(state.rooms.get("kept").is_some());
+
+        // 보존 기한이 지나면 비는 순간 삭제된다
+        state.rooms.get_mut("kept").unwrap().reserved_until = Some(Instant::now());
+        handle_join_room(state.clone(), "b", "kept", participant, None).await;
+        leave_room_internal(&state, "b", "kept").await;
+        assert!(state.rooms.get("kept").is_none());
+    }
+
+    #[tokio::test]
+    async fn occupied_reserved_room_still_times_out() {
+        let mut config = crate::config::Config::from_env();
+        config.room.timeout_ms = 0;
+        config.room.closing_grace_ms = 0;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let _member = state.insert_test_peer("member");
+        let ttl = Duration::from_secs(60);
+        assert!(reserve_room(&state, "empty", ttl));
+        assert!(reserve_room(&state, "occupied", ttl));
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "member", "occupied", participant, None).await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        cleanup_old_rooms(state.clone()).await;
+
+        assert!(state.rooms.get("empty").is_some());
+        assert!(state.rooms.get("occupied").is_none());
+    }
+
+    async fn room_with_owner(close_on_owner_leave: bool) -> (Arc<AppState>, UnboundedReceiver) {
+        let state = Arc::new(AppState::new_for_test());
+        let _host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let options = RoomOptions {
+            close_on_owner_leave,
+            ..RoomOptions::default()
+        };
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "host", "room", participant, Some(options)).await;
+        handle_join_room(state.clone(), "guest", "room", participant, None).await;
+        drain_messages(&mut guest);
+        (state, guest)
+    }
+
+    #[tokio::test]
+    async fn owner_leaving_closes_room_when_configured() {
+        let (state, mut guest) = room_with_owner(true).await;
+
+        handle_leave_room(state.clone(), "host", None).await;
+
+        assert!(state.rooms.get("room").is_none());
+        assert!(matches!(
+            drain_messages(&mut guest).as_slice(),
+            [ServerMessage::RoomClosed { room_id, reason }]
+                if room_id == "room" && reason == "owner_left"
+        ));
+        let session = state.peers.get("guest").unwrap();
+        assert!(session.room_id.read().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn owner_disconnect_closes_room_when_configured() {
+        let (state, mut guest) = room_with_owner(true).await;
+
+        crate::handlers::handle_disconnect(state.clone(), "host").await;
+
+        assert!(state.rooms.get("room").is_none());
+        assert!(drain_messages(&mut guest)
+            .iter()
+            .any(|message| matches!(message, ServerMessage::RoomClosed { .. })));
+    }
+
+    #[tokio::test]
+    async fn owner_leaving_keeps_room_by_default() {
+        let (state, mut guest) = room_with_owner(false).await;
+
+        handle_leave_room(state.clone(), "host", None).await;
+
+        let room = state.rooms.get("room").expect("room kept");
+        assert!(room.users.read().await.contains("guest"));
+        drop(room);
+        let messages = drain_messages(&mut guest);
+        assert!(messages
+            .iter()
+            .any(|message| matches!(message, ServerMessage::UserLeft { socket_id } if socket_id == "host")));
+        assert!(!messages
+            .iter()
+            .any(|message| matches!(message, ServerMessage::RoomClosed { .. })));
+    }
+
+    #[tokio::test]
+    async fn slow_recipient_does_not_delay_fast_one() {
+        let state = AppState::new_for_test();
+        let slow = state.insert_test_peer("slow");
+        let mut fast = state.insert_test_peer("fast");
+        let room = Room::new("room".to_string());
+        room.users
+            .write()
+            .await
+            .extend(["slow".to_string(), "fast".to_string()]);
+        state.rooms.insert("room".to_string(), room);
+        // slow는 한 번도 읽지 않아 큐가 계속 쌓인다.
+        for _ in 0..10_000 {
+            broadcast_to_room(&state, "room", ServerMessage::HeartbeatAck).await;
+            assert!(matches!(fast.try_recv(), Ok(ServerMessage::HeartbeatAck)));
+        }
+        assert_eq!(slow.len(), 10_000);
+    }
+
+    #[tokio::test]
+    async fn public_room_list_hides_private_rooms() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut lobby = state.insert_test_peer("lobby");
+        let _host = state.insert_test_peer("host");
+        let _hidden = state.insert_test_peer("hidden");
+        let public = RoomOptions {
+            public: true,
+            ..RoomOptions::default()
+        };
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "host", "open", participant, Some(public)).await;
+        handle_join_room(state.clone(), "hidden", "secret", participant, None).await;
+
+        handle_list_public_rooms(state.clone(), "lobby").await;
+
+        match drain_messages(&mut lobby).as_slice() {
+            [ServerMessage::PublicRooms { rooms }] => assert_eq!(
+                rooms,
+                &[PublicRoomInfo {
+                    id: "open".to_string(),
+                    user_count: 1,
+                    max_size: state.config.room.max_size,
+                }]
+            ),
+            other => panic!("unexpected messages: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn public_room_list_is_rate_limited() {
+        let mut config = crate::config::Config::from_env();
+        config.room.list_rate_per_sec = 1;
+        config.room.list_burst = 2;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut lobby = state.insert_test_peer("lobby");
+
+        for _ in 0..3 {
+            handle_list_public_rooms(state.clone(), "lobby").await;
+        }
+
+        match drain_messages(&mut lobby).as_slice() {
+            [ServerMessage::PublicRooms { .. }, ServerMessage::PublicRooms { .. }, ServerMessage::Error {
+                code,
+                retry_after_ms: Some(_),
+                ..
+            }] => assert_eq!(code, "rate_limited"),
+            other => panic!("unexpected messages: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn resync_sends_full_list_to_members_only() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut member = state.insert_test_peer("member");
+        let _other = state.insert_test_peer("other");
+        let mut outsider = state.insert_test_peer("outsider");
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "member", "room", participant, None).await;
+        handle_join_room(state.clone(), "other", "room", participant, None).await;
+        drain_messages(&mut member);
+
+        let context = RequestContext::default();
+        handle_resync_room(state.clone(), "member", "room", &context).await;
+        handle_resync_room(state.clone(), "outsider", "room", &context).await;
+
+        assert!(matches!(
+            drain_messages(&mut member).as_slice(),
+            [ServerMessage::RoomUsers { users, .. }] if users == &["other".to_string()]
+        ));
+        assert!(matches!(
+            drain_messages(&mut outsider).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "not_in_room"
+        ));
+    }
+
+    #[tokio::test]
+    async fn join_and_resync_replies_echo_correlation_id() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut member = state.insert_test_peer("member");
+        let correlation = |id: &str| RequestContext {
+            correlation_id: Some(id.to_string()),
+        };
+        let participant = PeerRole::Participant;
+        let join = correlation("join-1");
+        handle_join_room_with(state.clone(), "member", "room", participant, None, &join).await;
+        handle_resync_room(state.clone(), "member", "room", &correlation("resync-2")).await;
+
+        let messages = drain_messages(&mut member);
+        assert!(messages.iter().any(|message| matches!(
+            message,
+            ServerMessage::JoinedRoom { correlation_id, .. } if correlation_id.as_deref() == Some("join-1")
+        )));
+        assert!(matches!(
+            messages.last(),
+            Some(ServerMessage::RoomUsers { correlation_id, .. })
+                if correlation_id.as_deref() == Some("resync-2")
+        ));
+        // 입장 시 자동으로 보내는 RoomUsers에는 붙지 않는다
+        assert!(matches!(
+            messages.first(),
+            Some(ServerMessage::RoomUsers {
+                correlation_id: None,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejected_join_and_resync_echo_correlation_id() {
+        let mut config = crate::config::Config::from_env();
+        config.room.max_size = 1;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let _host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let correlation = |id: &str| RequestContext {
+            correlation_id: Some(id.to_string()),
+        };
+        let participant = PeerRole::Participant;
+        handle_join_room(state.clone(), "host", "room", participant, None).await;
+
+        let join = correlation("join-1");
+        handle_join_room_with(state.clone(), "guest", "room", participant, None, &join).await;
+        handle_resync_room(state.clone(), "guest", "room", &correlation("resync-2")).await;
+
+        assert!(matches!(
+            drain_messages(&mut guest).as_slice(),
+            [
+                ServerMessage::RoomFull { correlation_id: full, .. },
+                ServerMessage::Error { code, correlation_id: error, .. },
+            ] if full.as_deref() == Some("join-1")
+                && code == "not_in_room"
+                && error.as_deref() == Some("resync-2")
+        ));
+    }
+
+    #[tokio::test]
+    async fn room_owner_is_visible_to_members_only() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let mut outsider = state.insert_test_peer("outsider");
+        for peer_id in ["host", "guest"] {
+            handle_join_room(state.clone(), peer_id, "room", PeerRole::Participant, None).await;
+        }
+        drain_messages(&mut host);
+        drain_messages(&mut guest);
+
+        handle_get_room_owner(state.clone(), "guest", "room").await;
+        handle_get_room_owner(state.clone(), "outsider", "room").await;
+
+        assert!(matches!(
+            drain_messages(&mut guest).as_slice(),
+            [ServerMessage::RoomOwner { room_id, owner: Some(owner) }]
+                if room_id == "room" && owner == "host"
+        ));
+        assert!(matches!(
+            drain_messages(&mut outsider).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "not_in_room"
+        ));
+    }
+
+    fn state_with_history(history_size: usize) -> Arc<AppState> {
+        let mut config = crate::config::Config::from_env();
+        config.room.history_size = history_size;
+        Arc::new(AppState::new_for_test_with_config(config))
+    }
+
+    #[tokio::test]
+    async fn room_history_is_capped_to_configured_size() {
+        let state = state_with_history(2);
+        for peer in ["a", "b", "c"] {
+            let _rx = state.insert_test_peer(peer);
+            handle_join_room(state.clone(), peer, "room", PeerRole::Participant, None).await;
+        }
+
+        let room = state.rooms.get("room").expect("room exists");
+        let history: Vec<RoomEvent> = room.history.read().await.iter().cloned().collect();
+        let joined: Vec<&str> = history
+            .iter()
+            .map(|event| match event {
+                RoomEvent::PeerJoined { socket_id, .. } => socket_id.as_str(),
+                other => panic!("unexpected event: {other:?}"),
+            })
+            .collect();
+        assert_eq!(joined, vec!["b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn room_history_is_replayed_to_late_joiner() {
+        let state = state_with_history(10);
+        let _a = state.insert_test_peer("a");
+        let _b = state.insert_test_peer("b");
+        handle_join_room(state.clone(), "a", "room", PeerRole::Participant, None).await;
+        handle_join_room(state.clone(), "b", "room", PeerRole::Participant, None).await;
+        handle_leave_room(state.clone(), "b", None).await;
+
+        let mut late = state.insert_test_peer("late");
+        handle_join_room(state.clone(), "late", "room", PeerRole::Participant, None).await;
+
+        let history = drain_messages(&mut late)
+            .into_iter()
+            .find_map(|message| match message {
+                ServerMessage::RoomHistory { events } => Some(events),
+                _ => None,
+            })
+            .expect("late joiner receives history");
+        assert!(matches!(
+            history.as_slice(),
+            [
+                RoomEvent::PeerJoined { socket_id: first, .. },
+                RoomEvent::PeerJoined { socket_id: second, .. },
+                RoomEvent::UserLeft { socket_id: left, .. },
+            ] if first == "a" && second == "b" && left == "b"
+        ));
+    }
+
+    #[tokio::test]
+    async fn room_history_is_not_sent_when_disabled() {
+        let state = state_with_history(0);
+        let mut a = state.insert_test_peer("a");
+        handle_join_room(state.clone(), "a", "room", PeerRole::Participant, None).await;
+
+        assert!(drain_messages(&mut a)
+            .iter()
+            .all(|message| !matches!(message, ServerMessage::RoomHistory { .. })));
+    }
+
+    #[tokio::test]
+    async fn observers_do_not_count_toward_room_capacity() {
+        let mut config = crate::config::Config::from_env();
+        config.room.max_size = 1;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let _a = state.insert_test_peer("a");
+        let mut watcher = state.insert_test_peer("watcher");
+        let mut b = state.insert_test_peer("b");
+
+        handle_join_room(state.clone(), "a", "room", PeerRole::Participant, None).await;
+        handle_join_room(state.clone(), "watcher", "room", PeerRole::Observer, None).await;
+        handle_join_room(state.clone(), "b", "room", PeerRole::Participant, None).await;
+
+        assert!(drain_messages(&mut watcher)
+            .iter()
+            .any(|message| matches!(message, ServerMessage::JoinedRoom { .. })));
+        assert!(matches!(
+            drain_messages(&mut b).as_slice(),
+            [ServerMessage::RoomFull { .. }]
+        ));
+        let room = state.rooms.get("room").expect("room exists");
+        assert_eq!(room.users.read().await.len(), 2);
+        drop(room);
+
+        // 관전자가 참여자로 다시 입장해도 인원 제한을 받는다
+        handle_join_room(
+            state.clone(),
+            "watcher",
+            "room",
+            PeerRole::Participant,
+            None,
+        )
+        .await;
+        assert!(matches!(
+            drain_messages(&mut watcher).as_slice(),
+            [ServerMessage::RoomFull { .. }]
+        ));
+        let watcher_role = *state.peers.get("watcher").unwrap().role.read().await;
+        assert_eq!(watcher_role, PeerRole::Observer);
+    }
+
+    #[tokio::test]
+    async fn joins_beyond_per_ip_limit_are_rejected() {
+        let mut config = crate::config::Config::from_env();
+        config.room.max_per_ip = 2;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let shared_ip: std::net::IpAddr = "203.0.113.7".parse().unwrap();
+
+        let mut receivers = Vec::new();
+        let mut peers = Vec::new();
+        for _ in 0..3 {
+            let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+            peers.push(
+                crate::handlers::handle_connection(state.clone(), tx, None, Some(shared_ip), None)
+                    .await,
+            );
+            receivers.push(rx);
+        }
+        let (tx, mut other_rx) = tokio::sync::mpsc::unbounded_channel();
+        let other = crate::handlers::handle_connection(
+            state.clone(),
+            tx,
+            None,
+            Some("198.51.100.1".parse().unwrap()),
+            None,
+        )
+        .await;
+
+        for peer in peers.iter().chain([&other]) {
+            handle_join_room(state.clone(), peer, "room", PeerRole::Participant, None).await;
+        }
+
+        assert!(matches!(
+            drain_messages(&mut receivers[2]).as_slice(),
+            [ServerMessage::Connected { .. }, ServerMessage::Error { code, .. }] if code == "ip_limit"
+        ));
+        assert!(drain_messages(&mut other_rx)
+            .iter()
+            .any(|message| matches!(message, ServerMessage::JoinedRoom { .. })));
+        let room = state.rooms.get("room").expect("room exists");
+        let users = room.users.read().await;
+        assert_eq!(users.len(), 3);
+        assert!(!users.contains(&peers[2]));
+    }
+
+    fn not_in_room(messages: &[ServerMessage]) -> bool {
+        matches!(messages, [ServerMessage::Error { code, .. }] if code == "not_in_room")
+    }
+
+    #[tokio::test]
+    async fn double_leave_is_idempotent_and_reports_not_in_room() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+        handle_join_room(state.clone(), "a", "room", PeerRole::Participant, None).await;
+        handle_join_room(state.clone(), "b", "room", PeerRole::Participant, None).await;
+        drain_messages(&mut a);
+
+        handle_leave_room(state.clone(), "b", Some("room")).await;
+        assert!(drain_messages(&mut a).iter().any(
+            |message| matches!(message, ServerMessage::UserLeft { socket_id } if socket_id == "b")
+        ));
+        drain_messages(&mut b);
+
+        handle_leave_room(state.clone(), "b", Some("room")).await;
+        assert!(not_in_room(&drain_messages(&mut b)));
+        assert!(drain_messages(&mut a).is_empty());
+        let room = state.rooms.get("room").expect("room kept");
+        assert_eq!(room.users.read().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn leave_without_join_reports_not_in_room() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut a = state.insert_test_peer("a");
+        let _b = state.insert_test_peer("b");
+        handle_join_room(state.clone(), "b", "other", PeerRole::Participant, None).await;
+
+        handle_leave_room(state.clone(), "a", None).await;
+        assert!(not_in_room(&drain_messages(&mut a)));
+
+        handle_leave_room(state.clone(), "a", Some("other")).await;
+        assert!(not_in_room(&drain_messages(&mut a)));
+        let room = state.rooms.get("other").expect("room kept");
+        assert!(room.users.read().await.contains("b"));
+    }
+
+    #[tokio::test]
+    async fn ping_room_returns_none_for_unknown_room() {
+        let state = AppState::new_for_test();
+        assert!(ping_room(&state, "missing").await.is_none());
     }
 }
@@ -1,8 +1,28 @@
 //! WebRTC 시그널링 핸들러
 
-use crate::protocol::ServerMessage;
-use crate::state::AppState;
+use crate::cluster;
+use crate::config::{SIGNALING_LOG_TARGET, TRANSFER_LOG_TARGET};
+use crate::protocol::{sdp, IceCandidateInput, PeerRole, ServerMessage};
+use crate::state::{AppState, ManifestAssembly, Room};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 청크 manifest 한 건이 가질 수 있는 최대 청크 수
+const MAX_MANIFEST_CHUNKS: u32 = 4096;
+
+/// 청크 하나를 반영한 결과
+enum ChunkOutcome {
+    Pending,
+    Relay {
+        chunks: Vec<(u32, String)>,
+        finished: bool,
+    },
+    Complete(String),
+    Rejected {
+        code: &'static str,
+        message: &'static str,
+    },
+}
 
 /// Offer 처리
 pub async fn handle_offer(
@@ -11,19 +31,73 @@ pub async fn handle_offer(
     room_id: &str,
     sdp: &str,
     target: Option<&str>,
+    generation: Option<u64>,
 ) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
+    if !check_signaling_target(&state, from_peer_id, target) {
+        return;
+    }
+    let may_offer = state
+        .rooms
+        .get(room_id)
+        .is_none_or(|room| room.may_offer(from_peer_id));
+    if !may_offer {
+        send_error(
+            &state,
+            from_peer_id,
+            "offer_not_allowed",
+            "Only the room host may send offers",
+        );
+        tracing::debug!(target: SIGNALING_LOG_TARGET, from = %from_peer_id, room_id = %room_id, "Dropped non-host offer");
+        return;
+    }
+    if target.is_some_and(|target_id| too_many_pending_offers(&state, from_peer_id, target_id)) {
+        send_error(
+            &state,
+            from_peer_id,
+            "too_many_pending_offers",
+            "Too many offers are waiting for an answer",
+        );
+        tracing::debug!(target: SIGNALING_LOG_TARGET, from = %from_peer_id, "Dropped offer over pending limit");
+        return;
+    }
+    let glare = target.is_some_and(|target_id| track_offer(&state, from_peer_id, target_id));
+    if glare {
+        tracing::info!(
+            target: SIGNALING_LOG_TARGET,
+            from = %from_peer_id,
+            target = ?target,
+            "Offer glare detected"
+        );
+    }
+    // 방 전체 Offer는 쌍이 없으므로 클라이언트 값을 그대로 전달한다
+    let generation = match target {
+        Some(target_id) => Some(next_offer_generation(
+            &state,
+            from_peer_id,
+            target_id,
+            generation,
+        )),
+        None => generation,
+    };
+    let sdp = sdp::apply_codec_preference(sdp, &state.config.signaling.sdp_codec_preference);
     let message = ServerMessage::Offer {
         from: from_peer_id.to_string(),
-        sdp: sdp.to_string(),
+        sdp: sdp.into_owned(),
+        glare,
+        generation,
     };
 
     if let Some(target_id) = target {
-        send_to_peer(&state, target_id, message).await;
+        send_to_peer(&state, from_peer_id, room_id, target_id, message).await;
     } else {
         broadcast_to_room_except(&state, room_id, from_peer_id, message).await;
     }
 
     tracing::debug!(
+        target: SIGNALING_LOG_TARGET,
         from = %from_peer_id,
         room_id = %room_id,
         target = ?target,
@@ -38,19 +112,38 @@ pub async fn handle_answer(
     room_id: &str,
     sdp: &str,
     target: Option<&str>,
+    mut generation: Option<u64>,
 ) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
+    if !check_signaling_target(&state, from_peer_id, target) {
+        return;
+    }
+    if let Some(target_id) = target {
+        // target이 보낸 Offer에 대한 응답이므로 대기 목록에서 지운다.
+        let offerer = state
+            .resolve_peer_id(target_id)
+            .unwrap_or_else(|| target_id.to_string());
+        let pair = (offerer, from_peer_id.to_string());
+        state.pending_offers.remove(&pair);
+        generation = generation.or_else(|| state.offer_generations.get(&pair).map(|g| *g));
+    }
+    let sdp = sdp::apply_codec_preference(sdp, &state.config.signaling.sdp_codec_preference);
     let message = ServerMessage::Answer {
         from: from_peer_id.to_string(),
-        sdp: sdp.to_string(),
+        sdp: sdp.into_owned(),
+        generation,
     };
 
     if let Some(target_id) = target {
-        send_to_peer(&state, target_id, message).await;
+        send_to_peer(&state, from_peer_id, room_id, target_id, message).await;
     } else {
         broadcast_to_room_except(&state, room_id, from_peer_id, message).await;
     }
 
     tracing::debug!(
+        target: SIGNALING_LOG_TARGET,
         from = %from_peer_id,
         room_id = %room_id,
         target = ?target,
@@ -59,25 +152,48 @@ pub async fn handle_answer(
 }
 
 /// ICE Candidate 처리
+///
+/// 한 피어의 메시지는 수신 루프(또는 수신 큐의 시그널링 lane)에서 하나씩 처리되고
+/// 여기서 태스크를 띄우지 않으므로, 같은 피어의 candidate는 보낸 순서대로 도착한다.
 pub async fn handle_ice_candidate(
     state: Arc<AppState>,
     from_peer_id: &str,
     room_id: &str,
-    candidate: &str,
+    candidate: &IceCandidateInput,
     target: Option<&str>,
 ) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
+    if !check_signaling_target(&state, from_peer_id, target) {
+        return;
+    }
+    let candidate = match candidate.normalize() {
+        Ok(candidate) => candidate,
+        Err(reason) => {
+            send_error(&state, from_peer_id, "invalid_candidate", reason);
+            tracing::debug!(
+                target: SIGNALING_LOG_TARGET,
+                from = %from_peer_id,
+                room_id = %room_id,
+                "Rejected ICE candidate"
+            );
+            return;
+        }
+    };
     let message = ServerMessage::IceCandidate {
         from: from_peer_id.to_string(),
-        candidate: candidate.to_string(),
+        candidate,
     };
 
     if let Some(target_id) = target {
-        send_to_peer(&state, target_id, message).await;
+        send_to_peer(&state, from_peer_id, room_id, target_id, message).await;
     } else {
         broadcast_to_room_except(&state, room_id, from_peer_id, message).await;
     }
 
     tracing::debug!(
+        target: SIGNALING_LOG_TARGET,
         from = %from_peer_id,
         room_id = %room_id,
         target = ?target,
@@ -85,6 +201,252 @@ pub async fn handle_ice_candidate(
     );
 }
 
+/// IceRestart 처리. 중복/자기 자신을 뺀 targets에게만 전달한다.
+pub async fn handle_ice_restart(
+    state: Arc<AppState>,
+    from_peer_id: &str,
+    room_id: &str,
+    targets: &[String],
+) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
+    // 일부만 보내지 않도록 한도를 넘으면 아무에게도 전달하지 않는다 (MAX_RELAY_TARGETS)
+    let max_targets = state.config.signaling.max_relay_targets;
+    if max_targets > 0 && targets.len() > max_targets {
+        send_error(
+            &state,
+            from_peer_id,
+            "too_many_targets",
+            &format!("At most {max_targets} targets are allowed"),
+        );
+        return;
+    }
+    let mut recipients: Vec<&str> = targets
+        .iter()
+        .map(|target| target.trim())
+        .filter(|target| !target.is_empty() && *target != from_peer_id)
+        .collect();
+    recipients.sort_unstable();
+    recipients.dedup();
+    if recipients.is_empty() {
+        send_error(
+            &state,
+            from_peer_id,
+            "target_required",
+            "IceRestart must list at least one target",
+        );
+        return;
+    }
+
+    for target_id in &recipients {
+        let message = ServerMessage::IceRestart {
+            from: from_peer_id.to_string(),
+        };
+        send_to_peer(&state, from_peer_id, room_id, target_id, message).await;
+    }
+
+    tracing::debug!(
+        target: SIGNALING_LOG_TARGET,
+        from = %from_peer_id,
+        room_id = %room_id,
+        targets = ?recipients,
+        "Relayed ICE restart"
+    );
+}
+
+/// RelayToRole 처리. 보낸 피어를 뺀 방 멤버 중 `role` 역할인 피어에게만 전달한다.
+pub async fn handle_relay_to_role(
+    state: Arc<AppState>,
+    from_peer_id: &str,
+    room_id: &str,
+    role: PeerRole,
+    event: String,
+    data: serde_json::Value,
+) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
+    if contains_blocked_content(&state, &data) {
+        send_error(
+            &state,
+            from_peer_id,
+            "content_blocked",
+            "Relay data contains blocked content",
+        );
+        return;
+    }
+    let metric_event = event.clone();
+    let message = ServerMessage::RelayToRole {
+        from: from_peer_id.to_string(),
+        role,
+        event,
+        data,
+    };
+    // 방 멤버가 아니면 broadcast_to_members가 not_in_room으로 거부한다
+    let audience = Audience::Role(role);
+    if !broadcast_to_members(&state, room_id, from_peer_id, audience, message).await {
+        return;
+    }
+    let allowed = &state.config.signaling.relay_metric_events;
+    state.metrics.relay.record_event(&metric_event, allowed);
+
+    tracing::debug!(
+        target: SIGNALING_LOG_TARGET,
+        from = %from_peer_id,
+        room_id = %room_id,
+        role = ?role,
+        "Relayed message to role"
+    );
+}
+
+/// RelayToCapable 처리. 보낸 피어를 뺀 방 멤버 중 `capability`를 알린 피어에게만 전달한다.
+pub async fn handle_relay_to_capable(
+    state: Arc<AppState>,
+    from_peer_id: &str,
+    room_id: &str,
+    capability: String,
+    event: String,
+    data: serde_json::Value,
+) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
+    if contains_blocked_content(&state, &data) {
+        send_error(
+            &state,
+            from_peer_id,
+            "content_blocked",
+            "Relay data contains blocked content",
+        );
+        return;
+    }
+    let metric_event = event.clone();
+    let message = ServerMessage::RelayToCapable {
+        from: from_peer_id.to_string(),
+        capability: capability.clone(),
+        event,
+        data,
+    };
+    let audience = Audience::Capable(&capability);
+    if !broadcast_to_members(&state, room_id, from_peer_id, audience, message).await {
+        return;
+    }
+    let allowed = &state.config.signaling.relay_metric_events;
+    state.metrics.relay.record_event(&metric_event, allowed);
+
+    tracing::debug!(
+        target: SIGNALING_LOG_TARGET,
+        from = %from_peer_id,
+        room_id = %room_id,
+        capability = %capability,
+        "Relayed message to capable peers"
+    );
+}
+
+/// data 안의 문자열 값(중첩 포함)이 RELAY_CONTENT_BLOCKLIST에 걸리는지
+fn contains_blocked_content(state: &AppState, data: &serde_json::Value) -> bool {
+    let Some(blocklist) = &state.content_blocklist else {
+        return false;
+    };
+    let mut pending = vec![data];
+    while let Some(value) = pending.pop() {
+        match value {
+            serde_json::Value::String(text) if blocklist.is_match(text) => return true,
+            serde_json::Value::Array(items) => pending.extend(items),
+            serde_json::Value::Object(fields) => pending.extend(fields.values()),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// BinaryRelay 처리. 같은 방의 target에게만 전달하며 BINARY_RELAY_MAX_BYTES를 넘으면 거부한다.
+pub async fn handle_binary_relay(
+    state: Arc<AppState>,
+    from_peer_id: &str,
+    room_id: &str,
+    target: &str,
+    data: Vec<u8>,
+) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
+    let max_bytes = state.config.signaling.binary_relay_max_bytes;
+    if data.len() > max_bytes {
+        send_error(
+            &state,
+            from_peer_id,
+            "binary_too_large",
+            &format!("Binary relay data exceeds {max_bytes} bytes"),
+        );
+        return;
+    }
+    let target = target.trim();
+    if target.is_empty() {
+        send_error(
+            &state,
+            from_peer_id,
+            "target_required",
+            "BinaryRelay must specify a target",
+        );
+        return;
+    }
+
+    let bytes = data.len();
+    let message = ServerMessage::BinaryRelay {
+        from: from_peer_id.to_string(),
+        data,
+    };
+    send_to_peer(&state, from_peer_id, room_id, target, message).await;
+
+    tracing::debug!(
+        target: SIGNALING_LOG_TARGET,
+        from = %from_peer_id,
+        room_id = %room_id,
+        target = %target,
+        bytes = bytes,
+        "Relayed binary data"
+    );
+}
+
+/// IdentityAssertion 처리. 대역 외 신원 확인 데이터를 같은 방의 target에게만 그대로 전달한다.
+pub async fn handle_identity_assertion(
+    state: Arc<AppState>,
+    from_peer_id: &str,
+    room_id: &str,
+    target: &str,
+    assertion: String,
+) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
+    let target = target.trim();
+    if target.is_empty() {
+        send_error(
+            &state,
+            from_peer_id,
+            "target_required",
+            "IdentityAssertion must specify a target",
+        );
+        return;
+    }
+
+    let message = ServerMessage::IdentityAssertion {
+        from: from_peer_id.to_string(),
+        assertion,
+    };
+    send_to_peer(&state, from_peer_id, room_id, target, message).await;
+
+    tracing::debug!(
+        target: SIGNALING_LOG_TARGET,
+        from = %from_peer_id,
+        room_id = %room_id,
+        target = %target,
+        "Relayed identity assertion"
+    );
+}
+
 /// Manifest 처리 (Native QUIC 모드용)
 pub async fn handle_manifest(
     state: Arc<AppState>,
@@ -93,18 +455,22 @@ pub async fn handle_manifest(
     manifest: &str,
     target: Option<&str>,
 ) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
     let message = ServerMessage::Manifest {
         from: from_peer_id.to_string(),
         manifest: manifest.to_string(),
     };
 
     if let Some(target_id) = target {
-        send_to_peer(&state, target_id, message).await;
+        send_to_peer(&state, from_peer_id, room_id, target_id, message).await;
     } else {
         broadcast_to_room_except(&state, room_id, from_peer_id, message).await;
     }
 
     tracing::info!(
+        target: TRANSFER_LOG_TARGET,
         from = %from_peer_id,
         room_id = %room_id,
         target = ?target,
@@ -112,6 +478,182 @@ pub async fn handle_manifest(
     );
 }
 
+/// ManifestChunk 처리
+///
+/// 청크는 chunk_index 순서대로만 중계되며, 앞 번호보다 먼저 도착한 청크는 보관했다가 함께 보낸다.
+/// MANIFEST_REASSEMBLE=true이면 모든 청크가 모인 뒤 하나의 Manifest로 전달한다.
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_manifest_chunk(
+    state: Arc<AppState>,
+    from_peer_id: &str,
+    room_id: &str,
+    target: Option<&str>,
+    chunk_index: u32,
+    total_chunks: u32,
+    data: &str,
+) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
+    if total_chunks == 0 || total_chunks > MAX_MANIFEST_CHUNKS || chunk_index >= total_chunks {
+        send_error(
+            &state,
+            from_peer_id,
+            "invalid_manifest_chunk",
+            "chunk_index must be below total_chunks",
+        );
+        return;
+    }
+
+    let config = &state.config.signaling;
+    let timeout = Duration::from_millis(config.manifest_chunk_timeout_ms);
+    let key = (
+        from_peer_id.to_string(),
+        target.unwrap_or(room_id).to_string(),
+    );
+
+    // DashMap guard는 await 이전에 반드시 해제한다.
+    let outcome = {
+        let mut assembly = state
+            .manifest_chunks
+            .entry(key.clone())
+            .or_insert_with(|| ManifestAssembly::new(room_id.to_string(), total_chunks));
+        let index = chunk_index as usize;
+
+        if assembly.started_at.elapsed() > timeout {
+            ChunkOutcome::Rejected {
+                code: "manifest_chunk_timeout",
+                message: "Manifest chunks did not complete in time",
+            }
+        } else if assembly.total_chunks != total_chunks {
+            ChunkOutcome::Rejected {
+                code: "invalid_manifest_chunk",
+                message: "total_chunks changed mid-transfer",
+            }
+        } else if chunk_index < assembly.next_index || assembly.chunks[index].is_some() {
+            // 중복 청크는 무시
+            ChunkOutcome::Pending
+        } else if assembly.received_bytes + data.len() > config.manifest_max_bytes {
+            ChunkOutcome::Rejected {
+                code: "manifest_too_large",
+                message: "Manifest exceeds the maximum size",
+            }
+        } else {
+            assembly.received_bytes += data.len();
+            assembly.chunks[index] = Some(data.to_string());
+
+            if config.manifest_reassemble {
+                if assembly.chunks.iter().all(Option::is_some) {
+                    ChunkOutcome::Complete(
+                        assembly.chunks.iter_mut().flat_map(Option::take).collect(),
+                    )
+                } else {
+                    ChunkOutcome::Pending
+                }
+            } else {
+                let mut chunks = Vec::new();
+                while assembly.next_index < assembly.total_chunks {
+                    let next = assembly.next_index;
+                    let Some(chunk) = assembly.chunks[next as usize].take() else {
+                        break;
+                    };
+                    chunks.push((next, chunk));
+                    assembly.next_index += 1;
+                }
+                if chunks.is_empty() {
+                    ChunkOutcome::Pending
+                } else {
+                    ChunkOutcome::Relay {
+                        chunks,
+                        finished: assembly.next_index == assembly.total_chunks,
+                    }
+                }
+            }
+        }
+    };
+
+    match outcome {
+        ChunkOutcome::Pending => {}
+        ChunkOutcome::Rejected { code, message } => {
+            state.manifest_chunks.remove(&key);
+            send_error(&state, from_peer_id, code, message);
+            tracing::warn!(
+                target: TRANSFER_LOG_TARGET,
+                from = %from_peer_id,
+                room_id = %room_id,
+                code,
+                "Dropped manifest chunks"
+            );
+        }
+        ChunkOutcome::Complete(manifest) => {
+            state.manifest_chunks.remove(&key);
+            let message = ServerMessage::Manifest {
+                from: from_peer_id.to_string(),
+                manifest,
+            };
+            if let Some(target_id) = target {
+                send_to_peer(&state, from_peer_id, room_id, target_id, message).await;
+            } else {
+                broadcast_to_room_except(&state, room_id, from_peer_id, message).await;
+            }
+            tracing::info!(
+                target: TRANSFER_LOG_TARGET,
+                from = %from_peer_id,
+                room_id = %room_id,
+                "Relayed reassembled manifest"
+            );
+        }
+        ChunkOutcome::Relay { chunks, finished } => {
+            if finished {
+                state.manifest_chunks.remove(&key);
+            }
+            for (chunk_index, data) in chunks {
+                let message = ServerMessage::ManifestChunk {
+                    from: from_peer_id.to_string(),
+                    chunk_index,
+                    total_chunks,
+                    data,
+                };
+                if let Some(target_id) = target {
+                    send_to_peer(&state, from_peer_id, room_id, target_id, message).await;
+                } else {
+                    broadcast_to_room_except(&state, room_id, from_peer_id, message).await;
+                }
+            }
+        }
+    }
+}
+
+/// 시간 안에 완성되지 않은 청크 manifest 정리
+pub fn cleanup_stale_manifest_chunks(state: &AppState) {
+    let timeout = Duration::from_millis(state.config.signaling.manifest_chunk_timeout_ms);
+    let mut expired = Vec::new();
+
+    state.manifest_chunks.retain(|(from, _), assembly| {
+        if assembly.started_at.elapsed() > timeout {
+            expired.push((from.clone(), assembly.room_id.clone()));
+            false
+        } else {
+            true
+        }
+    });
+
+    for (from, room_id) in expired {
+        send_error(
+            state,
+            &from,
+            "manifest_chunk_timeout",
+            "Manifest chunks did not complete in time",
+        );
+        tracing::warn!(
+            target: TRANSFER_LOG_TARGET,
+            from = %from,
+            room_id = %room_id,
+            "Expired incomplete manifest chunks"
+        );
+    }
+}
+
 /// 🆕 TransferReady 처리 (Receiver -> Sender)
 pub async fn handle_transfer_ready(
     state: Arc<AppState>,
@@ -119,17 +661,21 @@ pub async fn handle_transfer_ready(
     room_id: &str,
     target: Option<&str>,
 ) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
     let message = ServerMessage::TransferReady {
         from: from_peer_id.to_string(),
     };
 
     if let Some(target_id) = target {
-        send_to_peer(&state, target_id, message).await;
+        send_to_peer(&state, from_peer_id, room_id, target_id, message).await;
     } else {
         broadcast_to_room_except(&state, room_id, from_peer_id, message).await;
     }
 
     tracing::info!(
+        target: TRANSFER_LOG_TARGET,
         from = %from_peer_id,
         room_id = %room_id,
         target = ?target,
@@ -145,6 +691,9 @@ pub async fn handle_transfer_complete(
     room_id: &str,
     target: Option<&str>,
 ) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
     // 🚀 [고속 중계] 불필요한 로깅 최소화로 지연 감소
     // tracing::debug!(
     //     from = %from_peer_id,
@@ -159,39 +708,39 @@ pub async fn handle_transfer_complete(
 
     // 🚀 [고속 중계] 즉시 전송 - 타겟이 명시된 경우 직접 전송
     if let Some(target_id) = target {
+        if reject_self_target(&state, from_peer_id, target_id) {
+            return;
+        }
         // 🚀 [고속 중계] 비동기 전송으로 블로킹 방지
-        if let Some(peer_session) = state.peers.get(target_id) {
-            let forward_msg = ServerMessage::TransferComplete {
-                from: from_peer_id.to_string(),
-            };
-
+        let resolved = state.resolve_peer_id(target_id);
+        if let Some(target_peer_id) = &resolved {
+            if !check_same_room(&state, from_peer_id, room_id, target_peer_id).await {
+                return;
+            }
+        }
+        if let Some(peer_session) = resolved.and_then(|peer_id| state.peers.get(&peer_id)) {
             // 🚀 [고속 중계] send로 블로킹 없이 전송 시도
             // UnboundedSender는 블로킹하지 않으므로 try_send 대신 send 사용
-            if let Err(e) = peer_session.sender.send(forward_msg) {
-                tracing::warn!("Failed to send transfer complete to {}: {}", target_id, e);
-            } else {
-                tracing::info!(
-                    from = %from_peer_id,
-                    to = %target_id,
-                    "Transfer complete relayed (fast track)"
+            if let Err(e) = peer_session.sender.send(message) {
+                tracing::warn!(
+                    target: TRANSFER_LOG_TARGET,
+                    "Failed to send transfer complete to {}: {}",
+                    target_id,
+                    e
                 );
             }
+        } else {
+            cluster::publish_target(&state, room_id, target_id, message).await;
         }
     } else {
-        // 🚀 [고속 중계] 브로드캐스트는 비동기로 처리
-        // 라이프타임 문제를 해결하기 위해 문자열을 소유권으로 복제
-        let room_id_owned = room_id.to_string();
-        let from_peer_id_owned = from_peer_id.to_string();
-        let state_clone = state.clone();
-
-        tokio::spawn(async move {
-            broadcast_to_room_except(&state_clone, &room_id_owned, &from_peer_id_owned, message)
-                .await;
-        });
+        // 브로드캐스트도 제자리에서 처리한다. 채널 전송은 블로킹하지 않으며,
+        // 별도 태스크로 넘기면 같은 피어가 뒤이어 보낸 메시지가 먼저 도착할 수 있다.
+        broadcast_to_room_except(&state, room_id, from_peer_id, message).await;
     }
 
-    // 🚀 [고속 중계] 완료 신호는 즉시 처리해야 하므로 로깅 최소화
+    // 🚀 [고속 중계] 완료 신호는 즉시 처리해야 하므로 로깅은 한 번만
     tracing::info!(
+        target: TRANSFER_LOG_TARGET,
         from = %from_peer_id,
         room_id = %room_id,
         target = ?target,
@@ -199,11 +748,344 @@ pub async fn handle_transfer_complete(
     );
 }
 
-/// 특정 피어에게 메시지 전송
-async fn send_to_peer(state: &AppState, peer_id: &str, message: ServerMessage) {
+/// TransferProgress 처리. 보내는 피어당 TRANSFER_PROGRESS_RATE를 넘는 갱신은 조용히 버린다.
+/// 완료(100%) 갱신은 마지막 상태가 되므로 같은 방·target의 전송마다 한 번은 한도와 관계없이 전달한다.
+pub async fn handle_transfer_progress(
+    state: Arc<AppState>,
+    from_peer_id: &str,
+    room_id: &str,
+    target: Option<&str>,
+    percent: f32,
+    bytes: u64,
+) {
+    if !ensure_can_send(&state, from_peer_id).await {
+        return;
+    }
+    if !percent.is_finite() {
+        send_error(
+            &state,
+            from_peer_id,
+            "invalid_progress",
+            "Transfer progress percent must be a number",
+        );
+        return;
+    }
+    let percent = percent.clamp(0.0, 100.0);
+    let exempt = percent >= 100.0 && first_completion(&state, from_peer_id, room_id, target);
+    if !exempt && progress_throttled(&state, from_peer_id) {
+        tracing::trace!(target: TRANSFER_LOG_TARGET, from = %from_peer_id, "Dropped throttled transfer progress");
+        return;
+    }
+
+    if percent < 100.0 {
+        // 진행 중인 갱신이 전달되면 다음 완료를 다시 한도 없이 보낼 수 있다
+        if let Some(session) = state.peers.get(from_peer_id) {
+            let key = (room_id.to_string(), target.map(str::to_string));
+            session.completed_transfers.lock().unwrap().remove(&key);
+        }
+    }
+
+    let message = ServerMessage::TransferProgress {
+        from: from_peer_id.to_string(),
+        percent,
+        bytes,
+    };
+    if let Some(target_id) = target {
+        send_to_peer(&state, from_peer_id, room_id, target_id, message).await;
+    } else {
+        broadcast_to_room_except(&state, room_id, from_peer_id, message).await;
+    }
+
+    tracing::debug!(
+        target: TRANSFER_LOG_TARGET,
+        from = %from_peer_id,
+        room_id = %room_id,
+        target = ?target,
+        percent = percent,
+        "Relayed transfer progress"
+    );
+}
+
+/// 완료 갱신으로 한도를 건너뛴 전송 수 상한. 넘치면 완료도 한도를 따른다
+const MAX_COMPLETED_TRANSFERS: usize = 64;
+
+/// 이 방·target으로 보내는 완료(100%) 갱신이 이번 전송에서 처음인지 기록하며 확인한다.
+fn first_completion(
+    state: &AppState,
+    from_peer_id: &str,
+    room_id: &str,
+    target: Option<&str>,
+) -> bool {
+    let Some(session) = state.peers.get(from_peer_id) else {
+        return false;
+    };
+    let mut completed = session.completed_transfers.lock().unwrap();
+    if completed.len() >= MAX_COMPLETED_TRANSFERS {
+        return false;
+    }
+    completed.insert((room_id.to_string(), target.map(str::to_string)))
+}
+
+fn progress_throttled(state: &AppState, from_peer_id: &str) -> bool {
+    let Some(session) = state.peers.get(from_peer_id) else {
+        return true;
+    };
+    session.progress_limiter.as_ref().is_some_and(|limiter| {
+        limiter
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .try_acquire()
+            .is_err()
+    })
+}
+
+/// 응답 없는 Offer를 MAX_PENDING_OFFERS 계산에 포함하는 최대 시간
+const PENDING_OFFER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 새 target에 대한 Offer가 MAX_PENDING_OFFERS를 넘기는지 확인한다.
+/// 이미 대기 중인 target으로의 재Offer는 기존 항목을 갱신하므로 허용한다.
+fn too_many_pending_offers(state: &AppState, from_peer_id: &str, target: &str) -> bool {
+    let limit = state.config.signaling.max_pending_offers;
+    if limit == 0 {
+        return false;
+    }
+    let to_peer_id = state
+        .resolve_peer_id(target)
+        .unwrap_or_else(|| target.to_string());
+    let mut pending = 0;
+    for entry in state.pending_offers.iter() {
+        let (from, to) = entry.key();
+        if from != from_peer_id || entry.value().elapsed() > PENDING_OFFER_TIMEOUT {
+            continue;
+        }
+        if *to == to_peer_id {
+            return false;
+        }
+        pending += 1;
+    }
+    pending >= limit
+}
+
+/// target Offer를 기록하고, 반대 방향 Offer가 GLARE_WINDOW_MS 안에 대기 중이면 true를 반환한다.
+/// glare 감지와 MAX_PENDING_OFFERS가 모두 꺼져 있으면 기록하지 않는다.
+fn track_offer(state: &AppState, from_peer_id: &str, target: &str) -> bool {
+    let window = Duration::from_millis(state.config.signaling.glare_window_ms);
+    if window.is_zero() && state.config.signaling.max_pending_offers == 0 {
+        return false;
+    }
+    let to_peer_id = state
+        .resolve_peer_id(target)
+        .unwrap_or_else(|| target.to_string());
+    let reverse = (to_peer_id.clone(), from_peer_id.to_string());
+    let glare = !window.is_zero()
+        && state
+            .pending_offers
+            .get(&reverse)
+            .is_some_and(|sent_at| sent_at.elapsed() <= window);
+    state
+        .pending_offers
+        .insert((from_peer_id.to_string(), to_peer_id), Instant::now());
+    glare
+}
+
+/// source→target Offer의 세대를 하나 올려 돌려준다.
+/// 클라이언트가 더 큰 값을 보냈으면 그 값으로 맞춘다. `u64::MAX`에 닿으면 그 값에 머문다.
+fn next_offer_generation(
+    state: &AppState,
+    from_peer_id: &str,
+    target: &str,
+    requested: Option<u64>,
+) -> u64 {
+    let to_peer_id = state
+        .resolve_peer_id(target)
+        .unwrap_or_else(|| target.to_string());
+    let mut current = state
+        .offer_generations
+        .entry((from_peer_id.to_string(), to_peer_id))
+        .or_insert(0);
+    *current = requested.unwrap_or(0).max(current.saturating_add(1));
+    *current
+}
+
+/// 응답 없이 GLARE_WINDOW_MS(MAX_PENDING_OFFERS 사용 시 대기 제한 시간)가 지난 Offer 기록 정리
+pub fn cleanup_stale_offers(state: &AppState) {
+    let mut window = Duration::from_millis(state.config.signaling.glare_window_ms);
+    if state.config.signaling.max_pending_offers > 0 {
+        window = window.max(PENDING_OFFER_TIMEOUT);
+    }
+    state
+        .pending_offers
+        .retain(|_, sent_at| sent_at.elapsed() <= window);
+}
+
+/// Observer는 시그널링/전송 메시지를 보낼 수 없다.
+async fn ensure_can_send(state: &AppState, from_peer_id: &str) -> bool {
+    let role = match state.peers.get(from_peer_id) {
+        Some(session) => *session.role.read().await,
+        None => PeerRole::default(),
+    };
+    if role == PeerRole::Observer {
+        send_error(
+            state,
+            from_peer_id,
+            "observer_readonly",
+            "Observers cannot send signaling messages",
+        );
+        tracing::debug!(target: SIGNALING_LOG_TARGET, from = %from_peer_id, "Dropped observer relay");
+        return false;
+    }
+    true
+}
+
+/// SDP/ICE 중계 대상 확인. REQUIRE_SIGNALING_TARGET이면 target 없는 방 전체 전송을 거부한다.
+/// 자기 자신을 target으로 지정하면 glare 기록 등 부수 효과 전에 거부한다.
+fn check_signaling_target(state: &AppState, from_peer_id: &str, target: Option<&str>) -> bool {
+    match target {
+        None if state.config.signaling.require_target => {
+            send_error(
+                state,
+                from_peer_id,
+                "target_required",
+                "Signaling messages must specify a target",
+            );
+            false
+        }
+        Some(target) => !reject_self_target(state, from_peer_id, target),
+        None => true,
+    }
+}
+
+/// target이 보낸 피어 자신(peer_id 또는 자기 client_token)이면 self_target 에러를 보내고 true.
+fn reject_self_target(state: &AppState, from_peer_id: &str, target: &str) -> bool {
+    let is_self = state
+        .resolve_peer_id(target)
+        .is_some_and(|peer_id| peer_id == from_peer_id);
+    if is_self {
+        send_error(
+            state,
+            from_peer_id,
+            "self_target",
+            "Cannot relay a message to yourself",
+        );
+        tracing::debug!(target: SIGNALING_LOG_TARGET, from = %from_peer_id, "Rejected self-targeted relay");
+    }
+    is_self
+}
+
+/// 요청한 피어에게 에러 메시지 전송
+pub fn send_error(state: &AppState, peer_id: &str, code: &str, message: &str) {
+    send_request_error(state, peer_id, &RequestContext::default(), code, message);
+}
+
+/// 응답을 요청과 맞출 수 있게 요청에 담겨 온 값을 들고 다니는 문맥
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    /// 응답에 그대로 돌려줄 클라이언트의 correlation_id
+    pub correlation_id: Option<String>,
+}
+
+/// 요청을 거절하는 에러 응답. `correlation_id`를 함께 돌려준다.
+pub fn send_request_error(
+    state: &AppState,
+    peer_id: &str,
+    context: &RequestContext,
+    code: &str,
+    message: &str,
+) {
     if let Some(session) = state.peers.get(peer_id) {
-        let _ = session.sender.send(message);
+        let _ = session.sender.send(ServerMessage::Error {
+            code: code.to_string(),
+            message: message.to_string(),
+            retry_after_ms: None,
+            correlation_id: context.correlation_id.clone(),
+        });
+    }
+}
+
+/// 특정 피어에게 메시지 전송 (peer_id 또는 client_token)
+/// 이 노드에 없는 target이면 다른 노드로 발행한다.
+async fn send_to_peer(
+    state: &AppState,
+    from_peer_id: &str,
+    room_id: &str,
+    target: &str,
+    message: ServerMessage,
+) {
+    if reject_self_target(state, from_peer_id, target) {
+        return;
+    }
+    // 방에 없는 피어는 로컬/원격 어느 쪽으로도 보내지 않는다
+    if !ensure_room_member(state, from_peer_id, room_id).await {
+        return;
+    }
+    if let Some(target_peer_id) = state.resolve_peer_id(target) {
+        if !check_same_room(state, from_peer_id, room_id, &target_peer_id).await {
+            return;
+        }
+        if let Some(session) = state.peers.get(&target_peer_id) {
+            // 거부된 전송은 방을 살려 두지 않도록 전달된 뒤에만 활동으로 본다
+            if session.sender.send(message).is_ok() {
+                state.touch_room(room_id);
+            }
+            return;
+        }
+    }
+    cluster::publish_target(state, room_id, target, message).await;
+}
+
+/// 보낸 피어가 `room_id` 방의 멤버인지 확인한다. 아니면 not_in_room으로 거부한다.
+async fn ensure_room_member(state: &AppState, from_peer_id: &str, room_id: &str) -> bool {
+    let member = match state.rooms.get(room_id) {
+        Some(room) => room.users.read().await.contains(from_peer_id),
+        None => false,
+    };
+    if !member {
+        send_error(
+            state,
+            from_peer_id,
+            "not_in_room",
+            "Not a member of this room",
+        );
+        tracing::debug!(
+            target: SIGNALING_LOG_TARGET,
+            from = %from_peer_id,
+            room_id = %room_id,
+            "Rejected relay from non-member"
+        );
+    }
+    member
+}
+
+/// 보낸 피어와 target이 모두 `room_id` 방에 있는지 확인한다. 아니면 target_different_room으로 거부한다.
+async fn check_same_room(
+    state: &AppState,
+    from_peer_id: &str,
+    room_id: &str,
+    target_peer_id: &str,
+) -> bool {
+    let same_room = match state.rooms.get(room_id) {
+        Some(room) => {
+            let users = room.users.read().await;
+            users.contains(from_peer_id) && users.contains(target_peer_id)
+        }
+        None => false,
+    };
+    if !same_room {
+        send_error(
+            state,
+            from_peer_id,
+            "target_different_room",
+            "Target is not in the same room",
+        );
+        tracing::debug!(
+            target: SIGNALING_LOG_TARGET,
+            from = %from_peer_id,
+            target = %target_peer_id,
+            room_id = %room_id,
+            "Rejected cross-room relay"
+        );
     }
+    same_room
 }
 
 /// 방의 특정 피어를 제외하고 브로드캐스트
@@ -213,14 +1095,1083 @@ async fn broadcast_to_room_except(
     except_peer_id: &str,
     message: ServerMessage,
 ) {
-    if let Some(room) = state.rooms.get(room_id) {
-        let users = room.users.read().await;
-        for peer_id in users.iter() {
-            if peer_id != except_peer_id {
-                if let Some(session) = state.peers.get(peer_id) {
-                    let _ = session.sender.send(message.clone());
-                }
+    broadcast_to_members(state, room_id, except_peer_id, Audience::All, message).await;
+}
+
+/// 방 브로드캐스트의 수신 대상
+#[derive(Clone, Copy)]
+enum Audience<'a> {
+    All,
+    /// 이 역할의 피어만 (RelayToRole)
+    Role(PeerRole),
+    /// 이 기능을 알린 피어만 (RelayToCapable)
+    Capable(&'a str),
+}
+
+/// 방의 특정 피어를 제외하고 `audience`에 해당하는 멤버에게 브로드캐스트한다.
+/// 보낸 피어가 방 멤버가 아니거나 fan-out/속도 제한에 걸리면 보내지 않고 false를 돌려준다.
+async fn broadcast_to_members(
+    state: &AppState,
+    room_id: &str,
+    except_peer_id: &str,
+    audience: Audience<'_>,
+    message: ServerMessage,
+) -> bool {
+    if !ensure_room_member(state, except_peer_id, room_id).await {
+        return false;
+    }
+    let role = match audience {
+        Audience::Role(role) => Some(role),
+        _ => None,
+    };
+    let recipients = match state.rooms.get(room_id) {
+        Some(room) => {
+            let mut recipients =
+                crate::handlers::snapshot_recipients(state, &room, Some(except_peer_id), role)
+                    .await;
+            if let Audience::Capable(capability) = audience {
+                recipients.retain(|(peer_id, _)| {
+                    state
+                        .peers
+                        .get(peer_id)
+                        .is_some_and(|session| session.capabilities.advertises(capability))
+                });
+            }
+            if !check_broadcast_allowed(state, &room, except_peer_id, recipients.len()) {
+                return false;
+            }
+            room.touch();
+            recipients
+        }
+        None => Vec::new(),
+    }; // 방 guard를 놓은 뒤 전송한다.
+       // 죽은 채널 정리는 별도 태스크가 처리한다.
+    crate::handlers::fan_out(state, recipients, &message);
+    let except = Some(except_peer_id);
+    match audience {
+        Audience::All => cluster::publish_room(state, room_id, except, message).await,
+        Audience::Role(role) => cluster::publish_role(state, room_id, except, role, message).await,
+        Audience::Capable(capability) => {
+            cluster::publish_capable(state, room_id, except, capability, message).await
+        }
+    }
+    true
+}
+
+/// 방 전체 중계의 fan-out 한도와 방 단위 속도 제한 확인. 거부하면 보낸 피어에게 알린다.
+fn check_broadcast_allowed(
+    state: &AppState,
+    room: &Room,
+    except_peer_id: &str,
+    recipients: usize,
+) -> bool {
+    let max_fanout = state.config.signaling.max_broadcast_fanout;
+    if max_fanout > 0 && recipients > max_fanout {
+        send_error(
+            state,
+            except_peer_id,
+            "fanout_too_large",
+            "Room is too large for broadcast signaling; specify a target",
+        );
+        tracing::warn!(
+            target: SIGNALING_LOG_TARGET,
+            from = %except_peer_id,
+            room_id = %room.id,
+            recipients,
+            max_fanout,
+            "Rejected broadcast exceeding fan-out limit"
+        );
+        return false;
+    }
+    if let Err(retry_after_ms) = room.try_acquire_relay() {
+        if state.config.room.msg_rate_notify {
+            if let Some(session) = state.peers.get(except_peer_id) {
+                let _ = session.sender.send(ServerMessage::Error {
+                    code: "room_rate_limited".to_string(),
+                    message: "Room relay rate exceeded, message dropped".to_string(),
+                    retry_after_ms: Some(retry_after_ms),
+                    correlation_id: None,
+                });
+            }
+        }
+        tracing::debug!(
+            target: SIGNALING_LOG_TARGET,
+            from = %except_peer_id,
+            room_id = %room.id,
+            "Dropped broadcast over room rate limit"
+        );
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{IceCandidatePayload, RoomOptions};
+    use crate::state::drain_messages;
+
+    async fn state_with_room(peers: &[&str]) -> AppState {
+        let state = AppState::new_for_test();
+        let room = Room::new("room".to_string());
+        room.users
+            .write()
+            .await
+            .extend(peers.iter().map(|peer| peer.to_string()));
+        state.rooms.insert("room".to_string(), room);
+        state
+    }
+
+    #[tokio::test]
+    async fn non_member_cannot_relay_into_room() {
+        let state = Arc::new(state_with_room(&["a", "b"]).await);
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+        let mut outsider = state.insert_test_peer("outsider");
+
+        handle_offer(state.clone(), "outsider", "room", "v=0", None, None).await;
+        handle_offer(state.clone(), "outsider", "room", "v=0", Some("b"), None).await;
+
+        assert!(drain_messages(&mut a).is_empty());
+        assert!(drain_messages(&mut b).is_empty());
+        let codes: Vec<String> = drain_messages(&mut outsider)
+            .into_iter()
+            .filter_map(|message| match message {
+                ServerMessage::Error { code, .. } => Some(code),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(codes, ["not_in_room", "not_in_room"]);
+    }
+
+    fn progress_of(messages: Vec<ServerMessage>) -> Vec<f32> {
+        messages
+            .into_iter()
+            .filter_map(|message| match message {
+                ServerMessage::TransferProgress { percent, .. } => Some(percent),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn transfer_progress_is_relayed_to_target() {
+        let state = Arc::new(state_with_room(&["a", "b", "c"]).await);
+        let _a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+        let mut c = state.insert_test_peer("c");
+
+        handle_transfer_progress(state.clone(), "a", "room", Some("b"), 42.5, 1024).await;
+
+        assert!(matches!(
+            drain_messages(&mut b).as_slice(),
+            [ServerMessage::TransferProgress { from, percent, bytes: 1024 }]
+                if from == "a" && *percent == 42.5
+        ));
+        assert!(drain_messages(&mut c).is_empty());
+    }
+
+    #[tokio::test]
+    async fn transfer_progress_is_throttled_per_sender_except_completion() {
+        let mut config = crate::config::Config::from_env();
+        config.signaling.transfer_progress_rate = 1;
+        let state = AppState::new_for_test_with_config(config);
+        let room = Room::new("room".to_string());
+        room.users
+            .write()
+            .await
+            .extend(["a".to_string(), "b".to_string()]);
+        state.rooms.insert("room".to_string(), room);
+        let state = Arc::new(state);
+        let _a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        for percent in [10.0, 20.0, 30.0, 100.0] {
+            handle_transfer_progress(state.clone(), "a", "room", Some("b"), percent, 0).await;
+        }
+
+        assert_eq!(progress_of(drain_messages(&mut b)), [10.0, 100.0]);
+
+        // 완료를 반복해 보내도 한도를 건너뛰는 것은 전송마다 한 번뿐이다
+        for _ in 0..5 {
+            handle_transfer_progress(state.clone(), "a", "room", Some("b"), 100.0, 0).await;
+        }
+        assert!(drain_messages(&mut b).is_empty());
+    }
+
+    #[tokio::test]
+    async fn broadcast_transfer_complete_keeps_order_with_later_relays() {
+        let state = Arc::new(state_with_room(&["a", "b"]).await);
+        let _a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        handle_transfer_complete(state.clone(), "a", "room", None).await;
+        handle_transfer_ready(state.clone(), "a", "room", None).await;
+
+        assert!(matches!(
+            drain_messages(&mut b).as_slice(),
+            [
+                ServerMessage::TransferComplete { .. },
+                ServerMessage::TransferReady { .. }
+            ]
+        ));
+    }
+
+    #[tokio::test]
+    async fn ice_candidate_relays_canonical_structured_form() {
+        let state = Arc::new(state_with_room(&["a", "b"]).await);
+        let _a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        let raw = IceCandidateInput::Raw("candidate:1 1 udp 1 10.0.0.1 9 typ host".to_string());
+        handle_ice_candidate(state.clone(), "a", "room", &raw, Some("b")).await;
+
+        match drain_messages(&mut b).as_slice() {
+            [ServerMessage::IceCandidate { from, candidate }] => {
+                assert_eq!(from, "a");
+                assert_eq!(
+                    candidate,
+                    &IceCandidatePayload {
+                        candidate: "candidate:1 1 udp 1 10.0.0.1 9 typ host".to_string(),
+                        sdp_mid: None,
+                        sdp_m_line_index: None,
+                        username_fragment: None,
+                    }
+                );
+            }
+            other => panic!("unexpected messages: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn host_only_room_rejects_guest_offers_but_relays_answers() {
+        let state = Arc::new(AppState::new_for_test());
+        let mut host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let options = RoomOptions {
+            host_only_offers: true,
+            ..RoomOptions::default()
+        };
+        crate::handlers::handle_join_room(
+            state.clone(),
+            "host",
+            "room",
+            PeerRole::Participant,
+            Some(options),
+        )
+        .await;
+        // 이미 있는 방이므로 guest의 options는 무시된다.
+        crate::handlers::handle_join_room(
+            state.clone(),
+            "guest",
+            "room",
+            PeerRole::Participant,
+            Some(RoomOptions::default()),
+        )
+        .await;
+        drain_messages(&mut host);
+        drain_messages(&mut guest);
+
+        handle_offer(state.clone(), "guest", "room", "v=0", Some("host"), None).await;
+        assert!(drain_messages(&mut host).is_empty());
+        assert!(matches!(
+            drain_messages(&mut guest).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "offer_not_allowed"
+        ));
+
+        handle_offer(state.clone(), "host", "room", "v=0", Some("guest"), None).await;
+        handle_answer(state.clone(), "guest", "room", "v=0", Some("host"), None).await;
+        assert!(matches!(
+            drain_messages(&mut guest).as_slice(),
+            [ServerMessage::Offer { from, .. }] if from == "host"
+        ));
+        assert!(matches!(
+            drain_messages(&mut host).as_slice(),
+            [ServerMessage::Answer { from, .. }] if from == "guest"
+        ));
+    }
+
+    #[tokio::test]
+    async fn ice_restart_reaches_each_listed_target_once() {
+        let state = Arc::new(state_with_room(&["a", "b", "c", "d"]).await);
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+        let mut c = state.insert_test_peer("c");
+        let mut d = state.insert_test_peer("d");
+
+        let targets = ["b", "c", "b", "a"].map(String::from);
+        handle_ice_restart(state.clone(), "a", "room", &targets).await;
+
+        for receiver in [&mut b, &mut c] {
+            assert!(matches!(
+                drain_messages(receiver).as_slice(),
+                [ServerMessage::IceRestart { from }] if from == "a"
+            ));
+        }
+        assert!(drain_messages(&mut a).is_empty());
+        assert!(drain_messages(&mut d).is_empty());
+    }
+
+    #[tokio::test]
+    async fn ice_restart_target_list_is_capped() {
+        let state = Arc::new(state_with_signaling(|signaling| {
+            signaling.max_relay_targets = 2;
+        }));
+        join(&state, "room", &["a", "b", "c", "d"]).await;
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+        let mut c = state.insert_test_peer("c");
+        let mut d = state.insert_test_peer("d");
+
+        let at_limit = ["b", "c"].map(String::from);
+        handle_ice_restart(state.clone(), "a", "room", &at_limit).await;
+        assert!(drain_messages(&mut a).is_empty());
+        assert_eq!(drain_messages(&mut b).len(), 1);
+        assert_eq!(drain_messages(&mut c).len(), 1);
+
+        let over_limit = ["b", "c", "d"].map(String::from);
+        handle_ice_restart(state.clone(), "a", "room", &over_limit).await;
+        assert!(matches!(
+            drain_messages(&mut a).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "too_many_targets"
+        ));
+        for receiver in [&mut b, &mut c, &mut d] {
+            assert!(drain_messages(receiver).is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn ice_restart_without_targets_is_rejected() {
+        let state = Arc::new(state_with_room(&["a", "b"]).await);
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        handle_ice_restart(state.clone(), "a", "room", &["a".to_string()]).await;
+
+        assert!(matches!(
+            drain_messages(&mut a).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "target_required"
+        ));
+        assert!(drain_messages(&mut b).is_empty());
+    }
+
+    #[tokio::test]
+    async fn observer_receives_broadcasts_but_cannot_relay() {
+        let state = Arc::new(state_with_room(&["a", "b", "watcher"]).await);
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+        let mut watcher = state.insert_test_peer("watcher");
+        *state.peers.get("watcher").unwrap().role.write().await = PeerRole::Observer;
+
+        handle_offer(state.clone(), "a", "room", "v=0", None, None).await;
+        assert!(matches!(
+            drain_messages(&mut watcher).as_slice(),
+            [ServerMessage::Offer { from, .. }] if from == "a"
+        ));
+        drain_messages(&mut b);
+
+        handle_offer(state.clone(), "watcher", "room", "v=0", None, None).await;
+        handle_answer(state.clone(), "watcher", "room", "v=0", Some("a"), None).await;
+        handle_transfer_ready(state.clone(), "watcher", "room", Some("a")).await;
+
+        assert!(drain_messages(&mut a).is_empty());
+        assert!(drain_messages(&mut b).is_empty());
+        let errors = drain_messages(&mut watcher);
+        assert_eq!(errors.len(), 3);
+        assert!(errors.iter().all(|message| matches!(
+            message,
+            ServerMessage::Error { code, .. } if code == "observer_readonly"
+        )));
+    }
+
+    #[tokio::test]
+    async fn relay_to_role_reaches_only_matching_members() {
+        let state = Arc::new(state_with_room(&["host", "guest", "w1", "w2"]).await);
+        let mut host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let mut watchers = [state.insert_test_peer("w1"), state.insert_test_peer("w2")];
+        for watcher in ["w1", "w2"] {
+            *state.peers.get(watcher).unwrap().role.write().await = PeerRole::Observer;
+        }
+
+        let data = serde_json::json!({ "slide": 3 });
+        let observer = PeerRole::Observer;
+        let event = "slide_changed".to_string();
+        handle_relay_to_role(state.clone(), "host", "room", observer, event, data.clone()).await;
+
+        for watcher in &mut watchers {
+            assert!(matches!(
+                drain_messages(watcher).as_slice(),
+                [ServerMessage::RelayToRole { from, role: PeerRole::Observer, event, data: got }]
+                    if from == "host" && event == "slide_changed" && *got == data
+            ));
+        }
+        assert!(drain_messages(&mut guest).is_empty());
+        assert!(drain_messages(&mut host).is_empty());
+
+        let participant = PeerRole::Participant;
+        let event = "ping".to_string();
+        handle_relay_to_role(state.clone(), "host", "room", participant, event, data).await;
+        assert!(matches!(
+            drain_messages(&mut guest).as_slice(),
+            [ServerMessage::RelayToRole {
+                role: PeerRole::Participant,
+                ..
+            }]
+        ));
+        assert!(drain_messages(&mut host).is_empty());
+        for watcher in &mut watchers {
+            assert!(drain_messages(watcher).is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn relay_blocklist_rejects_matching_strings_only() {
+        let mut config = crate::config::Config::from_env();
+        config.signaling.relay_content_blocklist = "(?i)badword".to_string();
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let room = Room::new("room".to_string());
+        room.users
+            .write()
+            .await
+            .extend(["host".to_string(), "guest".to_string()]);
+        state.rooms.insert("room".to_string(), room);
+        let mut host = state.insert_test_peer("host");
+        let mut guest = state.insert_test_peer("guest");
+        let participant = PeerRole::Participant;
+
+        let blocked = serde_json::json!({ "chat": { "lines": ["hello", "BadWord here"] } });
+        handle_relay_to_role(
+            state.clone(),
+            "host",
+            "room",
+            participant,
+            "chat".into(),
+            blocked,
+        )
+        .await;
+        assert!(drain_messages(&mut guest).is_empty());
+        assert!(matches!(
+            drain_messages(&mut host).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "content_blocked"
+        ));
+
+        let allowed = serde_json::json!({ "chat": "hello", "count": 3 });
+        handle_relay_to_role(
+            state.clone(),
+            "host",
+            "room",
+            participant,
+            "chat".into(),
+            allowed,
+        )
+        .await;
+        assert!(matches!(
+            drain_messages(&mut guest).as_slice(),
+            [ServerMessage::RelayToRole { .. }]
+        ));
+        assert!(drain_messages(&mut host).is_empty());
+    }
+
+    #[tokio::test]
+    async fn relay_to_role_from_non_member_is_rejected() {
+        let state = Arc::new(state_with_room(&["guest"]).await);
+        let mut guest = state.insert_test_peer("guest");
+        let mut outsider = state.insert_test_peer("outsider");
+
+        let participant = PeerRole::Participant;
+        let data = serde_json::json!({ "spam": true });
+        handle_relay_to_role(
+            state.clone(),
+            "outsider",
+            "room",
+            participant,
+            "x".into(),
+            data,
+        )
+        .await;
+
+        assert!(drain_messages(&mut guest).is_empty());
+        assert!(matches!(
+            drain_messages(&mut outsider).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "not_in_room"
+        ));
+    }
+
+    #[tokio::test]
+    async fn relay_to_capable_from_non_member_is_rejected() {
+        let state = Arc::new(state_with_room(&["quic"]).await);
+        let mut quic = state.insert_test_peer("quic");
+        let mut outsider = state.insert_test_peer("outsider");
+        let names = vec!["supports-quic".to_string()];
+        state
+            .peers
+            .get("quic")
+            .unwrap()
+            .capabilities
+            .advertise(names);
+
+        let capability = "supports-quic".to_string();
+        let data = serde_json::json!({ "spam": true });
+        handle_relay_to_capable(
+            state.clone(),
+            "outsider",
+            "room",
+            capability,
+            "x".into(),
+            data,
+        )
+        .await;
+
+        assert!(drain_messages(&mut quic).is_empty());
+        assert!(matches!(
+            drain_messages(&mut outsider).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "not_in_room"
+        ));
+    }
+
+    #[tokio::test]
+    async fn relay_to_capable_reaches_only_advertising_members() {
+        let state = Arc::new(state_with_room(&["a", "quic", "plain"]).await);
+        let mut a = state.insert_test_peer("a");
+        let mut quic = state.insert_test_peer("quic");
+        let mut plain = state.insert_test_peer("plain");
+        let advertise = |peer_id: &str, names: &[&str]| {
+            let names = names.iter().map(|name| name.to_string()).collect();
+            state
+                .peers
+                .get(peer_id)
+                .unwrap()
+                .capabilities
+                .advertise(names);
+        };
+        advertise("a", &["supports-quic"]);
+        advertise("quic", &["supports-quic", "h3"]);
+        advertise("plain", &["h3"]);
+
+        let data = serde_json::json!({ "port": 4433 });
+        let capability = "supports-quic".to_string();
+        let event = "quic_offer".to_string();
+        handle_relay_to_capable(state.clone(), "a", "room", capability, event, data.clone()).await;
+
+        assert!(matches!(
+            drain_messages(&mut quic).as_slice(),
+            [ServerMessage::RelayToCapable { from, capability, event, data: got }]
+                if from == "a" && capability == "supports-quic"
+                    && event == "quic_offer" && *got == data
+        ));
+        assert!(drain_messages(&mut plain).is_empty());
+        assert!(drain_messages(&mut a).is_empty());
+
+        // 목록을 바꾸면 이전 기능은 더 이상 대상이 아니다
+        advertise("quic", &["h3"]);
+        let capability = "supports-quic".to_string();
+        handle_relay_to_capable(state.clone(), "a", "room", capability, "x".into(), data).await;
+        assert!(drain_messages(&mut quic).is_empty());
+    }
+
+    #[tokio::test]
+    async fn broadcast_beyond_fanout_limit_is_rejected() {
+        let state = Arc::new(state_with_signaling(|signaling| {
+            signaling.require_target = false;
+            signaling.max_broadcast_fanout = 2;
+        }));
+        join(&state, "room", &["a", "b", "c", "d"]).await;
+        let mut a = state.insert_test_peer("a");
+        let mut others: Vec<_> = ["b", "c", "d"]
+            .iter()
+            .map(|peer| state.insert_test_peer(peer))
+            .collect();
+
+        handle_offer(state.clone(), "a", "room", "v=0", None, None).await;
+        assert!(matches!(
+            drain_messages(&mut a).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "fanout_too_large"
+        ));
+        assert!(others.iter_mut().all(|rx| drain_messages(rx).is_empty()));
+
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), None).await;
+        assert_eq!(drain_messages(&mut others[0]).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn sustained_room_broadcasts_are_throttled() {
+        let mut config = crate::config::Config::from_env();
+        config.signaling.require_target = false;
+        config.room.msg_rate_per_sec = 1;
+        config.room.msg_burst = 3;
+        config.room.msg_rate_notify = true;
+        let room = Room::with_config("room".to_string(), &config.room);
+        room.users
+            .write()
+            .await
+            .extend(["a".to_string(), "b".to_string()]);
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        state.rooms.insert("room".to_string(), room);
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        for _ in 0..10 {
+            handle_offer(state.clone(), "a", "room", "v=0", None, None).await;
+        }
+
+        assert_eq!(drain_messages(&mut b).len(), 3);
+        let errors = drain_messages(&mut a);
+        assert_eq!(errors.len(), 7);
+        assert!(errors.iter().all(|message| matches!(
+            message,
+            ServerMessage::Error { code, retry_after_ms: Some(retry), .. }
+                if code == "room_rate_limited" && *retry > 0
+        )));
+    }
+
+    fn offer_glare(messages: &[ServerMessage]) -> Option<bool> {
+        match messages {
+            [ServerMessage::Offer { glare, .. }] => Some(*glare),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn simultaneous_offers_are_flagged_as_glare() {
+        let state = Arc::new(state_with_room(&["a", "b"]).await);
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), None).await;
+        handle_offer(state.clone(), "b", "room", "v=0", Some("a"), None).await;
+
+        assert_eq!(offer_glare(&drain_messages(&mut b)), Some(false));
+        assert_eq!(offer_glare(&drain_messages(&mut a)), Some(true));
+    }
+
+    #[tokio::test]
+    async fn offer_generation_increments_per_pair_and_rides_on_answer() {
+        let state = Arc::new(state_with_room(&["a", "b", "c"]).await);
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+        let mut c = state.insert_test_peer("c");
+        fn generations(messages: &[ServerMessage]) -> Vec<Option<u64>> {
+            messages
+                .iter()
+                .filter_map(|message| match message {
+                    ServerMessage::Offer { generation, .. } => Some(*generation),
+                    ServerMessage::Answer { generation, .. } => Some(*generation),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), None).await;
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), None).await;
+        handle_offer(state.clone(), "a", "room", "v=0", Some("c"), None).await;
+        assert_eq!(generations(&drain_messages(&mut b)), [Some(1), Some(2)]);
+        assert_eq!(generations(&drain_messages(&mut c)), [Some(1)]);
+
+        // 클라이언트가 더 큰 세대를 보내면 그 값에서 이어간다
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), Some(10)).await;
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), Some(3)).await;
+        assert_eq!(generations(&drain_messages(&mut b)), [Some(10), Some(11)]);
+
+        handle_answer(state.clone(), "b", "room", "v=0", Some("a"), None).await;
+        assert_eq!(generations(&drain_messages(&mut a)), [Some(11)]);
+    }
+
+    #[tokio::test]
+    async fn offer_generation_saturates_at_max() {
+        let state = Arc::new(state_with_room(&["a", "b"]).await);
+        let _a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), Some(u64::MAX)).await;
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), None).await;
+
+        let generations: Vec<_> = drain_messages(&mut b)
+            .into_iter()
+            .filter_map(|message| match message {
+                ServerMessage::Offer { generation, .. } => generation,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(generations, [u64::MAX, u64::MAX]);
+    }
+
+    #[tokio::test]
+    async fn answered_offer_does_not_cause_glare() {
+        let state = Arc::new(state_with_room(&["a", "b"]).await);
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), None).await;
+        handle_answer(state.clone(), "b", "room", "v=0", Some("a"), None).await;
+        drain_messages(&mut a);
+        drain_messages(&mut b);
+
+        handle_offer(state.clone(), "b", "room", "v=0", Some("a"), None).await;
+        assert_eq!(offer_glare(&drain_messages(&mut a)), Some(false));
+    }
+
+    #[tokio::test]
+    async fn pending_offers_are_capped_until_answered() {
+        let mut config = crate::config::Config::from_env();
+        config.signaling.max_pending_offers = 2;
+        config.signaling.glare_window_ms = 0;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let room = Room::new("room".to_string());
+        room.users
+            .write()
+            .await
+            .extend(["a", "b", "c", "d"].map(String::from));
+        state.rooms.insert("room".to_string(), room);
+        let mut a = state.insert_test_peer("a");
+        let mut d = state.insert_test_peer("d");
+        for peer in ["b", "c"] {
+            drop(state.insert_test_peer(peer));
+        }
+
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), None).await;
+        handle_offer(state.clone(), "a", "room", "v=0", Some("c"), None).await;
+        // 이미 대기 중인 target으로의 재Offer는 한도에 더 세지 않는다.
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), None).await;
+        assert_eq!(state.pending_offers.len(), 2);
+        assert!(drain_messages(&mut a).is_empty());
+
+        handle_offer(state.clone(), "a", "room", "v=0", Some("d"), None).await;
+        assert!(matches!(
+            drain_messages(&mut a).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "too_many_pending_offers"
+        ));
+        assert!(drain_messages(&mut d).is_empty());
+
+        handle_answer(state.clone(), "b", "room", "v=0", Some("a"), None).await;
+        drain_messages(&mut a);
+        handle_offer(state.clone(), "a", "room", "v=0", Some("d"), None).await;
+        assert!(matches!(
+            drain_messages(&mut d).as_slice(),
+            [ServerMessage::Offer { from, .. }] if from == "a"
+        ));
+        assert_eq!(state.pending_offers.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn targeted_relay_requires_target_in_same_room() {
+        let state = Arc::new(state_with_room(&["a", "b"]).await);
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+        let mut outsider = state.insert_test_peer("outsider");
+        let mut other_room = Room::new("other".to_string());
+        other_room.users.get_mut().insert("outsider".to_string());
+        state.rooms.insert("other".to_string(), other_room);
+
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), None).await;
+        assert!(matches!(
+            drain_messages(&mut b).as_slice(),
+            [ServerMessage::Offer { from, .. }] if from == "a"
+        ));
+        assert!(drain_messages(&mut a).is_empty());
+
+        // 다른 방의 피어, 또는 보낸 피어가 없는 방 id로는 전달하지 않는다.
+        handle_offer(state.clone(), "a", "room", "v=0", Some("outsider"), None).await;
+        handle_transfer_complete(state.clone(), "a", "other", Some("outsider")).await;
+        assert!(drain_messages(&mut outsider).is_empty());
+        let codes: Vec<String> = drain_messages(&mut a)
+            .into_iter()
+            .filter_map(|message| match message {
+                ServerMessage::Error { code, .. } => Some(code),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(codes, ["target_different_room", "target_different_room"]);
+    }
+
+    #[tokio::test]
+    async fn self_targeted_offer_is_rejected_and_not_delivered() {
+        let state = Arc::new(state_with_room(&["a", "b"]).await);
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+        state
+            .client_tokens
+            .insert("token-a".to_string(), "a".to_string());
+
+        handle_offer(state.clone(), "a", "room", "v=0", Some("a"), None).await;
+        // 자기 client_token으로 지정해도 같은 피어다.
+        handle_manifest(state.clone(), "a", "room", "{}", Some("token-a")).await;
+
+        let codes: Vec<String> = drain_messages(&mut a)
+            .into_iter()
+            .map(|message| match message {
+                ServerMessage::Error { code, .. } => code,
+                other => panic!("self relay delivered: {other:?}"),
+            })
+            .collect();
+        assert_eq!(codes, ["self_target", "self_target"]);
+        assert!(drain_messages(&mut b).is_empty());
+        assert!(state.pending_offers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn relay_by_client_token_follows_reconnect() {
+        let state = Arc::new(state_with_room(&["a"]).await);
+        let _a = state.insert_test_peer("a");
+        let (old_tx, mut old_rx) = tokio::sync::mpsc::unbounded_channel();
+        let old_id = crate::handlers::handle_connection(
+            state.clone(),
+            old_tx,
+            Some("token-b".to_string()),
+            None,
+            None,
+        )
+        .await;
+        crate::handlers::handle_disconnect(state.clone(), &old_id).await;
+        let (new_tx, mut new_rx) = tokio::sync::mpsc::unbounded_channel();
+        let new_id = crate::handlers::handle_connection(
+            state.clone(),
+            new_tx,
+            Some("token-b".to_string()),
+            None,
+            None,
+        )
+        .await;
+        // 교차 방 중계는 거부되므로 새 연결도 같은 방에 들어간다.
+        state
+            .rooms
+            .get("room")
+            .unwrap()
+            .users
+            .write()
+            .await
+            .insert(new_id);
+
+        handle_offer(state.clone(), "a", "room", "v=0", Some("token-b"), None).await;
+
+        drain_messages(&mut old_rx);
+        assert!(old_rx.try_recv().is_err());
+        assert!(drain_messages(&mut new_rx)
+            .iter()
+            .any(|message| matches!(message, ServerMessage::Offer { from, .. } if from == "a")));
+    }
+
+    fn state_with_signaling(
+        configure: impl FnOnce(&mut crate::config::SignalingConfig),
+    ) -> AppState {
+        let mut config = crate::config::Config::from_env();
+        configure(&mut config.signaling);
+        AppState::new_for_test_with_config(config)
+    }
+
+    async fn join(state: &AppState, room_id: &str, peers: &[&str]) {
+        let room = Room::new(room_id.to_string());
+        room.users
+            .write()
+            .await
+            .extend(peers.iter().map(|peer| peer.to_string()));
+        state.rooms.insert(room_id.to_string(), room);
+    }
+
+    #[tokio::test]
+    async fn manifest_chunks_are_relayed_in_index_order() {
+        let state = Arc::new(state_with_signaling(|signaling| {
+            signaling.manifest_reassemble = false;
+            signaling.manifest_max_bytes = 1024;
+            signaling.manifest_chunk_timeout_ms = 30_000;
+        }));
+        join(&state, "room", &["a", "b"]).await;
+        let _a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        for (index, data) in [(1, "B"), (0, "A"), (2, "C")] {
+            handle_manifest_chunk(state.clone(), "a", "room", Some("b"), index, 3, data).await;
+        }
+
+        let relayed: Vec<(u32, String)> = drain_messages(&mut b)
+            .into_iter()
+            .map(|message| match message {
+                ServerMessage::ManifestChunk {
+                    chunk_index, data, ..
+                } => (chunk_index, data),
+                other => panic!("unexpected message: {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            relayed,
+            vec![(0, "A".into()), (1, "B".into()), (2, "C".into())]
+        );
+        assert!(state.manifest_chunks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn manifest_chunks_reassemble_into_single_manifest() {
+        let state = Arc::new(state_with_signaling(|signaling| {
+            signaling.manifest_reassemble = true;
+            signaling.manifest_max_bytes = 1024;
+            signaling.manifest_chunk_timeout_ms = 30_000;
+        }));
+        join(&state, "room", &["a", "b"]).await;
+        let _a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        for (index, data) in [(2, "}]}"), (0, r#"{"files":[{"#), (1, r#""name":"x""#)] {
+            handle_manifest_chunk(state.clone(), "a", "room", None, index, 3, data).await;
+        }
+
+        match drain_messages(&mut b).as_slice() {
+            [ServerMessage::Manifest { from, manifest }] => {
+                assert_eq!(from, "a");
+                assert_eq!(manifest, r#"{"files":[{"name":"x"}]}"#);
             }
+            other => panic!("unexpected messages: {other:?}"),
         }
     }
+
+    #[tokio::test]
+    async fn incomplete_manifest_chunks_time_out() {
+        let state = Arc::new(state_with_signaling(|signaling| {
+            signaling.manifest_reassemble = true;
+            signaling.manifest_max_bytes = 1024;
+            signaling.manifest_chunk_timeout_ms = 10;
+        }));
+        join(&state, "room", &["a", "b"]).await;
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        handle_manifest_chunk(state.clone(), "a", "room", Some("b"), 0, 3, "A").await;
+        handle_manifest_chunk(state.clone(), "a", "room", Some("b"), 2, 3, "C").await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        cleanup_stale_manifest_chunks(&state);
+
+        assert!(state.manifest_chunks.is_empty());
+        assert!(drain_messages(&mut b).is_empty());
+        assert!(matches!(
+            drain_messages(&mut a).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "manifest_chunk_timeout"
+        ));
+    }
+
+    #[tokio::test]
+    async fn oversized_manifest_chunks_are_rejected() {
+        let state = Arc::new(state_with_signaling(|signaling| {
+            signaling.manifest_reassemble = true;
+            signaling.manifest_max_bytes = 4;
+            signaling.manifest_chunk_timeout_ms = 30_000;
+        }));
+        join(&state, "room", &["a", "b"]).await;
+        let mut a = state.insert_test_peer("a");
+
+        handle_manifest_chunk(state.clone(), "a", "room", Some("b"), 0, 2, "abc").await;
+        handle_manifest_chunk(state.clone(), "a", "room", Some("b"), 1, 2, "def").await;
+
+        assert!(state.manifest_chunks.is_empty());
+        assert!(matches!(
+            drain_messages(&mut a).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "manifest_too_large"
+        ));
+    }
+
+    #[tokio::test]
+    async fn untargeted_offer_is_rejected_when_target_required() {
+        let state = Arc::new(state_with_signaling(|signaling| {
+            signaling.require_target = true
+        }));
+        join(&state, "room", &["a", "b"]).await;
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        handle_offer(state.clone(), "a", "room", "v=0", None, None).await;
+        assert!(drain_messages(&mut b).is_empty());
+        assert!(matches!(
+            drain_messages(&mut a).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "target_required"
+        ));
+
+        handle_offer(state.clone(), "a", "room", "v=0", Some("b"), None).await;
+        assert!(matches!(
+            drain_messages(&mut b).as_slice(),
+            [ServerMessage::Offer { .. }]
+        ));
+    }
+
+    #[tokio::test]
+    async fn untargeted_offer_is_broadcast_by_default() {
+        let state = Arc::new(state_with_signaling(|signaling| {
+            signaling.require_target = false
+        }));
+        join(&state, "room", &["a", "b"]).await;
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        handle_answer(state.clone(), "a", "room", "v=0", None, None).await;
+
+        assert!(drain_messages(&mut a).is_empty());
+        assert!(matches!(
+            drain_messages(&mut b).as_slice(),
+            [ServerMessage::Answer { .. }]
+        ));
+    }
+
+    #[tokio::test]
+    async fn invalid_ice_candidate_is_rejected_to_sender() {
+        let state = Arc::new(state_with_room(&["a", "b"]).await);
+        let mut a = state.insert_test_peer("a");
+        let mut b = state.insert_test_peer("b");
+
+        let raw = IceCandidateInput::Raw("bogus".to_string());
+        handle_ice_candidate(state.clone(), "a", "room", &raw, None).await;
+
+        assert!(drain_messages(&mut b).is_empty());
+        assert!(matches!(
+            drain_messages(&mut a).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "invalid_candidate"
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejected_targeted_send_does_not_touch_room() {
+        let state = Arc::new(state_with_room(&["alice", "bob"]).await);
+        join(&state, "other", &["mallory"]).await;
+        let _alice = state.insert_test_peer("alice");
+        let _bob = state.insert_test_peer("bob");
+        let _mallory = state.insert_test_peer("mallory");
+        let stale = Instant::now() - Duration::from_secs(60);
+        *state
+            .rooms
+            .get("room")
+            .unwrap()
+            .last_activity
+            .lock()
+            .unwrap() = stale;
+
+        handle_offer(state.clone(), "alice", "room", "v=0", Some("mallory"), None).await;
+        handle_offer(state.clone(), "mallory", "room", "v=0", Some("bob"), None).await;
+        assert!(state.rooms.get("room").unwrap().idle_for() >= Duration::from_secs(60));
+
+        handle_offer(state.clone(), "alice", "room", "v=0", Some("bob"), None).await;
+        assert!(state.rooms.get("room").unwrap().idle_for() < Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn identity_assertion_reaches_only_the_target_in_the_same_room() {
+        let state = Arc::new(state_with_room(&["alice", "bob", "carol"]).await);
+        join(&state, "other", &["mallory"]).await;
+        let mut alice = state.insert_test_peer("alice");
+        let mut bob = state.insert_test_peer("bob");
+        let mut carol = state.insert_test_peer("carol");
+        let mut mallory = state.insert_test_peer("mallory");
+
+        let assertion = r#"{"sas":"4821","fingerprint":"sha-256 AB:CD"}"#.to_string();
+        handle_identity_assertion(state.clone(), "alice", "room", "bob", assertion.clone()).await;
+        handle_identity_assertion(state.clone(), "alice", "room", "mallory", assertion.clone())
+            .await;
+
+        assert!(matches!(
+            drain_messages(&mut bob).as_slice(),
+            [ServerMessage::IdentityAssertion { from, assertion: received }]
+                if from == "alice" && received == &assertion
+        ));
+        assert!(drain_messages(&mut carol).is_empty());
+        assert!(drain_messages(&mut mallory).is_empty());
+        assert!(matches!(
+            drain_messages(&mut alice).as_slice(),
+            [ServerMessage::Error { code, .. }] if code == "target_different_room"
+        ));
+    }
 }
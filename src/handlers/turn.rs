@@ -1,61 +1,384 @@
 //! TURN 자격증명 핸들러
 
 use crate::config::TurnConfig;
+use crate::metrics::RefreshOutcome;
 use crate::protocol::{IceServer, ServerMessage, TurnConfigData};
 use crate::state::AppState;
+use anyhow::{bail, Context, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::future::BoxFuture;
 use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use sha1::Sha1;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc::UnboundedSender;
 
 type HmacSha1 = Hmac<Sha1>;
 
-/// TURN 설정 요청 처리
+/// 외부 자격증명 서비스 응답 대기 한도
+const CREDENTIAL_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// TURN 서버 도달 확인 한 번의 대기 한도
+const TURN_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 실패한 TURN 도달 확인을 다시 시도하는 간격 (REQUIRE_TURN_READY)
+const TURN_PROBE_RETRY: Duration = Duration::from_secs(5);
+
+/// 발급된 TURN username/password
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TurnCredentials {
+    pub username: String,
+    #[serde(alias = "credential")]
+    pub password: String,
+    /// 유효 시간 (초). 없으면 TURN_CREDENTIAL_TTL로 안내한다
+    #[serde(default)]
+    pub ttl: Option<u64>,
+}
+
+/// TURN 자격증명 발급 방식
+pub trait TurnCredentialProvider: Send + Sync {
+    fn issue(&self) -> BoxFuture<'_, Result<TurnCredentials>>;
+}
+
+/// TURN_SECRET으로 RFC 5766 HMAC-SHA1 자격증명을 만드는 기본 방식
+pub struct HmacCredentialProvider {
+    secret: String,
+    ttl: u64,
+    ttl_jitter: u64,
+}
+
+impl TurnCredentialProvider for HmacCredentialProvider {
+    fn issue(&self) -> BoxFuture<'_, Result<TurnCredentials>> {
+        let ttl = jittered_ttl(self.ttl, self.ttl_jitter);
+        Box::pin(async move { generate_credentials(&self.secret, ttl) })
+    }
+}
+
+/// `ttl ± jitter` 범위의 무작위 TTL. 최소 1초.
+fn jittered_ttl(ttl: u64, jitter: u64) -> u64 {
+    if jitter == 0 {
+        return ttl;
+    }
+    let low = ttl.saturating_sub(jitter).max(1);
+    let high = ttl.saturating_add(jitter).max(low);
+    rand::thread_rng().gen_range(low..=high)
+}
+
+/// TURN_CREDENTIAL_ENDPOINT에 GET 요청해 단기 자격증명을 받아 오는 방식.
+/// 응답은 `{"username": "...", "password": "...", "ttl": 600}` JSON이다 (`ttl`은 생략 가능).
+pub struct HttpCredentialProvider {
+    http: reqwest::Client,
+    endpoint: String,
+}
+
+impl TurnCredentialProvider for HttpCredentialProvider {
+    fn issue(&self) -> BoxFuture<'_, Result<TurnCredentials>> {
+        Box::pin(async {
+            let credentials: TurnCredentials = self
+                .http
+                .get(&self.endpoint)
+                .timeout(CREDENTIAL_FETCH_TIMEOUT)
+                .send()
+                .await
+                .context("TURN credential endpoint request failed")?
+                .error_for_status()
+                .context("TURN credential endpoint returned an error")?
+                .json()
+                .await
+                .context("invalid TURN credential endpoint response")?;
+            if credentials.username.is_empty() || credentials.password.is_empty() {
+                bail!("TURN credential endpoint returned empty credentials");
+            }
+            Ok(credentials)
+        })
+    }
+}
+
+/// TURN_CREDENTIAL_ENDPOINT가 있으면 HTTP 방식, 없으면 HMAC 방식
+pub fn credential_provider_from_config(
+    config: &TurnConfig,
+    http: &reqwest::Client,
+) -> Arc<dyn TurnCredentialProvider> {
+    if config.credential_endpoint.is_empty() {
+        Arc::new(HmacCredentialProvider {
+            secret: config.secret.clone(),
+            ttl: config.credential_ttl,
+            ttl_jitter: config.credential_ttl_jitter,
+        })
+    } else {
+        Arc::new(HttpCredentialProvider {
+            http: http.clone(),
+            endpoint: config.credential_endpoint.clone(),
+        })
+    }
+}
+
+/// TURN 설정 요청 처리. 발급한 자격증명의 만료 시각을 세션에 기록한다 (TURN_EXPIRY_REMINDER_MS).
 pub async fn handle_turn_config_request(
     state: Arc<AppState>,
+    peer_id: &str,
     sender: &UnboundedSender<ServerMessage>,
     room_id: &str,
+    correlation_id: Option<String>,
 ) {
     let turn_config = &state.config.turn;
 
-    if turn_config.url.is_empty() || turn_config.secret.is_empty() {
+    if turn_config.url.is_empty() || !turn_config.has_credential_source() {
         let _ = sender.send(ServerMessage::TurnConfig {
             success: false,
             data: None,
             error: Some("TURN server not configured".to_string()),
+            correlation_id,
         });
         return;
     }
 
-    let credentials = generate_credentials(turn_config);
-    let now = SystemTime::now()
+    let credentials = match state.turn_credentials.issue().await {
+        Ok(credentials) => credentials,
+        Err(err) => {
+            tracing::error!(room_id = %room_id, error = %err, "Failed to generate TURN credentials");
+            let _ = sender.send(ServerMessage::TurnConfig {
+                success: false,
+                data: None,
+                error: Some("TURN credential generation failed".to_string()),
+                correlation_id,
+            });
+            return;
+        }
+    };
+    let force_relay = state
+        .rooms
+        .get(room_id)
+        .is_some_and(|room| room.options.force_relay);
+    let now_ms = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
-        .as_secs();
+        .as_millis() as u64;
+    let now = now_ms / 1000;
+    let ttl = credentials.ttl.unwrap_or(turn_config.credential_ttl);
 
     let _ = sender.send(ServerMessage::TurnConfig {
         success: true,
         data: Some(TurnConfigData {
-            ice_servers: credentials,
-            ttl: turn_config.credential_ttl,
+            ice_servers: build_ice_servers(
+                turn_config,
+                &credentials.username,
+                &credentials.password,
+            ),
+            ttl,
             timestamp: now,
             room_id: room_id.to_string(),
+            ice_transport_policy: if force_relay { "relay" } else { "all" }.to_string(),
         }),
         error: None,
+        correlation_id,
     });
+    state.metrics.turn.record_issued();
+    if let Some(session) = state.peers.get(peer_id) {
+        let expires_at_ms = now_ms.saturating_add(ttl.saturating_mul(1000));
+        session
+            .turn_expires_at_ms
+            .store(expires_at_ms, Ordering::Relaxed);
+    }
 
     tracing::info!(room_id = %room_id, "TURN config sent");
 }
 
-/// TURN 자격증명 생성 (RFC 5766 HMAC-SHA1)
-fn generate_credentials(config: &TurnConfig) -> Vec<IceServer> {
+/// 자격증명 만료가 TURN_EXPIRY_REMINDER_MS 안으로 다가온 피어에게 `CredentialExpiringSoon`을 보낸다.
+/// 발급 한 번에 한 번만 보낸다. 안내한 피어 수를 돌려준다.
+pub fn remind_expiring_credentials(state: &AppState) -> usize {
+    let lead_ms = state.config.turn.expiry_reminder_ms;
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let mut reminded = 0;
+    for session in state.peers.iter() {
+        let expires_at_ms = session.turn_expires_at_ms.load(Ordering::Relaxed);
+        if expires_at_ms == 0 || expires_at_ms > now_ms.saturating_add(lead_ms) {
+            continue;
+        }
+        // 그 사이 재발급되었으면 새 만료 시각을 기준으로 다음에 다시 본다
+        if session
+            .turn_expires_at_ms
+            .compare_exchange(expires_at_ms, 0, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            continue;
+        }
+        let _ = session.sender.send(ServerMessage::CredentialExpiringSoon {
+            expires_in_ms: expires_at_ms.saturating_sub(now_ms),
+        });
+        reminded += 1;
+    }
+    if reminded > 0 {
+        tracing::debug!(peers = reminded, "Sent TURN credential expiry reminders");
+    }
+    reminded
+}
+
+/// 자격증명 갱신 요청 처리. 아직 유효하면 재발급하지 않는다.
+/// 갱신 시점의 자격증명 나이와 결과(유효/만료/해석 불가)를 메트릭으로 남긴다.
+pub async fn handle_turn_refresh(
+    state: Arc<AppState>,
+    peer_id: &str,
+    sender: &UnboundedSender<ServerMessage>,
+    room_id: &str,
+    current_username: &str,
+) {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
-    let expiry_time = now + config.credential_ttl;
+    let issued_at = credential_issued_at(current_username);
+    let outcome = if validate_credentials(current_username) {
+        RefreshOutcome::StillValid
+    } else if issued_at.is_some() {
+        RefreshOutcome::Expired
+    } else {
+        RefreshOutcome::Invalid
+    };
+    state
+        .metrics
+        .turn
+        .record_refresh(outcome, issued_at.map(|issued| now.saturating_sub(issued)));
+
+    if outcome == RefreshOutcome::StillValid {
+        let _ = sender.send(ServerMessage::TurnConfig {
+            success: true,
+            data: None,
+            error: Some("Credentials still valid".to_string()),
+            correlation_id: None,
+        });
+    } else {
+        handle_turn_config_request(state, peer_id, sender, room_id, None).await;
+    }
+}
+
+/// /health, /ready에 보고하는 TURN 설정 상태
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnStatus {
+    Ok,
+    /// 일부만 설정되어 RequestTurnConfig가 실패하거나 쓸 수 없는 URL을 내준다
+    Misconfigured,
+    /// TURN_SERVER_URL/TURN_SECRET 모두 비어 있음
+    Disabled,
+}
+
+/// url/secret/전송/포트 설정이 서로 맞는지 확인한다.
+pub fn turn_status(config: &TurnConfig) -> TurnStatus {
+    let url = normalize_turn_host(&config.url);
+    match (url.is_empty(), !config.has_credential_source()) {
+        (true, true) => return TurnStatus::Disabled,
+        (false, false) => {}
+        _ => return TurnStatus::Misconfigured,
+    }
+    let transports = [
+        (config.enable_udp, config.ports.udp),
+        (config.enable_tcp, config.ports.tcp),
+        (config.enable_tls, config.ports.tls),
+    ];
+    let any_enabled = transports.iter().any(|(enabled, _)| *enabled);
+    let ports_valid = transports
+        .iter()
+        .all(|(enabled, port)| !enabled || *port != 0);
+    if !any_enabled || !ports_valid || config.credential_ttl == 0 {
+        return TurnStatus::Misconfigured;
+    }
+    TurnStatus::Ok
+}
+
+/// TURN 서버에 실제로 닿는지 확인한다. TCP/TLS가 켜져 있으면 그 포트로 TCP 연결을,
+/// UDP만 켜져 있으면 STUN Binding 요청에 응답이 오는지 본다.
+pub async fn probe_turn_server(config: &TurnConfig) -> bool {
+    if turn_status(config) != TurnStatus::Ok {
+        return false;
+    }
+    let host = normalize_turn_host(&config.url);
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let probe = async {
+        if config.enable_tcp || config.enable_tls {
+            let port = if config.enable_tcp {
+                config.ports.tcp
+            } else {
+                config.ports.tls
+            };
+            tokio::net::TcpStream::connect((host, port)).await.is_ok()
+        } else {
+            stun_binding(host, config.ports.udp).await.is_ok()
+        }
+    };
+    tokio::time::timeout(TURN_PROBE_TIMEOUT, probe)
+        .await
+        .unwrap_or(false)
+}
+
+/// STUN Binding 요청을 보내고 같은 transaction id의 응답(성공/에러 무관)을 기다린다.
+async fn stun_binding(host: &str, port: u16) -> std::io::Result<()> {
+    let addr = tokio::net::lookup_host((host, port))
+        .await?
+        .next()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no address"))?;
+    let local = if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = tokio::net::UdpSocket::bind(local).await?;
+    socket.connect(addr).await?;
+    let transaction: [u8; 12] = rand::random();
+    // type 0x0001 (Binding Request), length 0, magic cookie
+    let mut request = vec![0x00, 0x01, 0x00, 0x00, 0x21, 0x12, 0xa4, 0x42];
+    request.extend_from_slice(&transaction);
+    socket.send(&request).await?;
+    let mut response = [0u8; 576];
+    loop {
+        let len = socket.recv(&mut response).await?;
+        if len >= 20 && response[8..20] == transaction {
+            return Ok(());
+        }
+    }
+}
+
+/// REQUIRE_TURN_READY: 도달 확인이 처음 성공할 때까지 재시도하고, 성공하면 /ready를 연다.
+pub async fn run_turn_readiness_probe(state: &AppState) {
+    loop {
+        if probe_turn_server(&state.config.turn).await {
+            state.turn_reachable.store(true, Ordering::Release);
+            tracing::info!("TURN probe succeeded, instance is ready");
+            return;
+        }
+        tracing::warn!(
+            retry_ms = TURN_PROBE_RETRY.as_millis() as u64,
+            "TURN probe failed, staying not ready"
+        );
+        tokio::time::sleep(TURN_PROBE_RETRY).await;
+    }
+}
+
+/// `user_{발급 시각}_{난수}:{만료 시각}` 형식 username에서 발급 시각을 읽는다.
+fn credential_issued_at(username: &str) -> Option<u64> {
+    let (base, expiry) = username.rsplit_once(':')?;
+    expiry.parse::<u64>().ok()?;
+    base.strip_prefix("user_")?.split('_').next()?.parse().ok()
+}
+
+/// TURN 자격증명 생성 (RFC 5766 HMAC-SHA1)
+fn generate_credentials(secret: &str, ttl: u64) -> Result<TurnCredentials> {
+    if secret.is_empty() {
+        bail!("TURN secret is empty");
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("system clock is before the unix epoch")?
+        .as_secs();
+    let expiry_time = now
+        .checked_add(ttl)
+        .context("TURN_CREDENTIAL_TTL overflows the expiry time")?;
 
     // username 생성
     let random: u64 = rand::random();
@@ -63,18 +386,20 @@ fn generate_credentials(config: &TurnConfig) -> Vec<IceServer> {
     let credential_username = format!("{}:{}", base_username, expiry_time);
 
     // HMAC-SHA1 해시 생성
-    let password = generate_hmac_hash(&credential_username, &config.secret);
+    let password = generate_hmac_hash(&credential_username, secret)?;
 
-    // ICE 서버 목록 생성
-    build_ice_servers(config, &credential_username, &password)
+    Ok(TurnCredentials {
+        username: credential_username,
+        password,
+        ttl: Some(ttl),
+    })
 }
 
-fn generate_hmac_hash(username: &str, secret: &str) -> String {
-    let mut mac =
-        HmacSha1::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+fn generate_hmac_hash(username: &str, secret: &str) -> Result<String> {
+    let mut mac = HmacSha1::new_from_slice(secret.as_bytes()).context("invalid HMAC key")?;
     mac.update(username.as_bytes());
     let result = mac.finalize();
-    BASE64.encode(result.into_bytes())
+    Ok(BASE64.encode(result.into_bytes()))
 }
 
 fn build_ice_servers(config: &TurnConfig, username: &str, password: &str) -> Vec<IceServer> {
@@ -155,6 +480,28 @@ fn build_ice_servers(config: &TurnConfig, username: &str, password: &str) -> Vec
         });
     }
 
+    // 항상 포함하는 공개 STUN 서버. 위에서 이미 추가된 URL은 건너뛴다.
+    for stun in &config.stun_servers {
+        let stun = stun.trim();
+        if stun.is_empty() {
+            continue;
+        }
+        let stun_url = if stun.starts_with("stun:") {
+            stun.to_string()
+        } else {
+            format!("stun:{stun}")
+        };
+        if servers.iter().any(|server| server.urls.contains(&stun_url)) {
+            continue;
+        }
+        servers.push(IceServer {
+            urls: vec![stun_url],
+            username: None,
+            credential: None,
+            credential_type: None,
+        });
+    }
+
     servers
 }
 
@@ -204,6 +551,7 @@ pub fn validate_credentials(username: &str) -> bool {
 mod tests {
     use super::*;
     use crate::config::{TurnConfig, TurnPorts};
+    use std::collections::HashSet;
 
     fn turn_config_with_fallbacks(fallback_servers: Vec<String>) -> TurnConfig {
         TurnConfig {
@@ -219,8 +567,261 @@ mod tests {
                 tls: 443,
             },
             credential_ttl: 600,
+            credential_ttl_jitter: 0,
+            expiry_reminder_ms: 0,
+            require_ready: false,
             fallback_servers,
+            stun_servers: Vec::new(),
+            credential_endpoint: String::new(),
+        }
+    }
+
+    #[test]
+    fn turn_status_reports_each_configuration_state() {
+        let complete = turn_config_with_fallbacks(Vec::new());
+        assert_eq!(turn_status(&complete), TurnStatus::Ok);
+
+        let mut disabled = complete.clone();
+        disabled.url = String::new();
+        disabled.secret = String::new();
+        assert_eq!(turn_status(&disabled), TurnStatus::Disabled);
+
+        let mut no_secret = complete.clone();
+        no_secret.secret = String::new();
+        let mut no_url = complete.clone();
+        no_url.url = "turn:".to_string();
+        let mut zero_port = complete.clone();
+        zero_port.ports.tcp = 0;
+        let mut no_transport = complete.clone();
+        no_transport.enable_udp = false;
+        no_transport.enable_tcp = false;
+        for config in [no_secret, no_url, zero_port, no_transport] {
+            let status = turn_status(&config);
+            assert_eq!(status, TurnStatus::Misconfigured, "{config:?}");
+        }
+
+        // 꺼진 전송의 포트는 보지 않는다
+        let mut unused_tls_port = complete;
+        unused_tls_port.ports.tls = 0;
+        assert_eq!(turn_status(&unused_tls_port), TurnStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn credential_generation_failure_is_reported_instead_of_panicking() {
+        let mut config = crate::config::Config::from_env();
+        config.turn = turn_config_with_fallbacks(Vec::new());
+        config.turn.credential_ttl = u64::MAX;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        handle_turn_config_request(state.clone(), "peer", &tx, "room", None).await;
+
+        assert!(matches!(
+            crate::state::drain_messages(&mut rx).as_slice(),
+            [ServerMessage::TurnConfig {
+                success: false,
+                data: None,
+                error: Some(_),
+                correlation_id: None,
+            }]
+        ));
+        assert_eq!(state.metrics.turn.issued(), 0);
+    }
+
+    #[tokio::test]
+    async fn default_provider_issues_hmac_credentials_for_the_secret() {
+        let config = turn_config_with_fallbacks(Vec::new());
+        let provider = credential_provider_from_config(&config, &reqwest::Client::new());
+
+        let credentials = provider.issue().await.expect("hmac credentials");
+
+        assert_eq!(credentials.ttl, Some(600));
+        assert!(validate_credentials(&credentials.username));
+        let expected = generate_hmac_hash(&credentials.username, "test-secret").unwrap();
+        assert_eq!(credentials.password, expected);
+    }
+
+    #[tokio::test]
+    async fn jittered_expiries_vary_within_the_window() {
+        let mut config = turn_config_with_fallbacks(Vec::new());
+        config.credential_ttl_jitter = 60;
+        let provider = credential_provider_from_config(&config, &reqwest::Client::new());
+
+        let mut ttls = HashSet::new();
+        for _ in 0..50 {
+            let credentials = provider.issue().await.expect("hmac credentials");
+            let ttl = credentials.ttl.unwrap();
+            assert!((540..=660).contains(&ttl), "ttl outside window: {ttl}");
+            let (base, expiry) = credentials.username.rsplit_once(':').unwrap();
+            let issued = credential_issued_at(&credentials.username).unwrap();
+            assert!(base.starts_with("user_"));
+            assert_eq!(expiry.parse::<u64>().unwrap(), issued + ttl);
+            assert!(validate_credentials(&credentials.username));
+            ttls.insert(ttl);
+        }
+        assert!(ttls.len() > 1, "jitter should spread expiries: {ttls:?}");
+    }
+
+    #[tokio::test]
+    async fn http_provider_credentials_are_passed_to_the_client() {
+        use axum::{http::StatusCode, routing::get, Json, Router};
+
+        let app = Router::new()
+            .route(
+                "/turn",
+                get(|| async {
+                    Json(serde_json::json!({
+                        "username": "svc-user",
+                        "password": "svc-pass",
+                        "ttl": 120,
+                    }))
+                }),
+            )
+            .route("/broken", get(|| async { StatusCode::BAD_GATEWAY }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let state_for = |path: &str| {
+            let mut config = crate::config::Config::from_env();
+            config.turn = turn_config_with_fallbacks(Vec::new());
+            config.turn.secret = String::new();
+            config.turn.credential_endpoint = format!("http://{addr}{path}");
+            assert_eq!(turn_status(&config.turn), TurnStatus::Ok);
+            Arc::new(AppState::new_for_test_with_config(config))
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        handle_turn_config_request(state_for("/turn"), "peer", &tx, "room", None).await;
+        match crate::state::drain_messages(&mut rx).as_slice() {
+            [ServerMessage::TurnConfig {
+                success: true,
+                data: Some(data),
+                ..
+            }] => {
+                assert_eq!(data.ttl, 120);
+                let turn_servers: Vec<&IceServer> = data
+                    .ice_servers
+                    .iter()
+                    .filter(|server| server.username.is_some())
+                    .collect();
+                assert!(!turn_servers.is_empty());
+                assert!(turn_servers.iter().all(|server| {
+                    server.username.as_deref() == Some("svc-user")
+                        && server.credential.as_deref() == Some("svc-pass")
+                }));
+            }
+            other => panic!("unexpected messages: {other:?}"),
         }
+
+        handle_turn_config_request(state_for("/broken"), "peer", &tx, "room", None).await;
+        assert!(matches!(
+            crate::state::drain_messages(&mut rx).as_slice(),
+            [ServerMessage::TurnConfig { success: false, .. }]
+        ));
+    }
+
+    #[tokio::test]
+    async fn force_relay_room_requests_relay_transport_policy() {
+        let mut config = crate::config::Config::from_env();
+        config.turn = turn_config_with_fallbacks(Vec::new());
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut room = crate::state::Room::new("private".to_string());
+        room.options.force_relay = true;
+        state.rooms.insert("private".to_string(), room);
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        handle_turn_config_request(state.clone(), "peer", &tx, "private", None).await;
+        handle_turn_config_request(state.clone(), "peer", &tx, "open", None).await;
+
+        let policies: Vec<String> = crate::state::drain_messages(&mut rx)
+            .into_iter()
+            .filter_map(|message| match message {
+                ServerMessage::TurnConfig {
+                    data: Some(data), ..
+                } => Some(data.ice_transport_policy),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(policies, ["relay", "all"]);
+    }
+
+    #[tokio::test]
+    async fn turn_config_echoes_request_correlation_id() {
+        let mut config = crate::config::Config::from_env();
+        config.turn = turn_config_with_fallbacks(Vec::new());
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let correlation_id = Some("req-7".to_string());
+        handle_turn_config_request(state.clone(), "peer", &tx, "room", correlation_id).await;
+        handle_turn_config_request(state, "peer", &tx, "room", None).await;
+
+        let ids: Vec<Option<String>> = crate::state::drain_messages(&mut rx)
+            .into_iter()
+            .filter_map(|message| match message {
+                ServerMessage::TurnConfig { correlation_id, .. } => Some(correlation_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, [Some("req-7".to_string()), None]);
+    }
+
+    #[tokio::test]
+    async fn expiry_reminder_fires_once_before_credentials_expire() {
+        let mut config = crate::config::Config::from_env();
+        config.turn = turn_config_with_fallbacks(Vec::new());
+        config.turn.credential_ttl = 1;
+        config.turn.expiry_reminder_ms = 800;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut rx = state.insert_test_peer("peer");
+        let tx = state.peers.get("peer").unwrap().sender.clone();
+
+        handle_turn_config_request(state.clone(), "peer", &tx, "room", None).await;
+        crate::state::drain_messages(&mut rx);
+        // 만료까지 1초 남아 아직 안내 범위(800ms) 밖이다
+        assert_eq!(remind_expiring_credentials(&state), 0);
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(remind_expiring_credentials(&state), 1);
+        match crate::state::drain_messages(&mut rx).as_slice() {
+            [ServerMessage::CredentialExpiringSoon { expires_in_ms }] => {
+                assert!(*expires_in_ms > 0 && *expires_in_ms <= 800);
+            }
+            other => panic!("unexpected messages: {other:?}"),
+        }
+        assert_eq!(remind_expiring_credentials(&state), 0);
+    }
+
+    #[tokio::test]
+    async fn issuing_and_refreshing_records_turn_metrics() {
+        let mut config = crate::config::Config::from_env();
+        config.turn = turn_config_with_fallbacks(Vec::new());
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        handle_turn_config_request(state.clone(), "peer", &tx, "room", None).await;
+        let valid = format!("user_{}_ab:{}", now - 120, now + 3600);
+        handle_turn_refresh(state.clone(), "peer", &tx, "room", &valid).await;
+        let expired = format!("user_{}_ab:{}", now - 7200, now - 1);
+        handle_turn_refresh(state.clone(), "peer", &tx, "room", &expired).await;
+        handle_turn_refresh(state.clone(), "peer", &tx, "room", "garbage").await;
+        crate::state::drain_messages(&mut rx);
+
+        let turn = &state.metrics.turn;
+        // 최초 발급 1회 + 만료/해석 불가 갱신에 따른 재발급 2회
+        assert_eq!(turn.issued(), 3);
+        assert_eq!(turn.refreshes(RefreshOutcome::StillValid), 1);
+        assert_eq!(turn.refreshes(RefreshOutcome::Expired), 1);
+        assert_eq!(turn.refreshes(RefreshOutcome::Invalid), 1);
+
+        let text = state.metrics.render(state.config.turn.credential_ttl);
+        assert!(text.contains("turn_credential_age_at_refresh_seconds_count 2"));
+        assert!(text.contains("turn_credential_age_at_refresh_seconds_bucket{le=\"300\"} 1"));
     }
 
     #[test]
@@ -246,6 +847,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn configured_stun_servers_are_always_appended_without_duplicates() {
+        let mut config = turn_config_with_fallbacks(Vec::new());
+        config.stun_servers = vec![
+            "stun:stun.l.google.com:19302".to_string(),
+            "stun1.l.google.com:19302".to_string(),
+            "stun:ponslink.com:3478".to_string(),
+        ];
+
+        let servers = build_ice_servers(&config, "user:123", "password");
+        let stun_urls: Vec<&str> = servers
+            .iter()
+            .filter(|server| server.username.is_none())
+            .flat_map(|server| server.urls.iter().map(String::as_str))
+            .collect();
+
+        assert_eq!(
+            stun_urls,
+            vec![
+                "stun:ponslink.com:3478",
+                "stun:stun.l.google.com:19302",
+                "stun:stun1.l.google.com:19302",
+            ]
+        );
+    }
+
     #[test]
     fn turn_server_url_with_scheme_and_port_is_normalized_before_composing_ice_urls() {
         let mut config = turn_config_with_fallbacks(vec!["stun:stun.l.google.com:19302".to_string()]);
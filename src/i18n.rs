@@ -0,0 +1,180 @@
+//! 서버가 만드는 에러 문구의 언어 (`?lang=`, SetLocale)
+//!
+//! `Error.code`는 언어와 무관하게 그대로 두고 사람이 읽는 `message`만 바꾼다.
+//! 카탈로그에 없는 code는 원문(영어)을 보낸다. 한도 값처럼 원문에 실행 중에 정해지는 내용이 든
+//! code는 그 내용을 잃지 않도록 카탈로그에 넣지 않는다. 운영자가 정한 Notice(MOTD) 문구는 바꾸지 않는다.
+
+use crate::protocol::ServerMessage;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 지원하는 언어
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    Ko,
+}
+
+impl Locale {
+    /// 언어 태그(`ko`, `ko-KR`, `en_US` 등)의 기본 언어로 고른다. 지원하지 않으면 None.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next().unwrap_or_default();
+        match primary.to_ascii_lowercase().as_str() {
+            "en" => Some(Self::En),
+            "ko" => Some(Self::Ko),
+            _ => None,
+        }
+    }
+}
+
+/// 연결의 현재 언어 (세션과 송신 태스크가 공유)
+#[derive(Debug, Default)]
+pub struct LocaleCell(AtomicU8);
+
+impl LocaleCell {
+    pub fn get(&self) -> Locale {
+        match self.0.load(Ordering::Relaxed) {
+            1 => Locale::Ko,
+            _ => Locale::En,
+        }
+    }
+
+    pub fn set(&self, locale: Locale) {
+        let value = match locale {
+            Locale::En => 0,
+            Locale::Ko => 1,
+        };
+        self.0.store(value, Ordering::Relaxed);
+    }
+}
+
+/// 에러 code의 번역 문구. `original`은 서버가 만든 영어 원문이다
+fn error_message(code: &str, original: &str, locale: Locale) -> Option<String> {
+    if locale == Locale::En {
+        return None;
+    }
+    let text = match code {
+        // 원문이 관리자가 적은 사유 그대로다
+        "closed_by_admin" if original.is_empty() => "관리자가 연결을 종료했습니다",
+        "closed_by_admin" => {
+            return Some(format!("관리자가 연결을 종료했습니다 (사유: {original})"))
+        }
+        "not_in_room" => "방에 참여하고 있지 않습니다",
+        "not_room_owner" => "방 owner만 할 수 있습니다",
+        "target_required" => "대상(target)을 지정해야 합니다",
+        "target_different_room" => "대상이 같은 방에 있지 않습니다",
+        "self_target" => "자기 자신에게는 보낼 수 없습니다",
+        "observer_readonly" => "관전자는 시그널링 메시지를 보낼 수 없습니다",
+        "rate_limited" => "요청이 너무 많습니다. 잠시 후 다시 시도하세요",
+        "room_rate_limited" => "방의 메시지가 너무 많습니다. 잠시 후 다시 시도하세요",
+        "server_at_capacity" => "서버 연결 수가 가득 찼습니다. 잠시 후 다시 시도하세요",
+        "server_overloaded" => "서버가 바쁩니다. 잠시 후 다시 시도하세요",
+        "roomless_peer_limit" => "방에 들어가지 않은 연결이 너무 많습니다. 잠시 후 다시 시도하세요",
+        "roomless_timeout" => "제한 시간 안에 방에 들어가지 않아 연결을 종료했습니다",
+        "lifetime_exceeded" => "연결 유지 시간이 지났습니다. 다시 연결하세요",
+        "invalid_room_id" => "방 id에 제어 문자나 보이지 않는 문자가 있습니다",
+        "room_draining" => "점검 중인 방이라 입장할 수 없습니다",
+        "global_user_cap" => "서버 전체 참여 인원이 가득 찼습니다",
+        "ip_limit" => "이 주소에서 방에 참여한 연결이 너무 많습니다",
+        "owner_room_limit" => "이 주소에서 만들 수 있는 방 수를 넘었습니다",
+        "content_blocked" => "허용되지 않는 내용이 있어 보내지 않았습니다",
+        "fanout_too_large" => "방 인원이 많아 전체 전송을 할 수 없습니다. target을 지정하세요",
+        "inbound_queue_full" => "처리 대기 중인 메시지가 너무 많습니다",
+        "unsupported_locale" => "지원하지 않는 언어입니다",
+        "room_transition_too_fast" => "방 입장/퇴장이 너무 잦습니다. 잠시 후 다시 시도하세요",
+        _ => return None,
+    };
+    Some(text.to_string())
+}
+
+/// 보내기 직전에 `Error.message`를 연결의 언어로 바꾼다.
+pub fn localize(message: ServerMessage, locale: Locale) -> ServerMessage {
+    match message {
+        ServerMessage::Error {
+            code,
+            message,
+            retry_after_ms,
+            correlation_id,
+        } => {
+            let message = error_message(&code, &message, locale).unwrap_or(message);
+            ServerMessage::Error {
+                code,
+                message,
+                retry_after_ms,
+                correlation_id,
+            }
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(code: &str, message: &str) -> ServerMessage {
+        ServerMessage::Error {
+            code: code.to_string(),
+            message: message.to_string(),
+            retry_after_ms: None,
+            correlation_id: None,
+        }
+    }
+
+    #[test]
+    fn known_error_renders_in_selected_locale_and_keeps_code() {
+        let original = error("not_in_room", "Not in any room");
+        for (locale, expected) in [
+            (Locale::Ko, "방에 참여하고 있지 않습니다"),
+            (Locale::En, "Not in any room"),
+        ] {
+            match localize(original.clone(), locale) {
+                ServerMessage::Error { code, message, .. } => {
+                    assert_eq!(code, "not_in_room");
+                    assert_eq!(message, expected);
+                }
+                other => panic!("unexpected message: {other:?}"),
+            }
+        }
+
+        // 카탈로그에 없는 code는 원문을 보낸다
+        assert!(matches!(
+            localize(error("custom_code", "Original text"), Locale::Ko),
+            ServerMessage::Error { message, .. } if message == "Original text"
+        ));
+    }
+
+    #[test]
+    fn runtime_details_survive_localization() {
+        let localized = |code, text| match localize(error(code, text), Locale::Ko) {
+            ServerMessage::Error { message, .. } => message,
+            other => panic!("unexpected message: {other:?}"),
+        };
+
+        assert_eq!(
+            localized("closed_by_admin", "abuse"),
+            "관리자가 연결을 종료했습니다 (사유: abuse)"
+        );
+        assert_eq!(
+            localized("too_many_targets", "At most 64 targets are allowed"),
+            "At most 64 targets are allowed"
+        );
+        assert_eq!(
+            localized("unknown_field", "Unknown field `romId`"),
+            "Unknown field `romId`"
+        );
+    }
+
+    #[test]
+    fn locale_tags_resolve_to_primary_language() {
+        assert_eq!(Locale::parse("ko"), Some(Locale::Ko));
+        assert_eq!(Locale::parse("ko-KR"), Some(Locale::Ko));
+        assert_eq!(Locale::parse("EN_us"), Some(Locale::En));
+        assert_eq!(Locale::parse("fr"), None);
+
+        let cell = LocaleCell::default();
+        assert_eq!(cell.get(), Locale::En);
+        cell.set(Locale::Ko);
+        assert_eq!(cell.get(), Locale::Ko);
+    }
+}
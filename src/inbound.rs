@@ -0,0 +1,185 @@
+//! 연결별 수신 큐 (INBOUND_QUEUE_DEPTH)
+//!
+//! 수신 루프는 메시지를 큐에 넣기만 하고, 연결마다 띄운 처리 태스크가 꺼내서 처리한다.
+//! TURN 요청처럼 느릴 수 있는 제어 메시지는 별도 lane에서 처리하므로
+//! 뒤따르는 Offer/Answer/IceCandidate가 그 처리를 기다리지 않는다. lane 안에서는 순서를 지킨다.
+
+use crate::config::InboundDropPolicy;
+use crate::protocol::ClientMessage;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+/// 깊이가 정해진 단일 소비자 큐
+pub struct InboundQueue<T> {
+    items: Mutex<VecDeque<T>>,
+    depth: usize,
+    policy: InboundDropPolicy,
+    notify: Notify,
+    closed: AtomicBool,
+}
+
+impl<T> InboundQueue<T> {
+    pub fn new(depth: usize, policy: InboundDropPolicy) -> Self {
+        Self {
+            items: Mutex::new(VecDeque::with_capacity(depth)),
+            depth: depth.max(1),
+            policy,
+            notify: Notify::new(),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// 큐에 넣는다. 가득 차서 정책에 따라 메시지를 버렸으면 false.
+    pub fn push(&self, item: T) -> bool {
+        let accepted = {
+            let mut items = self.items.lock().unwrap();
+            if items.len() < self.depth {
+                items.push_back(item);
+                true
+            } else {
+                if self.policy == InboundDropPolicy::DropOldest {
+                    items.pop_front();
+                    items.push_back(item);
+                }
+                false
+            }
+        };
+        self.notify.notify_one();
+        accepted
+    }
+
+    /// 다음 메시지를 기다린다. 닫힌 뒤 비어 있으면 None.
+    pub async fn pop(&self) -> Option<T> {
+        loop {
+            let notified = self.notify.notified();
+            if let Some(item) = self.items.lock().unwrap().pop_front() {
+                return Some(item);
+            }
+            if self.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            notified.await;
+        }
+    }
+
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.notify.notify_one();
+    }
+}
+
+/// 처리 시간이 길 수 있어 시그널링과 분리하는 메시지
+fn is_control(message: &ClientMessage) -> bool {
+    matches!(
+        message,
+        ClientMessage::RequestTurnConfig { .. }
+            | ClientMessage::RefreshTurnCredentials { .. }
+            | ClientMessage::CheckTurnServerStatus
+    )
+}
+
+/// 연결 하나의 시그널링/제어 lane과 처리 태스크
+pub struct InboundLanes {
+    signaling: Arc<InboundQueue<ClientMessage>>,
+    control: Arc<InboundQueue<ClientMessage>>,
+    workers: [JoinHandle<()>; 2],
+}
+
+impl InboundLanes {
+    /// lane마다 처리 태스크를 띄운다. `handle`은 메시지 하나를 처리하는 future를 만든다.
+    pub fn spawn<F, Fut>(depth: usize, policy: InboundDropPolicy, handle: F) -> Self
+    where
+        F: Fn(ClientMessage) -> Fut + Clone + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let signaling = Arc::new(InboundQueue::new(depth, policy));
+        let control = Arc::new(InboundQueue::new(depth, policy));
+        let workers = [
+            spawn_worker(signaling.clone(), handle.clone()),
+            spawn_worker(control.clone(), handle),
+        ];
+        Self {
+            signaling,
+            control,
+            workers,
+        }
+    }
+
+    /// 메시지를 해당 lane에 넣는다. 큐가 가득 차 버린 메시지가 있으면 false.
+    pub fn dispatch(&self, message: ClientMessage) -> bool {
+        if is_control(&message) {
+            self.control.push(message)
+        } else {
+            self.signaling.push(message)
+        }
+    }
+
+    /// 연결 종료 시 남은 메시지를 버리고 처리 태스크를 멈춘다.
+    pub fn shutdown(self) {
+        self.signaling.close();
+        self.control.close();
+        for worker in self.workers {
+            worker.abort();
+        }
+    }
+}
+
+fn spawn_worker<F, Fut>(queue: Arc<InboundQueue<ClientMessage>>, handle: F) -> JoinHandle<()>
+where
+    F: Fn(ClientMessage) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        while let Some(message) = queue.pop().await {
+            handle(message).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn full_queue_applies_drop_policy() {
+        let newest = InboundQueue::new(2, InboundDropPolicy::DropNewest);
+        let oldest = InboundQueue::new(2, InboundDropPolicy::DropOldest);
+        for queue in [&newest, &oldest] {
+            assert!(queue.push(1));
+            assert!(queue.push(2));
+            assert!(!queue.push(3));
+        }
+        assert_eq!(*newest.items.lock().unwrap(), [1, 2]);
+        assert_eq!(*oldest.items.lock().unwrap(), [2, 3]);
+    }
+
+    #[tokio::test]
+    async fn slow_control_message_does_not_block_signaling() {
+        let (done_tx, mut done_rx) = mpsc::unbounded_channel();
+        let lanes = InboundLanes::spawn(8, InboundDropPolicy::DropNewest, move |message| {
+            let done_tx = done_tx.clone();
+            async move {
+                let slow = is_control(&message);
+                if slow {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                let _ = done_tx.send(slow);
+            }
+        });
+
+        lanes.dispatch(ClientMessage::CheckTurnServerStatus);
+        lanes.dispatch(ClientMessage::Heartbeat);
+
+        let first = tokio::time::timeout(Duration::from_millis(500), done_rx.recv())
+            .await
+            .expect("signaling lane must not wait for the TURN request");
+        assert_eq!(first, Some(false));
+        lanes.shutdown();
+    }
+}
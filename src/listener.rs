@@ -0,0 +1,60 @@
+//! 수신 소켓 설정 (TCP_NODELAY, TCP_KEEPALIVE_SECS, LISTEN_BACKLOG)
+//!
+//! 옵션은 listen 소켓에 걸어 두어 수락한 연결이 그대로 물려받게 한다.
+//! TCP_NODELAY는 axum이 수락 시점에도 다시 적용한다 (`main`의 `tcp_nodelay`).
+
+use crate::config::LimitsConfig;
+use anyhow::{Context, Result};
+use socket2::{Domain, Protocol, Socket, TcpKeepalive, Type};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// `addr`에 소켓 옵션을 적용한 listener를 연다.
+pub async fn bind(addr: &str, limits: &LimitsConfig) -> Result<TcpListener> {
+    let socket_addr = tokio::net::lookup_host(addr)
+        .await?
+        .next()
+        .with_context(|| format!("no address for {addr}"))?;
+    let socket = Socket::new(
+        Domain::for_address(socket_addr),
+        Type::STREAM,
+        Some(Protocol::TCP),
+    )?;
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.set_nodelay(limits.tcp_nodelay)?;
+    if limits.tcp_keepalive_secs > 0 {
+        let keepalive =
+            TcpKeepalive::new().with_time(Duration::from_secs(limits.tcp_keepalive_secs));
+        socket.set_tcp_keepalive(&keepalive)?;
+    }
+    socket.set_nonblocking(true)?;
+    socket.bind(&socket_addr.into())?;
+    socket.listen(limits.listen_backlog.max(1))?;
+    Ok(TcpListener::from_std(socket.into())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use socket2::SockRef;
+
+    #[tokio::test]
+    async fn accepted_socket_inherits_configured_options() {
+        let mut limits = Config::from_env().limits;
+        limits.tcp_nodelay = true;
+        limits.tcp_keepalive_secs = 42;
+        let listener = bind("127.0.0.1:0", &limits).await.expect("bind");
+        let addr = listener.local_addr().unwrap();
+
+        let _client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (accepted, _) = listener.accept().await.unwrap();
+
+        assert!(accepted.nodelay().unwrap());
+        let socket = SockRef::from(&accepted);
+        assert!(socket.keepalive().unwrap());
+        #[cfg(target_os = "linux")]
+        assert_eq!(socket.keepalive_time().unwrap(), Duration::from_secs(42));
+    }
+}
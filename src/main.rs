@@ -3,22 +3,31 @@
 mod admin;
 mod auth;
 mod billing;
+mod cluster;
 mod config;
+mod crypto;
 mod database;
 mod handlers;
+mod i18n;
+mod inbound;
+mod listener;
 mod mesh;
+mod metrics;
+mod persistence;
 mod protocol;
+mod rate_limit;
 mod state;
+mod version;
 
 use anyhow::{Context, Result};
 use axum::{
     extract::{
-        ws::{Message, WebSocket},
-        State, WebSocketUpgrade,
+        ws::{CloseFrame, Message, WebSocket},
+        ConnectInfo, Query, State, WebSocketUpgrade,
     },
     http::{
-        header::{AUTHORIZATION, CONTENT_TYPE},
-        HeaderValue, Method, StatusCode,
+        header::{AUTHORIZATION, CONTENT_TYPE, ORIGIN},
+        HeaderMap, HeaderValue, Method, StatusCode,
     },
     response::{Html, IntoResponse, Json},
     routing::{get, post, put},
@@ -27,24 +36,44 @@ use axum::{
 use config::Config;
 use futures::{SinkExt, StreamExt};
 use protocol::{ClientMessage, ServerMessage};
+use serde::Deserialize;
 use state::AppState;
-use std::sync::Arc;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 use tokio::sync::mpsc;
 use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::Instrument;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let config = Config::from_env();
 
+    // 소켓을 열지 않고 설정만 검사 (--check-config)
+    if std::env::args().any(|arg| arg == "--check-config") {
+        let ok = check_config(&config, &mut std::io::stdout());
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
     // 로깅 초기화
     tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(&config.log_level))
+        .with(tracing_subscriber::EnvFilter::new(config.log_filter()))
         .with(tracing_subscriber::fmt::layer())
         .init();
 
     let state = Arc::new(AppState::new(config.clone()).await?);
 
+    // 이전 실행에서 저장한 방 복원
+    let persistence_path = (!config.persistence_path.is_empty())
+        .then(|| std::path::PathBuf::from(&config.persistence_path));
+    if let Some(path) = &persistence_path {
+        if let Err(err) = persistence::restore_rooms(&state, path).await {
+            tracing::warn!(error = %err, "Failed to restore room metadata");
+        }
+    }
+
     // 방 정리 스케줄러
     let cleanup_state = state.clone();
     tokio::spawn(async move {
@@ -52,6 +81,98 @@ async fn main() -> Result<()> {
         loop {
             interval.tick().await;
             handlers::cleanup_old_rooms(cleanup_state.clone()).await;
+            handlers::cleanup_stale_manifest_chunks(&cleanup_state);
+            handlers::cleanup_stale_offers(&cleanup_state);
+            cleanup_state.metrics.rooms.prune_creation_windows();
+            cleanup_state.maintain_maps();
+        }
+    });
+
+    // 빈 방 유예가 끝난 방 정리 (EMPTY_ROOM_LINGER_MS)
+    let linger_ms = config.room.empty_room_linger_ms;
+    if linger_ms > 0 {
+        let linger_state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(linger_ms.min(1_000)));
+            loop {
+                interval.tick().await;
+                handlers::reap_empty_rooms(&linger_state);
+            }
+        });
+    }
+
+    // 시그널링이 조용한 방에 KeepAlive 안내 (ROOM_KEEPALIVE_INTERVAL_MS)
+    let keepalive_ms = config.room.keepalive_interval_ms;
+    if keepalive_ms > 0 {
+        let keepalive_state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(keepalive_ms));
+            loop {
+                interval.tick().await;
+                handlers::nudge_idle_rooms(&keepalive_state).await;
+            }
+        });
+    }
+
+    // 방에 들어가지 않고 ROOMLESS_GRACE_MS를 넘긴 연결 정리 (MAX_ROOMLESS_PEERS)
+    if config.limits.max_roomless_peers > 0 {
+        let roomless_state = state.clone();
+        let sweep_ms = config.limits.roomless_grace_ms.clamp(1, 1_000);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(sweep_ms));
+            loop {
+                interval.tick().await;
+                handlers::disconnect_roomless_peers(&roomless_state);
+            }
+        });
+    }
+
+    // 만료가 다가온 TURN 자격증명 안내 (TURN_EXPIRY_REMINDER_MS)
+    let reminder_ms = config.turn.expiry_reminder_ms;
+    if reminder_ms > 0 {
+        let reminder_state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(reminder_ms.min(1_000)));
+            loop {
+                interval.tick().await;
+                handlers::remind_expiring_credentials(&reminder_state);
+            }
+        });
+    }
+
+    // TURN 도달 확인 전까지 /ready를 닫아 둔다 (REQUIRE_TURN_READY)
+    if config.turn.require_ready {
+        let probe_state = state.clone();
+        tokio::spawn(async move { handlers::run_turn_readiness_probe(&probe_state).await });
+    }
+
+    // 다른 노드에서 발행한 중계 수신 (CLUSTER_BACKEND=redis)
+    let cluster_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(err) = cluster::run_subscriber(cluster_state).await {
+            tracing::error!(error = %err, "Cluster relay subscriber failed");
+        }
+    });
+
+    // presence 유효 시간이 지나기 전에 이 노드의 멤버를 다시 기록한다
+    if config.cluster.backend == "redis" {
+        let presence_state = state.clone();
+        let refresh_every = Duration::from_secs(config.cluster.presence_ttl_secs) / 3;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(refresh_every.max(Duration::from_secs(1)));
+            loop {
+                interval.tick().await;
+                cluster::refresh_presence(&presence_state).await;
+            }
+        });
+    }
+
+    // 브로드캐스트 중 발견된 죽은 피어 정리
+    let reaper_state = state.clone();
+    tokio::spawn(async move {
+        loop {
+            reaper_state.dead_peers_notify.notified().await;
+            handlers::reap_dead_peers(&reaper_state).await;
         }
     });
 
@@ -72,15 +193,95 @@ async fn main() -> Result<()> {
         }
     });
 
+    let app = build_router(state.clone())?;
+
+    let addr = format!("{}:{}", config.host, config.port);
+    let listener = listener::bind(&addr, &config.limits)
+        .await
+        .with_context(|| format!("failed to bind {addr}"))?;
+
+    tracing::info!("🚀 PonsWarp Rust Signaling Server started");
+    tracing::info!("Address: {}", addr);
+    tracing::info!("WebSocket: ws://{}{}", addr, config.ws_path);
+    tracing::info!("Instance: {}", state.instance_id);
+
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .tcp_nodelay(config.limits.tcp_nodelay);
+    tokio::select! {
+        result = server => result.context("server failed")?,
+        _ = shutdown_signal() => tracing::info!("Shutdown signal received"),
+    }
+
+    if let Some(path) = &persistence_path {
+        if let Err(err) = persistence::save_rooms(&state, path).await {
+            tracing::error!(error = %err, "Failed to save room metadata");
+        }
+    }
+    Ok(())
+}
+
+/// 비밀 값을 가린 유효 설정과 검증 결과를 출력한다. 문제가 없으면 true.
+fn check_config(config: &Config, out: &mut impl Write) -> bool {
+    let mut errors = config.validate().err().unwrap_or_default();
+    if let Err(err) = cors_layer(config) {
+        errors.push(format!("{err:#}"));
+    }
+
+    let effective = serde_json::to_string_pretty(config).unwrap_or_default();
+    let _ = writeln!(out, "{effective}");
+    if errors.is_empty() {
+        let _ = writeln!(out, "Configuration OK");
+        return true;
+    }
+    for error in &errors {
+        let _ = writeln!(out, "error: {error}");
+    }
+    false
+}
+
+/// Ctrl+C 또는 SIGTERM 대기
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+fn build_router(state: Arc<AppState>) -> Result<Router> {
+    let config = state.config.clone();
+    let config = config.as_ref();
+
     // CORS 설정
-    let cors = cors_layer(&config)?;
+    let cors = cors_layer(config)?;
 
     // 라우터 설정
+    let base_path = config.base_path.as_str();
     let app = Router::new()
         .route("/", get(index_handler))
-        .route("/health", get(health_handler))
-        .route("/ready", get(readiness_handler))
-        .route("/ws", get(ws_handler))
+        .route(&format!("{base_path}/health"), get(health_handler))
+        .route(&format!("{base_path}/ready"), get(readiness_handler))
+        .route(&format!("{base_path}/version"), get(version_handler))
+        .route(&format!("{base_path}/load"), get(load_handler))
+        .route(&format!("{base_path}/metrics"), get(metrics_handler))
+        .route(&config.ws_path, get(ws_handler))
         .route("/api/mesh/health", get(mesh::mesh_health))
         .route("/api/mesh/ready", get(mesh::mesh_ready))
         .route("/api/mesh/workspaces", post(mesh::create_workspace))
@@ -137,6 +338,15 @@ async fn main() -> Result<()> {
         .route("/api/admin/me", get(admin::me))
         .route("/api/admin/overview", get(admin::overview))
         .route("/api/admin/operations", get(admin::operations))
+        .route("/admin/motd", put(admin::update_motd))
+        .route("/admin/config", get(admin::effective_config))
+        .route("/admin/events", get(admin::events))
+        .route("/admin/events/ticket", post(admin::events_ticket))
+        .route("/admin/rooms/:room_id/ping", post(admin::ping_room))
+        .route("/admin/rooms/:room_id/close", post(admin::close_room))
+        .route("/admin/rooms/:room_id/drain", post(admin::drain_room))
+        .route("/admin/rooms/:room_id/reserve", post(admin::reserve_room))
+        .route("/admin/peers/:peer_id/close", post(admin::close_peer))
         .route("/api/cloud-share", post(handlers::create_cloud_share))
         .route("/api/billing/checkout", post(billing::create_checkout))
         .route("/api/billing/capture", post(billing::capture_checkout))
@@ -163,19 +373,9 @@ async fn main() -> Result<()> {
             get(handlers::download_cloud_file),
         )
         .layer(cors)
-        .with_state(state.clone());
-
-    let addr = format!("{}:{}", config.host, config.port);
-    let listener = tokio::net::TcpListener::bind(&addr)
-        .await
-        .with_context(|| format!("failed to bind {addr}"))?;
-
-    tracing::info!("🚀 PonsWarp Rust Signaling Server started");
-    tracing::info!("Address: {}", addr);
-    tracing::info!("WebSocket: ws://{}/ws", addr);
+        .with_state(state);
 
-    axum::serve(listener, app).await.context("server failed")?;
-    Ok(())
+    Ok(app)
 }
 
 fn cors_layer(config: &Config) -> Result<CorsLayer> {
@@ -206,14 +406,19 @@ fn cors_layer(config: &Config) -> Result<CorsLayer> {
         .allow_credentials(true))
 }
 
-async fn index_handler() -> Html<&'static str> {
-    Html("<h1>PonsWarp Signaling Server (Rust)</h1><p>WebSocket endpoint: /ws</p>")
+async fn index_handler(State(state): State<Arc<AppState>>) -> Html<String> {
+    Html(format!(
+        "<h1>PonsWarp Signaling Server (Rust)</h1><p>WebSocket endpoint: {}</p>",
+        state.config.ws_path
+    ))
 }
 
-async fn health_handler() -> Json<serde_json::Value> {
+async fn health_handler(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
     Json(serde_json::json!({
         "status": "ok",
         "server": "ponswarp-signaling-rs",
+        "instanceId": state.instance_id,
+        "turn": handlers::turn_status(&state.config.turn),
         "timestamp": std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
@@ -221,10 +426,54 @@ async fn health_handler() -> Json<serde_json::Value> {
     }))
 }
 
+async fn version_handler() -> Json<version::BuildInfo> {
+    Json(version::BuildInfo::current())
+}
+
+/// 클라이언트 측 서버 선택용 부하 정보. MAX_CONNECTIONS가 없으면 load_factor는 0이다.
+async fn load_handler(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let connections = state.peers.len();
+    let max_connections = state.config.limits.max_connections;
+    let load_factor = if max_connections == 0 {
+        0.0
+    } else {
+        (connections as f64 / max_connections as f64).min(1.0)
+    };
+    Json(serde_json::json!({
+        "connections": connections,
+        "max_connections": max_connections,
+        "rooms": state.rooms.len(),
+        "load_factor": load_factor,
+    }))
+}
+
+async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let roomless = state
+        .roomless_peers
+        .load(std::sync::atomic::Ordering::Acquire);
+    state.metrics.connections.set_roomless_peers(roomless);
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.metrics.render(state.config.turn.credential_ttl),
+    )
+}
+
 async fn readiness_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let cloud_ready = !state.config.cloud.enabled || state.cloud.is_some();
     let billing_ready = !state.config.cloud.billing_enabled || state.cloud_db.is_some();
-    let ready = cloud_ready && billing_ready;
+    // 꺼진 TURN은 준비 완료로 보고, 반쯤 설정된 TURN만 배포 시점에 드러낸다
+    let turn = handlers::turn_status(&state.config.turn);
+    let probed = state
+        .turn_reachable
+        .load(std::sync::atomic::Ordering::Acquire);
+    let turn_reachable = !state.config.turn.require_ready || probed;
+    let ready = cloud_ready
+        && billing_ready
+        && turn_reachable
+        && turn != handlers::TurnStatus::Misconfigured;
     let status = if ready {
         StatusCode::OK
     } else {
@@ -237,29 +486,214 @@ async fn readiness_handler(State(state): State<Arc<AppState>>) -> impl IntoRespo
             "status": if ready { "ready" } else { "not_ready" },
             "cloudShare": cloud_ready,
             "billing": billing_ready,
+            "turn": turn,
+            "turnReachable": turn_reachable,
         })),
     )
 }
 
-async fn ws_handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+/// WebSocket 연결 쿼리 파라미터
+#[derive(Debug, Deserialize)]
+struct WsParams {
+    client_token: Option<String>,
+    /// 재접속 유예 중인 세션을 이어받을 때 `Connected.resume_token` 값
+    resume_token: Option<String>,
+    /// 에러 문구 언어 (예: ko). 지원하지 않으면 영어
+    lang: Option<String>,
+}
+
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    Query(params): Query<WsParams>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    State(state): State<Arc<AppState>>,
+) -> axum::response::Response {
+    // 브라우저는 다른 origin 페이지에서도 업그레이드하므로 CORS와 별도로 막는다 (WS_ENFORCE_ORIGIN)
+    if state.config.ws_enforce_origin && !ws_origin_allowed(&state.config, &headers) {
+        tracing::warn!(origin = ?headers.get(ORIGIN), "Rejected WebSocket upgrade from disallowed origin");
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    // 연결 폭주 시 업그레이드/등록을 동시에 이만큼만 진행한다 (MAX_CONCURRENT_UPGRADES)
+    let upgrade_permit = match &state.upgrade_permits {
+        Some(permits) => match permits.clone().try_acquire_owned() {
+            Ok(permit) => Some(permit),
+            Err(_) => {
+                tracing::warn!("Too many concurrent upgrades, rejected connection");
+                let retry_after = [(axum::http::header::RETRY_AFTER, "1")];
+                return (StatusCode::SERVICE_UNAVAILABLE, retry_after).into_response();
+            }
+        },
+        None => None,
+    };
+    let remote_ip = client_ip(
+        &state.config,
+        connect_info.map(|ConnectInfo(addr)| addr.ip()),
+        &headers,
+    );
+    let span = tracing::info_span!("connection", instance_id = %state.instance_id);
+    // 분할(continuation) frame은 tungstenite가 FIN까지 모아 완성된 Text로 넘긴다.
+    // 합친 크기가 한도를 넘으면 파싱 전에 연결이 오류로 끝난다.
+    let max_message_bytes = state.config.limits.max_message_bytes;
+    let ws = if max_message_bytes > 0 {
+        ws.max_message_size(max_message_bytes)
+            .max_frame_size(max_message_bytes)
+    } else {
+        ws
+    };
+    let client_token = params.client_token;
+    let resume_token = params.resume_token;
+    let locale = params
+        .lang
+        .as_deref()
+        .and_then(i18n::Locale::parse)
+        .unwrap_or_default();
+    ws.on_upgrade(move |socket| {
+        handle_socket(
+            socket,
+            state,
+            client_token,
+            resume_token,
+            locale,
+            remote_ip,
+            upgrade_permit,
+        )
+        .instrument(span)
+    })
+    .into_response()
+}
+
+/// Origin이 CORS_ORIGINS에 있는지 확인한다. `*`이면 모두 허용하고,
+/// Origin을 보내지 않는 네이티브 클라이언트는 막지 않는다.
+fn ws_origin_allowed(config: &Config, headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(ORIGIN) else {
+        return true;
+    };
+    let Ok(origin) = origin.to_str() else {
+        return false;
+    };
+    let origin = origin.trim().trim_end_matches('/');
+    config.cors_origins.iter().any(|allowed| {
+        let allowed = allowed.trim().trim_end_matches('/');
+        allowed == "*" || allowed.eq_ignore_ascii_case(origin)
+    })
 }
 
-async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
+/// IP별 제한에 쓸 클라이언트 주소. 접속 주소가 TRUSTED_PROXIES에 있으면 X-Forwarded-For를
+/// 오른쪽부터 읽어 신뢰하는 프록시가 아닌 첫 주소를 쓴다. 믿지 않는 접속이 보낸 헤더는 무시한다.
+fn client_ip(config: &Config, peer: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+    let trusted = |ip: &IpAddr| {
+        config
+            .limits
+            .trusted_proxies
+            .iter()
+            .any(|proxy| proxy.parse::<IpAddr>().is_ok_and(|proxy| proxy == *ip))
+    };
+    let peer = peer?;
+    if !trusted(&peer) {
+        return Some(peer);
+    }
+    let forwarded = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    for hop in forwarded.iter().rev() {
+        let Ok(ip) = hop.parse::<IpAddr>() else {
+            // 해석할 수 없는 항목 뒤로는 믿을 수 없으므로 마지막으로 확인한 프록시로 본다
+            break;
+        };
+        if !trusted(&ip) {
+            return Some(ip);
+        }
+    }
+    Some(peer)
+}
+
+async fn handle_socket(
+    socket: WebSocket,
+    state: Arc<AppState>,
+    client_token: Option<String>,
+    resume_token: Option<String>,
+    locale: i18n::Locale,
+    remote_ip: Option<IpAddr>,
+    upgrade_permit: Option<tokio::sync::OwnedSemaphorePermit>,
+) {
     let (mut ws_sender, mut ws_receiver) = socket.split();
-    let (tx, mut rx) = mpsc::unbounded_channel::<ServerMessage>();
+
+    // 동시 연결 수 확인 (거부 시 retry_after_ms 힌트를 보내고 종료)
+    // 확보한 슬롯은 이 함수가 끝날 때 돌려준다
+    let _connection_slot = match handlers::check_capacity(&state) {
+        Ok(slot) => slot,
+        Err(rejection) => {
+            if let Ok(json) = serde_json::to_string(&i18n::localize(*rejection, locale)) {
+                let _ = ws_sender.send(Message::Text(json)).await;
+            }
+            let close = Message::Close(Some(ServerClose::AtCapacity.frame()));
+            let _ = ws_sender.send(close).await;
+            return;
+        }
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel::<ServerMessage>();
 
     // 연결 처리
-    let peer_id = handlers::handle_connection(state.clone(), tx.clone()).await;
+    let peer_id = handlers::handle_connection(
+        state.clone(),
+        tx.clone(),
+        client_token,
+        remote_ip,
+        resume_token,
+    )
+    .await;
+    // 등록이 끝나면 다음 업그레이드가 진행할 수 있다
+    drop(upgrade_permit);
+
+    let stats = state
+        .peers
+        .get(&peer_id)
+        .map(|session| session.stats.clone())
+        .unwrap_or_default();
+    let stats_for_send = stats.clone();
+    let subscriptions = state
+        .peers
+        .get(&peer_id)
+        .map(|session| session.subscriptions.clone())
+        .unwrap_or_default();
+    let session_locale = state
+        .peers
+        .get(&peer_id)
+        .map(|session| {
+            session.locale.set(locale);
+            session.locale.clone()
+        })
+        .unwrap_or_default();
 
     // 송신 태스크
-    let send_task = tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if let Ok(json) = serde_json::to_string(&msg) {
-                if ws_sender.send(Message::Text(json)).await.is_err() {
-                    break;
-                }
-            }
+    let send_timeout = (state.config.limits.send_timeout_ms > 0)
+        .then(|| Duration::from_millis(state.config.limits.send_timeout_ms));
+    let shed_backlog = state.config.limits.outbound_shed_backlog;
+    let send_peer_id = peer_id.clone();
+    let signer = state.message_signer.clone();
+    // 서버가 먼저 끊는 이유 (수명 초과, 관리자 close). 송신 태스크가 Close frame으로 보낸다.
+    let server_close = Arc::new(OnceLock::new());
+    let close_for_send = server_close.clone();
+    let mut send_task = tokio::spawn(async move {
+        let exit = forward_messages(
+            ws_sender,
+            rx,
+            stats_for_send,
+            subscriptions,
+            session_locale,
+            signer,
+            close_for_send,
+            send_timeout,
+            shed_backlog,
+        );
+        if exit.await == SendExit::TimedOut {
+            tracing::warn!(peer_id = %send_peer_id, "WebSocket send timed out, dropping peer");
         }
     });
 
@@ -267,54 +701,325 @@ async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
     let state_clone = state.clone();
     let peer_id_clone = peer_id.clone();
     let tx_clone = tx.clone();
+    let mut rate_limiter = rate_limit::TokenBucket::from_config(&state.config.limits);
 
-    while let Some(result) = ws_receiver.next().await {
-        match result {
-            Ok(Message::Text(text)) => {
-                if let Ok(msg) = serde_json::from_str::<ClientMessage>(&text) {
-                    handle_client_message(&state_clone, &peer_id_clone, &tx_clone, msg).await;
-                }
+    // 수신 큐 (INBOUND_QUEUE_DEPTH). 설정되면 처리 태스크가 메시지를 처리한다.
+    let inbound_depth = state.config.limits.inbound_queue_depth;
+    let lanes = (inbound_depth > 0).then(|| {
+        let state = state.clone();
+        let peer_id = peer_id.clone();
+        let tx = tx.clone();
+        inbound::InboundLanes::spawn(
+            inbound_depth,
+            state.config.limits.inbound_drop_policy,
+            move |msg| {
+                let state = state.clone();
+                let peer_id = peer_id.clone();
+                let tx = tx.clone();
+                async move { handle_client_message(&state, &peer_id, &tx, msg).await }
+            },
+        )
+    });
+
+    // 연결 최대 유지 시간 (MAX_CONNECTION_LIFETIME_MS)
+    let lifetime_ms = state.config.limits.max_connection_lifetime_ms;
+    let expires_at = state
+        .peers
+        .get(&peer_id)
+        .filter(|_| lifetime_ms > 0)
+        .map(|session| session.connected_at + Duration::from_millis(lifetime_ms));
+    let expiry = async {
+        match expires_at {
+            Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(expiry);
+    let strict_fields = state.config.signaling.strict_client_fields;
+    let close_signal = state
+        .peers
+        .get(&peer_id)
+        .map(|session| session.close_signal.clone())
+        .unwrap_or_default();
+
+    loop {
+        // 송신 태스크가 끝났으면 (전송 실패/시간 초과) 더 받지 않고 연결을 정리한다.
+        let result = tokio::select! {
+            result = ws_receiver.next() => match result {
+                Some(result) => result,
+                None => break,
+            },
+            _ = &mut send_task => break,
+            _ = &mut expiry => {
+                let _ = tx.send(handlers::lifetime_exceeded_error());
+                let _ = server_close.set(ServerClose::LifetimeExceeded);
+                break;
             }
+            _ = close_signal.notified() => {
+                let _ = server_close.set(ServerClose::ClosedByAdmin);
+                break;
+            }
+        };
+        // 바이너리 frame은 BinaryRelay 전용이다 (protocol::binary)
+        let (bytes, parsed) = match result {
+            Ok(Message::Text(text)) => (text.len(), decode_text(&text, strict_fields)),
+            Ok(Message::Binary(frame)) => (
+                frame.len(),
+                protocol::binary::decode_client_frame(&frame)
+                    .map_err(|reason| Some(Box::new(handlers::invalid_binary_frame_error(reason)))),
+            ),
             Ok(Message::Close(_)) => break,
             Err(_) => break,
-            _ => {}
+            _ => continue,
+        };
+        stats.record_received(bytes);
+        if let Some(Err(retry_after_ms)) = rate_limiter.as_mut().map(|l| l.try_acquire()) {
+            let _ = tx_clone.send(handlers::rate_limited_error(retry_after_ms));
+            continue;
+        }
+        let msg = match parsed {
+            Ok(msg) => msg,
+            Err(Some(reply)) => {
+                let _ = tx_clone.send(*reply);
+                continue;
+            }
+            Err(None) => continue,
+        };
+        match &lanes {
+            Some(lanes) => {
+                if !lanes.dispatch(msg) {
+                    let _ = tx_clone.send(handlers::inbound_dropped_error());
+                }
+            }
+            None => handle_client_message(&state_clone, &peer_id_clone, &tx_clone, msg).await,
         }
     }
 
-    // 연결 해제
+    // 연결 해제 (큐에 남은 메시지는 처리하지 않는다)
+    if let Some(lanes) = lanes {
+        lanes.shutdown();
+    }
     handlers::handle_disconnect(state, &peer_id).await;
+    if server_close.get().is_some() {
+        // 서버가 먼저 끊는 경우 세션이 제거되어 채널이 닫히면
+        // 송신 태스크가 남은 메시지를 보내고 이유를 담은 Close로 마무리한다.
+        drop(tx);
+        drop(tx_clone);
+        let _ = tokio::time::timeout(Duration::from_secs(1), &mut send_task).await;
+    }
     send_task.abort();
 }
 
+/// Text frame을 해석한다. 해석할 수 없으면 조용히 버리고(`Err(None)`),
+/// 엄격 모드에서 알 수 없는 필드가 있으면 에러 응답을 돌려준다.
+fn decode_text(text: &str, strict: bool) -> Result<ClientMessage, Option<Box<ServerMessage>>> {
+    if strict {
+        return protocol::strict::decode_strict(text)
+            .map_err(|field| field.map(|field| Box::new(handlers::unknown_field_error(&field))));
+    }
+    serde_json::from_str(text).map_err(|_| None)
+}
+
+/// 서버가 먼저 연결을 닫는 이유. Close frame의 code/reason으로 클라이언트에 알린다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ServerClose {
+    /// MAX_CONNECTIONS 초과 (1013 Try Again Later)
+    AtCapacity,
+    /// MAX_CONNECTION_LIFETIME_MS 초과
+    LifetimeExceeded,
+    /// 관리자 API로 끊음
+    ClosedByAdmin,
+}
+
+impl ServerClose {
+    fn frame(self) -> CloseFrame<'static> {
+        let (code, reason) = match self {
+            Self::AtCapacity => (1013, "server_at_capacity"),
+            Self::LifetimeExceeded => (4000, "lifetime_exceeded"),
+            Self::ClosedByAdmin => (4001, "closed_by_admin"),
+        };
+        CloseFrame {
+            code,
+            reason: reason.into(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum SendExit {
+    /// 채널이 닫힘 (정상 종료)
+    Closed,
+    Failed,
+    TimedOut,
+}
+
+/// 채널의 메시지를 sink로 보낸다. 구독하지 않은 종류는 버리고,
+/// 한 번의 전송이 `send_timeout`을 넘기면 중단한다.
+/// 대기 메시지가 `shed_backlog` 이상 쌓여 있으면 낮은 우선순위 메시지를 버린다 (0이면 끔).
+/// 에러 문구는 보내기 직전에 연결의 언어(`locale`)로 바꾼다.
+#[allow(clippy::too_many_arguments)]
+async fn forward_messages<S>(
+    mut sink: S,
+    mut rx: mpsc::UnboundedReceiver<ServerMessage>,
+    stats: Arc<state::PeerStats>,
+    subscriptions: Arc<state::MessageFilter>,
+    locale: Arc<i18n::LocaleCell>,
+    signer: Option<Arc<protocol::signing::MessageSigner>>,
+    server_close: Arc<OnceLock<ServerClose>>,
+    send_timeout: Option<Duration>,
+    shed_backlog: usize,
+) -> SendExit
+where
+    S: futures::Sink<Message> + Unpin,
+{
+    // 서명한 frame의 연결별 번호 (재전송 방지)
+    let mut signed_seq = 0u64;
+    while let Some(msg) = rx.recv().await {
+        if !subscriptions.allows(&msg) {
+            continue;
+        }
+        if shed_backlog > 0
+            && rx.len() >= shed_backlog
+            && msg.priority() == protocol::MessagePriority::Low
+        {
+            continue;
+        }
+        let msg = i18n::localize(msg, locale.get());
+        let (frame, bytes) = match protocol::binary::encode_server_frame(&msg) {
+            Some(data) => {
+                let bytes = data.len();
+                (Message::Binary(data), bytes)
+            }
+            None => match serde_json::to_string(&msg) {
+                Ok(json) => {
+                    let json = match &signer {
+                        Some(signer) => {
+                            signed_seq += 1;
+                            signer.attach(json, signed_seq)
+                        }
+                        None => json,
+                    };
+                    let bytes = json.len();
+                    (Message::Text(json), bytes)
+                }
+                Err(_) => continue,
+            },
+        };
+        let send = sink.send(frame);
+        let sent = match send_timeout {
+            Some(limit) => match tokio::time::timeout(limit, send).await {
+                Ok(sent) => sent,
+                Err(_) => return SendExit::TimedOut,
+            },
+            None => send.await,
+        };
+        if sent.is_err() {
+            return SendExit::Failed;
+        }
+        stats.record_sent(bytes);
+    }
+    if let Some(reason) = server_close.get() {
+        let _ = sink.send(Message::Close(Some(reason.frame()))).await;
+    }
+    let _ = sink.close().await;
+    SendExit::Closed
+}
+
 async fn handle_client_message(
     state: &Arc<AppState>,
     peer_id: &str,
     sender: &mpsc::UnboundedSender<ServerMessage>,
     msg: ClientMessage,
 ) {
+    // 서버 전체 처리 한도 (MAX_CONCURRENT_HANDLERS). Heartbeat는 연결 유지용이라 세지 않는다.
+    let _permit = match &state.handler_permits {
+        Some(permits) if !matches!(msg, ClientMessage::Heartbeat) => match permits.try_acquire() {
+            Ok(permit) => Some(permit),
+            Err(_) => {
+                let _ = sender.send(handlers::server_overloaded_error());
+                return;
+            }
+        },
+        _ => None,
+    };
     match msg {
         ClientMessage::Heartbeat => {
-            handlers::handle_heartbeat(sender);
+            handlers::handle_heartbeat(state, peer_id).await;
+        }
+        ClientMessage::WhoAmI => {
+            handlers::handle_whoami(state, peer_id).await;
+        }
+        ClientMessage::Hello { capabilities } => {
+            handlers::handle_hello(state, peer_id, capabilities);
+        }
+        ClientMessage::Subscribe { types } => {
+            handlers::handle_subscribe(state, peer_id, types);
+        }
+        ClientMessage::IsPeerOnline {
+            peer_id: target_peer_id,
+        } => {
+            handlers::handle_peer_status_query(state, peer_id, &target_peer_id).await;
+        }
+        ClientMessage::JoinRoom {
+            room_id,
+            role,
+            options,
+            correlation_id,
+        } => {
+            handlers::handle_join_room_with(
+                state.clone(),
+                peer_id,
+                &room_id,
+                role.unwrap_or_default(),
+                options,
+                &handlers::RequestContext { correlation_id },
+            )
+            .await;
+        }
+        ClientMessage::LeaveRoom(payload) => {
+            let room_id = payload.and_then(|payload| payload.room_id);
+            handlers::handle_leave_room(state.clone(), peer_id, room_id.as_deref()).await;
+        }
+        ClientMessage::SetRoomMetadata { room_id, metadata } => {
+            handlers::handle_set_room_metadata(state.clone(), peer_id, &room_id, metadata).await;
+        }
+        ClientMessage::SetRoomName { room_id, name } => {
+            handlers::handle_set_room_name(state.clone(), peer_id, &room_id, &name).await;
+        }
+        ClientMessage::ListPublicRooms => {
+            handlers::handle_list_public_rooms(state.clone(), peer_id).await;
+        }
+        ClientMessage::ResyncRoom {
+            room_id,
+            correlation_id,
+        } => {
+            let context = handlers::RequestContext { correlation_id };
+            handlers::handle_resync_room(state.clone(), peer_id, &room_id, &context).await;
         }
-        ClientMessage::JoinRoom { room_id } => {
-            handlers::handle_join_room(state.clone(), peer_id, &room_id).await;
+        ClientMessage::EndSession { room_id } => {
+            handlers::handle_end_session(state.clone(), peer_id, &room_id).await;
         }
-        ClientMessage::LeaveRoom => {
-            handlers::handle_leave_room(state.clone(), peer_id).await;
+        ClientMessage::GetRoomOwner { room_id } => {
+            handlers::handle_get_room_owner(state.clone(), peer_id, &room_id).await;
         }
         ClientMessage::Offer {
             room_id,
             sdp,
             target,
+            generation,
         } => {
-            handlers::handle_offer(state.clone(), peer_id, &room_id, &sdp, target.as_deref()).await;
+            let target = target.as_deref();
+            handlers::handle_offer(state.clone(), peer_id, &room_id, &sdp, target, generation)
+                .await;
         }
         ClientMessage::Answer {
             room_id,
             sdp,
             target,
+            generation,
         } => {
-            handlers::handle_answer(state.clone(), peer_id, &room_id, &sdp, target.as_deref())
+            let target = target.as_deref();
+            handlers::handle_answer(state.clone(), peer_id, &room_id, &sdp, target, generation)
                 .await;
         }
         ClientMessage::IceCandidate {
@@ -331,6 +1036,80 @@ async fn handle_client_message(
             )
             .await;
         }
+        ClientMessage::IceRestart { room_id, targets } => {
+            handlers::handle_ice_restart(state.clone(), peer_id, &room_id, &targets).await;
+        }
+        ClientMessage::CoordinateIceRestart { room_id } => {
+            handlers::handle_coordinate_ice_restart(state.clone(), peer_id, &room_id).await;
+        }
+        ClientMessage::TransferProgress {
+            room_id,
+            target,
+            percent,
+            bytes,
+        } => {
+            handlers::handle_transfer_progress(
+                state.clone(),
+                peer_id,
+                &room_id,
+                target.as_deref(),
+                percent,
+                bytes,
+            )
+            .await;
+        }
+        ClientMessage::BinaryRelay {
+            room_id,
+            target,
+            data,
+        } => {
+            handlers::handle_binary_relay(state.clone(), peer_id, &room_id, &target, data).await;
+        }
+        ClientMessage::IdentityAssertion {
+            room_id,
+            target,
+            assertion,
+        } => {
+            handlers::handle_identity_assertion(
+                state.clone(),
+                peer_id,
+                &room_id,
+                &target,
+                assertion,
+            )
+            .await;
+        }
+        ClientMessage::RelayToRole {
+            room_id,
+            role,
+            event,
+            data,
+        } => {
+            handlers::handle_relay_to_role(state.clone(), peer_id, &room_id, role, event, data)
+                .await;
+        }
+        ClientMessage::SetLocale { lang } => {
+            handlers::handle_set_locale(state, peer_id, &lang);
+        }
+        ClientMessage::SetCapabilities { capabilities } => {
+            handlers::handle_set_capabilities(state, peer_id, capabilities);
+        }
+        ClientMessage::RelayToCapable {
+            room_id,
+            capability,
+            event,
+            data,
+        } => {
+            handlers::handle_relay_to_capable(
+                state.clone(),
+                peer_id,
+                &room_id,
+                capability,
+                event,
+                data,
+            )
+            .await;
+        }
         ClientMessage::Manifest {
             room_id,
             manifest,
@@ -345,6 +1124,24 @@ async fn handle_client_message(
             )
             .await;
         }
+        ClientMessage::ManifestChunk {
+            room_id,
+            target,
+            chunk_index,
+            total_chunks,
+            data,
+        } => {
+            handlers::handle_manifest_chunk(
+                state.clone(),
+                peer_id,
+                &room_id,
+                target.as_deref(),
+                chunk_index,
+                total_chunks,
+                &data,
+            )
+            .await;
+        }
         ClientMessage::TransferReady { room_id, target } => {
             handlers::handle_transfer_ready(state.clone(), peer_id, &room_id, target.as_deref())
                 .await;
@@ -353,22 +1150,32 @@ async fn handle_client_message(
             handlers::handle_transfer_complete(state.clone(), peer_id, &room_id, target.as_deref())
                 .await;
         }
-        ClientMessage::RequestTurnConfig { room_id, .. } => {
-            handlers::handle_turn_config_request(state.clone(), sender, &room_id).await;
+        ClientMessage::RequestTurnConfig {
+            room_id,
+            correlation_id,
+            ..
+        } => {
+            handlers::handle_turn_config_request(
+                state.clone(),
+                peer_id,
+                sender,
+                &room_id,
+                correlation_id,
+            )
+            .await;
         }
         ClientMessage::RefreshTurnCredentials {
             room_id,
             current_username,
         } => {
-            if handlers::validate_credentials(&current_username) {
-                let _ = sender.send(ServerMessage::TurnConfig {
-                    success: true,
-                    data: None,
-                    error: Some("Credentials still valid".to_string()),
-                });
-            } else {
-                handlers::handle_turn_config_request(state.clone(), sender, &room_id).await;
-            }
+            handlers::handle_turn_refresh(
+                state.clone(),
+                peer_id,
+                sender,
+                &room_id,
+                &current_username,
+            )
+            .await;
         }
         ClientMessage::CheckTurnServerStatus => {
             let _ = sender.send(ServerMessage::TurnServerStatusUpdate {
@@ -381,3 +1188,888 @@ async fn handle_client_message(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn router_with(configure: impl FnOnce(&mut Config)) -> Router {
+        let mut config = Config::from_env();
+        configure(&mut config);
+        build_router(Arc::new(AppState::new_for_test_with_config(config))).expect("router")
+    }
+
+    async fn status_of(router: &Router, path: &str) -> StatusCode {
+        router
+            .clone()
+            .oneshot(Request::get(path).body(Body::empty()).unwrap())
+            .await
+            .expect("response")
+            .status()
+    }
+
+    #[tokio::test]
+    async fn websocket_route_follows_configured_path() {
+        let router = router_with(|config| {
+            config.ws_path = "/signaling/ws".to_string();
+            config.base_path = "/signaling".to_string();
+        });
+
+        assert_ne!(
+            status_of(&router, "/signaling/ws").await,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(status_of(&router, "/ws").await, StatusCode::NOT_FOUND);
+        assert_eq!(
+            status_of(&router, "/signaling/health").await,
+            StatusCode::OK
+        );
+        assert_eq!(status_of(&router, "/health").await, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn check_config_reports_result_without_secrets() {
+        let mut config = Config::from_env();
+        config.turn.secret = "turn-secret".to_string();
+        config.cluster.backend = "memory".to_string();
+        let mut out = Vec::new();
+        assert!(check_config(&config, &mut out));
+        let report = String::from_utf8(out).unwrap();
+        assert!(report.ends_with("Configuration OK\n"));
+        assert!(!report.contains("turn-secret"));
+
+        config.cluster.backend = "etcd".to_string();
+        let mut out = Vec::new();
+        assert!(!check_config(&config, &mut out));
+        let report = String::from_utf8(out).unwrap();
+        assert!(report.contains("error: unknown CLUSTER_BACKEND: etcd"));
+    }
+
+    /// 실제 소켓으로 서버를 띄우고 WebSocket 주소를 반환한다.
+    async fn spawn_server(configure: impl FnOnce(&mut Config)) -> String {
+        let mut config = Config::from_env();
+        config.ws_path = "/ws".to_string();
+        configure(&mut config);
+        let router =
+            build_router(Arc::new(AppState::new_for_test_with_config(config))).expect("router");
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+        });
+        format!("ws://{addr}/ws")
+    }
+
+    #[test]
+    fn forwarded_client_ip_is_used_only_behind_trusted_proxies() {
+        let mut config = Config::from_env();
+        config.limits.trusted_proxies = vec!["10.0.0.1".to_string(), "10.0.0.2".to_string()];
+        let ip = |text: &str| text.parse::<IpAddr>().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            "198.51.100.7, 203.0.113.9, 10.0.0.2".parse().unwrap(),
+        );
+
+        // 신뢰하는 프록시 뒤의 첫 클라이언트 주소 (왼쪽 값은 클라이언트가 꾸밀 수 있다)
+        let proxied = client_ip(&config, Some(ip("10.0.0.1")), &headers);
+        assert_eq!(proxied, Some(ip("203.0.113.9")));
+        // 프록시가 아닌 접속이 보낸 헤더는 무시한다
+        let direct = client_ip(&config, Some(ip("192.0.2.5")), &headers);
+        assert_eq!(direct, Some(ip("192.0.2.5")));
+        // 헤더가 없으면 프록시 주소 그대로
+        let bare = client_ip(&config, Some(ip("10.0.0.1")), &HeaderMap::new());
+        assert_eq!(bare, Some(ip("10.0.0.1")));
+    }
+
+    #[tokio::test]
+    async fn upgrade_burst_beyond_concurrency_limit_is_rejected() {
+        let mut config = Config::from_env();
+        config.ws_path = "/ws".to_string();
+        config.limits.max_concurrent_upgrades = 2;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let router = build_router(state.clone()).expect("router");
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+        });
+        let permits = state.upgrade_permits.clone().unwrap();
+
+        // 진행 중인 업그레이드가 한도만큼 있는 동안 들어온 연결은 모두 503
+        let busy = permits.clone().acquire_many_owned(2).await.unwrap();
+        let burst = (0..4).map(|_| tokio_tungstenite::connect_async(url.clone()));
+        for result in futures::future::join_all(burst).await {
+            match result {
+                Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+                    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE)
+                }
+                other => panic!("expected 503, got {:?}", other.map(|_| ())),
+            }
+        }
+
+        // 등록이 끝난 연결은 허가를 돌려주므로 열린 채로도 다음 업그레이드를 막지 않는다
+        drop(busy);
+        let mut clients = Vec::new();
+        for _ in 0..3 {
+            let (client, _) = tokio_tungstenite::connect_async(url.clone())
+                .await
+                .expect("connect");
+            clients.push(client);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(permits.available_permits(), 2);
+    }
+
+    #[tokio::test]
+    async fn ws_upgrade_checks_origin_against_allowlist() {
+        use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+        let url = spawn_server(|config| {
+            config.cors_origins = vec!["https://warp.ponslink.com".to_string()];
+            config.ws_enforce_origin = true;
+        })
+        .await;
+        let connect = |origin: &'static str| {
+            let mut request = url.as_str().into_client_request().unwrap();
+            request
+                .headers_mut()
+                .insert(ORIGIN, HeaderValue::from_static(origin));
+            tokio_tungstenite::connect_async(request)
+        };
+
+        let allowed = connect("https://warp.ponslink.com").await;
+        assert!(allowed.is_ok(), "allowed origin upgrades");
+        match connect("https://evil.example.com").await {
+            Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+                assert_eq!(response.status(), StatusCode::FORBIDDEN)
+            }
+            other => panic!("expected 403, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn connection_is_closed_after_max_lifetime() {
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let url = spawn_server(|config| config.limits.max_connection_lifetime_ms = 100).await;
+        let (mut client, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .expect("connect");
+
+        let mut codes = Vec::new();
+        let closed = tokio::time::timeout(Duration::from_secs(2), async {
+            while let Some(frame) = client.next().await {
+                match frame {
+                    Ok(WsMessage::Text(text)) => {
+                        if let Ok(ServerMessage::Error { code, .. }) = serde_json::from_str(&text) {
+                            codes.push(code);
+                        }
+                    }
+                    Ok(WsMessage::Close(_)) | Err(_) => break,
+                    Ok(_) => {}
+                }
+            }
+        })
+        .await;
+
+        assert!(closed.is_ok(), "server must close the connection");
+        assert_eq!(codes, vec!["lifetime_exceeded".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn server_initiated_close_carries_code_and_reason() {
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        async fn close_frame_of(url: String) -> Option<(u16, String)> {
+            let (mut client, _) = tokio_tungstenite::connect_async(url)
+                .await
+                .expect("connect");
+            let read = async {
+                while let Some(Ok(frame)) = client.next().await {
+                    if let WsMessage::Close(frame) = frame {
+                        return frame
+                            .map(|frame| (u16::from(frame.code), frame.reason.into_owned()));
+                    }
+                }
+                None
+            };
+            tokio::time::timeout(Duration::from_secs(2), read)
+                .await
+                .expect("closed")
+        }
+
+        let url = spawn_server(|config| config.limits.max_connection_lifetime_ms = 100).await;
+        assert_eq!(
+            close_frame_of(url).await,
+            Some((4000, "lifetime_exceeded".to_string()))
+        );
+
+        let url = spawn_server(|config| config.limits.max_connections = 1).await;
+        let (_first, _) = tokio_tungstenite::connect_async(url.clone())
+            .await
+            .expect("connect");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            close_frame_of(url).await,
+            Some((1013, "server_at_capacity".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn fragmented_text_message_is_reassembled_before_parsing() {
+        use tokio_tungstenite::tungstenite::protocol::frame::coding::{Data, OpCode};
+        use tokio_tungstenite::tungstenite::protocol::frame::Frame;
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let url = spawn_server(|config| config.limits.max_message_bytes = 64).await;
+        let (mut client, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .expect("connect");
+
+        let fragment = |data: &str, opcode, is_final| {
+            WsMessage::Frame(Frame::message(data.as_bytes().to_vec(), opcode, is_final))
+        };
+        let heartbeat = r#"{"type":"Heartbeat"}"#;
+        let (head, tail) = heartbeat.split_at(8);
+        client
+            .send(fragment(head, OpCode::Data(Data::Text), false))
+            .await
+            .unwrap();
+        client
+            .send(fragment(tail, OpCode::Data(Data::Continue), true))
+            .await
+            .unwrap();
+
+        let acked = tokio::time::timeout(Duration::from_secs(2), async {
+            while let Some(Ok(frame)) = client.next().await {
+                if let WsMessage::Text(text) = frame {
+                    if matches!(serde_json::from_str(&text), Ok(ServerMessage::HeartbeatAck)) {
+                        return true;
+                    }
+                }
+            }
+            false
+        })
+        .await;
+        assert_eq!(acked, Ok(true));
+
+        // 합친 크기가 한도를 넘으면 연결이 끊긴다.
+        let filler = "x".repeat(40);
+        client
+            .send(fragment(&filler, OpCode::Data(Data::Text), false))
+            .await
+            .unwrap();
+        client
+            .send(fragment(&filler, OpCode::Data(Data::Continue), true))
+            .await
+            .unwrap();
+        let closed = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                match client.next().await {
+                    Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        })
+        .await;
+        assert!(closed.is_ok(), "oversized message must be rejected");
+    }
+
+    type WsClient = tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >;
+
+    /// `count`개의 클라이언트를 연결해 "room"에 입장시키고 각자의 peer_id와 함께 돌려준다.
+    async fn join_room_clients(url: &str, count: usize) -> (Vec<WsClient>, Vec<String>) {
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let mut clients = Vec::new();
+        let mut ids = Vec::new();
+        for _ in 0..count {
+            let (mut client, _) = tokio_tungstenite::connect_async(url)
+                .await
+                .expect("connect");
+            while let Some(Ok(frame)) = client.next().await {
+                if let WsMessage::Text(text) = frame {
+                    if let Ok(ServerMessage::Connected { socket_id, .. }) =
+                        serde_json::from_str(&text)
+                    {
+                        ids.push(socket_id);
+                        break;
+                    }
+                }
+            }
+            let join = r#"{"type":"JoinRoom","payload":{"room_id":"room"}}"#.to_string();
+            client.send(WsMessage::Text(join)).await.unwrap();
+            clients.push(client);
+        }
+        // 입장 처리가 끝난 뒤 중계한다
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        (clients, ids)
+    }
+
+    #[tokio::test]
+    async fn admin_event_stream_reports_room_join() {
+        use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let url = spawn_server(|config| config.admin.token = "secret-token".to_string()).await;
+        let events_url = url.replace("/ws", "/admin/events");
+        let mut request = events_url.as_str().into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, "Bearer secret-token".parse().unwrap());
+        let (mut admin, _) = tokio_tungstenite::connect_async(request)
+            .await
+            .expect("admin connect");
+
+        let (_clients, ids) = join_room_clients(&url, 1).await;
+
+        let joined = tokio::time::timeout(Duration::from_secs(2), async {
+            while let Some(Ok(WsMessage::Text(text))) = admin.next().await {
+                let event: serde_json::Value = serde_json::from_str(&text).unwrap();
+                if event["type"] == "peer_joined" {
+                    return Some(event);
+                }
+            }
+            None
+        })
+        .await
+        .expect("join event")
+        .expect("stream open");
+        assert_eq!(joined["room_id"], "room");
+        assert_eq!(joined["peer_id"], ids[0].as_str());
+
+        let rejected = tokio_tungstenite::connect_async(events_url.as_str()).await;
+        assert!(rejected.is_err(), "token is required");
+        let with_admin_token = format!("{events_url}?ticket=secret-token");
+        let rejected = tokio_tungstenite::connect_async(with_admin_token).await;
+        assert!(rejected.is_err(), "admin token is not a ticket");
+    }
+
+    #[tokio::test]
+    async fn rapid_ice_candidates_arrive_in_send_order() {
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        // 수신 큐를 켜 TURN lane과 시그널링 lane이 나뉜 경로까지 확인한다
+        let url = spawn_server(|config| config.limits.inbound_queue_depth = 256).await;
+        let (mut clients, ids) = join_room_clients(&url, 2).await;
+
+        let sent: Vec<String> = (0..50)
+            .map(|n| format!("candidate:{n} 1 udp 1 10.0.0.1 {} typ host", 9000 + n))
+            .collect();
+        for candidate in &sent {
+            let message = serde_json::json!({
+                "type": "IceCandidate",
+                "payload": {"room_id": "room", "candidate": candidate, "target": ids[1]},
+            });
+            clients[0]
+                .send(WsMessage::Text(message.to_string()))
+                .await
+                .unwrap();
+        }
+
+        let mut received = Vec::new();
+        let _ = tokio::time::timeout(Duration::from_secs(2), async {
+            while received.len() < sent.len() {
+                let Some(Ok(WsMessage::Text(text))) = clients[1].next().await else {
+                    break;
+                };
+                if let Ok(ServerMessage::IceCandidate { candidate, .. }) =
+                    serde_json::from_str(&text)
+                {
+                    received.push(candidate.candidate);
+                }
+            }
+        })
+        .await;
+        assert_eq!(received, sent);
+    }
+
+    #[tokio::test]
+    async fn binary_relay_frame_reaches_only_its_target() {
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let url = spawn_server(|_| {}).await;
+        let (mut clients, ids) = join_room_clients(&url, 3).await;
+
+        let mut frame = vec![protocol::binary::BINARY_RELAY, 4];
+        frame.extend_from_slice(b"room");
+        frame.push(ids[1].len() as u8);
+        frame.extend_from_slice(ids[1].as_bytes());
+        frame.extend_from_slice(&[0xde, 0xad, 0x00]);
+        clients[0].send(WsMessage::Binary(frame)).await.unwrap();
+
+        let mut expected = vec![protocol::binary::BINARY_RELAY, ids[0].len() as u8];
+        expected.extend_from_slice(ids[0].as_bytes());
+        expected.extend_from_slice(&[0xde, 0xad, 0x00]);
+        let mut received = Vec::new();
+        for (index, client) in clients.iter_mut().enumerate() {
+            let next_binary = async {
+                while let Some(Ok(frame)) = client.next().await {
+                    if let WsMessage::Binary(data) = frame {
+                        return Some(data);
+                    }
+                }
+                None
+            };
+            let wait = Duration::from_millis(300);
+            if let Ok(Some(data)) = tokio::time::timeout(wait, next_binary).await {
+                received.push((index, data));
+            }
+        }
+        assert_eq!(received, [(1, expected)]);
+    }
+
+    #[tokio::test]
+    async fn error_text_follows_connection_locale() {
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let url = spawn_server(|_| {}).await;
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("{url}?lang=ko-KR"))
+            .await
+            .expect("connect");
+        // 방에 없는 상태의 LeaveRoom은 not_in_room 에러로 돌아온다
+        async fn next_error(client: &mut WsClient) -> serde_json::Value {
+            let leave = WsMessage::Text(r#"{"type":"LeaveRoom"}"#.to_string());
+            client.send(leave).await.unwrap();
+            loop {
+                let frame = tokio::time::timeout(Duration::from_secs(2), client.next())
+                    .await
+                    .expect("reply");
+                let Some(Ok(WsMessage::Text(json))) = frame else {
+                    continue;
+                };
+                let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+                if value["type"] == "Error" {
+                    return value["payload"].clone();
+                }
+            }
+        }
+
+        let korean = next_error(&mut client).await;
+        assert_eq!(korean["code"], "not_in_room");
+        assert_eq!(korean["message"], "방에 참여하고 있지 않습니다");
+
+        let set_locale = r#"{"type":"SetLocale","payload":{"lang":"en"}}"#.to_string();
+        client.send(WsMessage::Text(set_locale)).await.unwrap();
+        let english = next_error(&mut client).await;
+        assert_eq!(english["code"], "not_in_room");
+        assert_eq!(english["message"], "Not in any room");
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected_only_in_strict_mode() {
+        let typo = r#"{"type":"ResyncRoom","payload":{"room_id":"abc","corelation_id":"c1"}}"#;
+
+        assert!(matches!(
+            decode_text(typo, false),
+            Ok(ClientMessage::ResyncRoom {
+                correlation_id: None,
+                ..
+            })
+        ));
+        let reply = decode_text(typo, true).unwrap_err().expect("error reply");
+        assert!(matches!(
+            *reply,
+            ServerMessage::Error { code, message, .. }
+                if code == "unknown_field" && message.contains("corelation_id")
+        ));
+        // 해석할 수 없는 frame은 두 모드 모두 조용히 버린다
+        assert!(matches!(decode_text("{", true), Err(None)));
+        assert!(matches!(decode_text("{", false), Err(None)));
+    }
+
+    #[tokio::test]
+    async fn stuck_writer_times_out_send_loop() {
+        use std::sync::atomic::Ordering;
+
+        // 첫 전송부터 끝나지 않는 sink (TCP 송신 버퍼가 가득 찬 클라이언트)
+        let stuck = futures::sink::unfold((), |_, _: Message| {
+            std::future::pending::<Result<(), axum::Error>>()
+        });
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(ServerMessage::HeartbeatAck).unwrap();
+        let stats = Arc::new(state::PeerStats::default());
+
+        let exit = tokio::time::timeout(
+            Duration::from_secs(2),
+            forward_messages(
+                stuck,
+                rx,
+                stats.clone(),
+                Arc::default(),
+                Arc::default(),
+                None,
+                Arc::default(),
+                Some(Duration::from_millis(50)),
+                0,
+            ),
+        )
+        .await
+        .expect("send loop must not block");
+
+        assert_eq!(exit, SendExit::TimedOut);
+        assert_eq!(stats.messages_sent.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn send_loop_ends_when_channel_closes() {
+        let (sink, mut sent) = futures::channel::mpsc::unbounded::<Message>();
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(ServerMessage::HeartbeatAck).unwrap();
+        drop(tx);
+
+        let exit = forward_messages(
+            sink,
+            rx,
+            Arc::new(state::PeerStats::default()),
+            Arc::default(),
+            Arc::default(),
+            None,
+            Arc::default(),
+            Some(Duration::from_millis(50)),
+            0,
+        )
+        .await;
+
+        assert_eq!(exit, SendExit::Closed);
+        assert!(matches!(sent.next().await, Some(Message::Text(_))));
+    }
+
+    #[tokio::test]
+    async fn send_loop_drops_unsubscribed_message_types() {
+        let (sink, sent) = futures::channel::mpsc::unbounded::<Message>();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let subscriptions = Arc::new(state::MessageFilter::default());
+        subscriptions.set(vec!["RoomUsers".to_string()]);
+        tx.send(ServerMessage::HeartbeatAck).unwrap();
+        tx.send(ServerMessage::RoomUsers {
+            users: vec!["peer".to_string()],
+            correlation_id: None,
+        })
+        .unwrap();
+        drop(tx);
+
+        let stats = Arc::new(state::PeerStats::default());
+        let close = Arc::default();
+        let locale = Arc::default();
+        forward_messages(sink, rx, stats, subscriptions, locale, None, close, None, 0).await;
+
+        let types: Vec<String> = sent
+            .map(|message| match message {
+                Message::Text(json) => serde_json::from_str::<serde_json::Value>(&json).unwrap()
+                    ["type"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                _ => String::new(),
+            })
+            .collect()
+            .await;
+        assert_eq!(types, ["RoomUsers"]);
+    }
+
+    #[tokio::test]
+    async fn send_loop_sheds_low_priority_messages_under_backlog() {
+        let (sink, sent) = futures::channel::mpsc::unbounded::<Message>();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let progress = |percent| ServerMessage::TransferProgress {
+            from: "sender".to_string(),
+            percent,
+            bytes: 0,
+        };
+        let complete = || ServerMessage::TransferComplete {
+            from: "receiver".to_string(),
+        };
+        for percent in [10.0, 20.0, 30.0, 40.0] {
+            tx.send(progress(percent)).unwrap();
+        }
+        tx.send(complete()).unwrap();
+        for percent in [50.0, 60.0, 70.0, 80.0] {
+            tx.send(progress(percent)).unwrap();
+        }
+        tx.send(complete()).unwrap();
+        drop(tx);
+
+        let stats = Arc::new(state::PeerStats::default());
+        let close = Arc::default();
+        let (subscriptions, locale) = (Arc::default(), Arc::default());
+        forward_messages(sink, rx, stats, subscriptions, locale, None, close, None, 3).await;
+
+        let frames: Vec<serde_json::Value> = sent
+            .map(|message| match message {
+                Message::Text(json) => serde_json::from_str(&json).unwrap(),
+                other => panic!("unexpected frame: {other:?}"),
+            })
+            .collect()
+            .await;
+        let summary: Vec<(String, Option<f64>)> = frames
+            .iter()
+            .map(|frame| {
+                let kind = frame["type"].as_str().unwrap_or_default().to_string();
+                (kind, frame["payload"]["percent"].as_f64())
+            })
+            .collect();
+        // 대기 메시지가 3개 이상인 동안에는 TransferProgress만 버려지고 TransferComplete는 모두 전달된다.
+        assert_eq!(
+            summary,
+            [
+                ("TransferComplete".to_string(), None),
+                ("TransferProgress".to_string(), Some(70.0)),
+                ("TransferProgress".to_string(), Some(80.0)),
+                ("TransferComplete".to_string(), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn send_loop_signs_json_frames_only_when_configured() {
+        let signer = protocol::signing::MessageSigner::new("shared-secret").map(Arc::new);
+        let mut frames = Vec::new();
+        for signer in [signer.clone(), None] {
+            let (sink, sent) = futures::channel::mpsc::unbounded::<Message>();
+            let (tx, rx) = mpsc::unbounded_channel();
+            tx.send(ServerMessage::HeartbeatAck).unwrap();
+            tx.send(ServerMessage::HeartbeatAck).unwrap();
+            drop(tx);
+            let stats = Arc::new(state::PeerStats::default());
+            let close = Arc::default();
+            let (subscriptions, locale) = (Arc::default(), Arc::default());
+            forward_messages(
+                sink,
+                rx,
+                stats,
+                subscriptions,
+                locale,
+                signer,
+                close,
+                None,
+                0,
+            )
+            .await;
+            match sent.collect::<Vec<_>>().await.as_slice() {
+                [Message::Text(first), Message::Text(second)] => {
+                    frames.push(first.clone());
+                    frames.push(second.clone());
+                }
+                other => panic!("unexpected frames: {other:?}"),
+            }
+        }
+
+        let signer = signer.unwrap();
+        for (frame, seq) in frames[..2].iter().zip(1..) {
+            let signed: serde_json::Value = serde_json::from_str(frame).unwrap();
+            assert_eq!(signed["seq"], seq);
+            let (stamped, _) = frame.rsplit_once(r#","sig":"#).unwrap();
+            assert_eq!(
+                signed["sig"].as_str(),
+                Some(signer.sign(&format!("{stamped}}}")).as_str())
+            );
+        }
+        let unsigned = serde_json::to_string(&ServerMessage::HeartbeatAck).unwrap();
+        assert_eq!(frames[2..], [unsigned.clone(), unsigned]);
+    }
+
+    async fn load_with_peers(peers: usize) -> serde_json::Value {
+        let mut config = Config::from_env();
+        config.base_path = String::new();
+        config.limits.max_connections = 4;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let _receivers: Vec<_> = (0..peers)
+            .map(|n| state.insert_test_peer(&format!("peer-{n}")))
+            .collect();
+        let response = build_router(state)
+            .expect("router")
+            .oneshot(Request::get("/load").body(Body::empty()).unwrap())
+            .await
+            .expect("response");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        serde_json::from_slice(&body).expect("json")
+    }
+
+    #[tokio::test]
+    async fn load_factor_scales_with_connections() {
+        let idle = load_with_peers(0).await;
+        let half = load_with_peers(2).await;
+        let over = load_with_peers(6).await;
+
+        assert_eq!(idle["load_factor"], 0.0);
+        assert_eq!(half["load_factor"], 0.5);
+        assert_eq!(half["connections"], 2);
+        assert_eq!(half["max_connections"], 4);
+        assert_eq!(over["load_factor"], 1.0);
+    }
+
+    #[tokio::test]
+    async fn health_reports_same_instance_id_across_requests() {
+        let mut config = Config::from_env();
+        config.base_path = String::new();
+        config.instance_id = String::new();
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let router = build_router(state.clone()).expect("router");
+
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let response = router
+                .clone()
+                .oneshot(Request::get("/health").body(Body::empty()).unwrap())
+                .await
+                .expect("response");
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .expect("body");
+            let health: serde_json::Value = serde_json::from_slice(&body).expect("json");
+            ids.push(health["instanceId"].clone());
+        }
+
+        assert_eq!(ids.len(), 2);
+        assert!(ids.iter().all(|id| id == state.instance_id.as_str()));
+    }
+
+    fn state_requiring_turn_on(port: u16) -> Arc<AppState> {
+        let mut config = Config::from_env();
+        config.base_path = String::new();
+        config.cloud.enabled = false;
+        config.cloud.billing_enabled = false;
+        config.turn.url = "127.0.0.1".to_string();
+        config.turn.secret = "secret".to_string();
+        config.turn.enable_udp = false;
+        config.turn.enable_tls = false;
+        config.turn.enable_tcp = true;
+        config.turn.ports.tcp = port;
+        config.turn.require_ready = true;
+        Arc::new(AppState::new_for_test_with_config(config))
+    }
+
+    #[tokio::test]
+    async fn ready_opens_after_turn_probe_succeeds() {
+        let turn = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let state = state_requiring_turn_on(turn.local_addr().unwrap().port());
+        let router = build_router(state.clone()).expect("router");
+        let before = status_of(&router, "/ready").await;
+        assert_eq!(before, StatusCode::SERVICE_UNAVAILABLE);
+
+        tokio::time::timeout(
+            Duration::from_secs(2),
+            handlers::run_turn_readiness_probe(&state),
+        )
+        .await
+        .expect("probe succeeds");
+
+        assert_eq!(status_of(&router, "/ready").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn ready_stays_closed_while_turn_is_unreachable() {
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = closed.local_addr().unwrap().port();
+        drop(closed);
+        let state = state_requiring_turn_on(port);
+        let router = build_router(state.clone()).expect("router");
+
+        assert!(!handlers::probe_turn_server(&state.config.turn).await);
+        let probe = tokio::time::timeout(
+            Duration::from_millis(300),
+            handlers::run_turn_readiness_probe(&state),
+        )
+        .await;
+
+        assert!(probe.is_err(), "probe keeps retrying");
+        let ready = status_of(&router, "/ready").await;
+        assert_eq!(ready, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn half_configured_turn_fails_readiness() {
+        let router = router_with(|config| {
+            config.base_path = String::new();
+            config.cloud.enabled = false;
+            config.cloud.billing_enabled = false;
+            config.turn.url = "turn.example.com".to_string();
+            config.turn.secret = String::new();
+        });
+        let ready = status_of(&router, "/ready").await;
+        assert_eq!(ready, StatusCode::SERVICE_UNAVAILABLE);
+
+        let response = router
+            .oneshot(Request::get("/health").body(Body::empty()).unwrap())
+            .await
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let health: serde_json::Value = serde_json::from_slice(&body).expect("json");
+        assert_eq!(health["turn"], "misconfigured");
+    }
+
+    #[tokio::test]
+    async fn version_endpoint_reports_build_info() {
+        let router = router_with(|config| config.base_path = String::new());
+        let response = router
+            .oneshot(Request::get("/version").body(Body::empty()).unwrap())
+            .await
+            .expect("response");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("body");
+        let info: serde_json::Value = serde_json::from_slice(&body).expect("json");
+        assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+        assert!(!info["gitSha"].as_str().unwrap_or_default().is_empty());
+        assert!(info["buildTimestamp"].is_u64());
+    }
+
+    #[tokio::test]
+    async fn default_paths_are_unchanged() {
+        let router = router_with(|config| {
+            config.ws_path = "/ws".to_string();
+            config.base_path = String::new();
+        });
+
+        assert_ne!(status_of(&router, "/ws").await, StatusCode::NOT_FOUND);
+        assert_eq!(status_of(&router, "/health").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn messages_beyond_handler_limit_are_shed() {
+        let mut config = Config::from_env();
+        config.limits.max_concurrent_handlers = 1;
+        let state = Arc::new(AppState::new_for_test_with_config(config));
+        let mut peer_rx = state.insert_test_peer("peer-a");
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let permits = state.handler_permits.as_ref().unwrap();
+        let busy = permits.try_acquire().unwrap();
+        handle_client_message(&state, "peer-a", &tx, ClientMessage::WhoAmI).await;
+        match rx.try_recv() {
+            Ok(ServerMessage::Error { code, .. }) => assert_eq!(code, "server_overloaded"),
+            other => panic!("expected server_overloaded, got {other:?}"),
+        }
+
+        drop(busy);
+        handle_client_message(&state, "peer-a", &tx, ClientMessage::WhoAmI).await;
+        assert!(rx.try_recv().is_err());
+        assert!(matches!(
+            peer_rx.try_recv(),
+            Ok(ServerMessage::Identity { .. })
+        ));
+        assert_eq!(permits.available_permits(), 1);
+    }
+}
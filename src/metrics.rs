@@ -0,0 +1,369 @@
+//! 운영 메트릭 (Prometheus 텍스트 형식, `{BASE_PATH}/metrics`)
+
+use dashmap::DashMap;
+use std::fmt::Write as _;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 프로세스 전역 메트릭
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub turn: TurnMetrics,
+    pub rooms: RoomMetrics,
+    pub relay: RelayMetrics,
+    pub connections: ConnectionMetrics,
+}
+
+impl Metrics {
+    /// Prometheus 텍스트 형식으로 내보낸다. TTL은 설정 값을 그대로 gauge로 노출한다.
+    pub fn render(&self, credential_ttl: u64) -> String {
+        let mut out = String::new();
+        self.turn.render(&mut out, credential_ttl);
+        self.rooms.render(&mut out);
+        self.relay.render(&mut out);
+        self.connections.render(&mut out);
+        out
+    }
+}
+
+/// 방 생성 속도를 세는 고정 창
+const CREATION_WINDOW: Duration = Duration::from_secs(60);
+
+/// (창 시작 시각, 창 안의 생성 수)
+type CreationWindow = (Instant, u32);
+
+/// 방 생성 메트릭. 방 id 무작위 대입을 잡기 위해 전역/IP별 분당 생성 수도 센다.
+#[derive(Debug, Default)]
+pub struct RoomMetrics {
+    created: AtomicU64,
+    global_window: Mutex<Option<CreationWindow>>,
+    per_ip_windows: DashMap<IpAddr, CreationWindow>,
+}
+
+impl RoomMetrics {
+    /// 방 생성 기록. 이번 생성으로 분당 한도(0이면 감시 안 함)를 처음 넘었으면 경고 로그를 남기고 true.
+    pub fn record_created(&self, ip: Option<IpAddr>, global_limit: u32, per_ip_limit: u32) -> bool {
+        self.created.fetch_add(1, Ordering::Relaxed);
+        let now = Instant::now();
+        let mut warned = false;
+
+        if global_limit > 0 {
+            let count = {
+                let mut window = self.global_window.lock().unwrap();
+                bump_window(window.get_or_insert((now, 0)), now)
+            };
+            if count == global_limit + 1 {
+                tracing::warn!(
+                    rooms_per_min = count,
+                    "Room creation rate exceeded threshold"
+                );
+                warned = true;
+            }
+        }
+        if let (Some(ip), true) = (ip, per_ip_limit > 0) {
+            let count = bump_window(&mut self.per_ip_windows.entry(ip).or_insert((now, 0)), now);
+            if count == per_ip_limit + 1 {
+                tracing::warn!(ip = %ip, rooms_per_min = count, "Room creation rate from one address exceeded threshold");
+                warned = true;
+            }
+        }
+        warned
+    }
+
+    pub fn created(&self) -> u64 {
+        self.created.load(Ordering::Relaxed)
+    }
+
+    /// 창이 끝난 IP별 카운터를 지운다 (정리 스케줄러).
+    pub fn prune_creation_windows(&self) {
+        self.per_ip_windows
+            .retain(|_, (started, _)| started.elapsed() < CREATION_WINDOW);
+    }
+
+    fn render(&self, out: &mut String) {
+        let _ = writeln!(
+            out,
+            "# HELP ponswarp_rooms_created_total Rooms created since start."
+        );
+        let _ = writeln!(out, "# TYPE ponswarp_rooms_created_total counter");
+        let _ = writeln!(out, "ponswarp_rooms_created_total {}", self.created());
+    }
+}
+
+/// 창이 지났으면 새로 시작하고, 생성 수를 하나 올려 돌려준다.
+fn bump_window(window: &mut CreationWindow, now: Instant) -> u32 {
+    if now.duration_since(window.0) >= CREATION_WINDOW {
+        *window = (now, 0);
+    }
+    window.1 += 1;
+    window.1
+}
+
+/// 연결 상태 gauge. 값은 확인할 때마다(연결 수락, `/metrics` 조회) 갱신한다.
+#[derive(Debug, Default)]
+pub struct ConnectionMetrics {
+    roomless_peers: AtomicU64,
+}
+
+impl ConnectionMetrics {
+    pub fn set_roomless_peers(&self, count: usize) {
+        self.roomless_peers.store(count as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String) {
+        let _ = writeln!(
+            out,
+            "# HELP ponswarp_roomless_peers Peers connected past ROOMLESS_GRACE_MS without a room."
+        );
+        let _ = writeln!(out, "# TYPE ponswarp_roomless_peers gauge");
+        let _ = writeln!(
+            out,
+            "ponswarp_roomless_peers {}",
+            self.roomless_peers.load(Ordering::Relaxed)
+        );
+    }
+}
+
+/// 허용 목록에 없는 relay event를 모으는 label
+const OTHER_EVENT_LABEL: &str = "other";
+
+/// RelayToRole event별 중계 수. 클라이언트가 정하는 event 이름이 label 수를 늘리지 않도록
+/// RELAY_METRIC_EVENTS에 있는 이름만 label로 쓰고 나머지는 `other`로 센다.
+#[derive(Debug, Default)]
+pub struct RelayMetrics {
+    events: DashMap<String, u64>,
+}
+
+impl RelayMetrics {
+    pub fn record_event(&self, event: &str, allowed: &[String]) {
+        *self
+            .events
+            .entry(event_label(event, allowed).to_string())
+            .or_insert(0) += 1;
+    }
+
+    fn render(&self, out: &mut String) {
+        let _ = writeln!(
+            out,
+            "# HELP ponswarp_relay_events_total RelayToRole messages by event."
+        );
+        let _ = writeln!(out, "# TYPE ponswarp_relay_events_total counter");
+        let mut events: Vec<(String, u64)> = self
+            .events
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+        events.sort();
+        for (event, count) in events {
+            let _ = writeln!(
+                out,
+                "ponswarp_relay_events_total{{event=\"{event}\"}} {count}"
+            );
+        }
+    }
+}
+
+/// 허용 목록에 있으면 event 이름, 없으면 `other`
+fn event_label<'a>(event: &'a str, allowed: &[String]) -> &'a str {
+    if allowed.iter().any(|name| name == event) {
+        event
+    } else {
+        OTHER_EVENT_LABEL
+    }
+}
+
+/// RefreshTurnCredentials 결과
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshOutcome {
+    /// 기존 자격증명이 아직 유효해 재발급하지 않음
+    StillValid,
+    /// 만료된 자격증명을 재발급
+    Expired,
+    /// username을 해석할 수 없어 재발급
+    Invalid,
+}
+
+impl RefreshOutcome {
+    fn label(self) -> &'static str {
+        match self {
+            Self::StillValid => "still_valid",
+            Self::Expired => "expired",
+            Self::Invalid => "invalid",
+        }
+    }
+}
+
+/// 갱신 요청 시점의 자격증명 나이 분포 버킷 (초)
+const AGE_BUCKETS: [u64; 9] = [60, 300, 900, 1800, 3600, 7200, 14400, 43200, 86400];
+
+/// TURN 자격증명 발급/갱신 메트릭
+#[derive(Debug, Default)]
+pub struct TurnMetrics {
+    issued: AtomicU64,
+    refresh_still_valid: AtomicU64,
+    refresh_expired: AtomicU64,
+    refresh_invalid: AtomicU64,
+    age_buckets: [AtomicU64; AGE_BUCKETS.len()],
+    age_sum: AtomicU64,
+    age_count: AtomicU64,
+}
+
+impl TurnMetrics {
+    pub fn record_issued(&self) {
+        self.issued.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 갱신 요청 기록. `age_secs`는 username에서 읽은 발급 시각 기준 경과 시간이다.
+    pub fn record_refresh(&self, outcome: RefreshOutcome, age_secs: Option<u64>) {
+        let counter = match outcome {
+            RefreshOutcome::StillValid => &self.refresh_still_valid,
+            RefreshOutcome::Expired => &self.refresh_expired,
+            RefreshOutcome::Invalid => &self.refresh_invalid,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        let Some(age) = age_secs else {
+            return;
+        };
+        for (bucket, bound) in self.age_buckets.iter().zip(AGE_BUCKETS) {
+            if age <= bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.age_sum.fetch_add(age, Ordering::Relaxed);
+        self.age_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn issued(&self) -> u64 {
+        self.issued.load(Ordering::Relaxed)
+    }
+
+    pub fn refreshes(&self, outcome: RefreshOutcome) -> u64 {
+        match outcome {
+            RefreshOutcome::StillValid => &self.refresh_still_valid,
+            RefreshOutcome::Expired => &self.refresh_expired,
+            RefreshOutcome::Invalid => &self.refresh_invalid,
+        }
+        .load(Ordering::Relaxed)
+    }
+
+    fn render(&self, out: &mut String, credential_ttl: u64) {
+        let _ = writeln!(
+            out,
+            "# HELP turn_credential_ttl_seconds Configured TURN credential TTL."
+        );
+        let _ = writeln!(out, "# TYPE turn_credential_ttl_seconds gauge");
+        let _ = writeln!(out, "turn_credential_ttl_seconds {credential_ttl}");
+
+        let _ = writeln!(
+            out,
+            "# HELP turn_credentials_issued_total TURN credentials issued."
+        );
+        let _ = writeln!(out, "# TYPE turn_credentials_issued_total counter");
+        let _ = writeln!(out, "turn_credentials_issued_total {}", self.issued());
+
+        let _ = writeln!(
+            out,
+            "# HELP turn_credential_refreshes_total RefreshTurnCredentials requests by outcome."
+        );
+        let _ = writeln!(out, "# TYPE turn_credential_refreshes_total counter");
+        for outcome in [
+            RefreshOutcome::StillValid,
+            RefreshOutcome::Expired,
+            RefreshOutcome::Invalid,
+        ] {
+            let _ = writeln!(
+                out,
+                "turn_credential_refreshes_total{{outcome=\"{}\"}} {}",
+                outcome.label(),
+                self.refreshes(outcome)
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP turn_credential_age_at_refresh_seconds Credential age when the client asked to refresh."
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE turn_credential_age_at_refresh_seconds histogram"
+        );
+        for (bucket, bound) in self.age_buckets.iter().zip(AGE_BUCKETS) {
+            let _ = writeln!(
+                out,
+                "turn_credential_age_at_refresh_seconds_bucket{{le=\"{bound}\"}} {}",
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.age_count.load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "turn_credential_age_at_refresh_seconds_bucket{{le=\"+Inf\"}} {count}"
+        );
+        let _ = writeln!(
+            out,
+            "turn_credential_age_at_refresh_seconds_sum {}",
+            self.age_sum.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(out, "turn_credential_age_at_refresh_seconds_count {count}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn age_histogram_is_cumulative() {
+        let metrics = Metrics::default();
+        metrics
+            .turn
+            .record_refresh(RefreshOutcome::StillValid, Some(200));
+        metrics
+            .turn
+            .record_refresh(RefreshOutcome::Expired, Some(90_000));
+
+        let text = metrics.render(86400);
+        assert!(text.contains("turn_credential_age_at_refresh_seconds_bucket{le=\"60\"} 0"));
+        assert!(text.contains("turn_credential_age_at_refresh_seconds_bucket{le=\"300\"} 1"));
+        assert!(text.contains("turn_credential_age_at_refresh_seconds_bucket{le=\"86400\"} 1"));
+        assert!(text.contains("turn_credential_age_at_refresh_seconds_bucket{le=\"+Inf\"} 2"));
+        assert!(text.contains("turn_credential_age_at_refresh_seconds_sum 90200"));
+        assert!(text.contains("turn_credential_ttl_seconds 86400"));
+    }
+
+    #[test]
+    fn rapid_room_creation_warns_once_per_window() {
+        let metrics = Metrics::default();
+        let noisy: IpAddr = "10.0.0.1".parse().unwrap();
+        let quiet: IpAddr = "10.0.0.2".parse().unwrap();
+
+        let warnings: Vec<bool> = (0..4)
+            .map(|_| metrics.rooms.record_created(Some(noisy), 0, 2))
+            .collect();
+        assert_eq!(warnings, [false, false, true, false]);
+        assert!(!metrics.rooms.record_created(Some(quiet), 0, 2));
+
+        // 전역 한도는 IP와 관계없이 센다
+        assert!(!metrics.rooms.record_created(Some(quiet), 1, 0));
+        assert!(metrics.rooms.record_created(None, 1, 0));
+        assert!(metrics.render(0).contains("ponswarp_rooms_created_total 7"));
+    }
+
+    #[test]
+    fn unknown_relay_events_share_the_other_label() {
+        let metrics = Metrics::default();
+        let allowed = vec!["slide_changed".to_string()];
+        metrics.relay.record_event("slide_changed", &allowed);
+        for event in ["x1", "x2", "x3"] {
+            metrics.relay.record_event(event, &allowed);
+        }
+
+        let text = metrics.render(0);
+        assert!(text.contains("ponswarp_relay_events_total{event=\"slide_changed\"} 1"));
+        assert!(text.contains("ponswarp_relay_events_total{event=\"other\"} 3"));
+        assert!(!text.contains("x1"));
+    }
+}
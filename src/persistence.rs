@@ -0,0 +1,341 @@
+//! 재시작 간 방 메타데이터 보존 (PERSISTENCE_PATH)
+//!
+//! 피어 연결은 저장할 수 없으므로 방 id, 생성 이후·마지막 활동 이후 경과 시간, owner와 방 옵션만 남기고,
+//! 기동 시 PERSISTENCE_RECONNECT_WINDOW_MS 동안 비어 있어도 유지되는 방으로 미리 만들어
+//! 재접속한 클라이언트가 같은 방(같은 정책)에 들어오도록 한다. owner는 같은 client_token으로 돌아오면 되찾는다.
+
+use crate::protocol::RoomOptions;
+use crate::state::{AppState, Room};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// 저장되는 방 메타데이터
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomSnapshot {
+    pub room_id: String,
+    /// 저장 시점의 방 나이
+    pub age_ms: u64,
+    /// 저장 시점에 마지막 활동 이후 지난 시간 (ROOM_TIMEOUT 계산용). 이전 버전 파일에는 없다
+    #[serde(default)]
+    pub idle_ms: Option<u64>,
+    /// 방을 만든 피어와 그 client_token. 이전 버전 파일에는 없다
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub owner_token: Option<String>,
+    /// 방 생성 시 정한 옵션. 없으면 기본값으로 복원한다
+    #[serde(default)]
+    pub options: RoomOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RoomsFile {
+    version: u32,
+    saved_at: u64,
+    rooms: Vec<RoomSnapshot>,
+}
+
+/// 현재 방 목록을 스냅샷으로 만든다.
+pub fn snapshot_rooms(state: &AppState) -> Vec<RoomSnapshot> {
+    let now = Instant::now();
+    let mut rooms: Vec<RoomSnapshot> = state
+        .rooms
+        .iter()
+        .map(|room| RoomSnapshot {
+            room_id: room.key().clone(),
+            age_ms: now.duration_since(room.created_at).as_millis() as u64,
+            idle_ms: Some(room.idle_for().as_millis() as u64),
+            owner: room.owner.clone(),
+            owner_token: room.owner_token.clone(),
+            options: room.options.clone(),
+        })
+        .collect();
+    rooms.sort_by(|a, b| a.room_id.cmp(&b.room_id));
+    rooms
+}
+
+/// 방 메타데이터를 파일에 저장한다. 임시 파일에 쓴 뒤 rename하여 부분 기록을 피한다.
+pub async fn save_rooms(state: &AppState, path: &Path) -> Result<usize> {
+    let rooms = snapshot_rooms(state);
+    let file = RoomsFile {
+        version: SNAPSHOT_VERSION,
+        saved_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        rooms,
+    };
+    let json = serde_json::to_vec_pretty(&file).context("failed to serialize rooms")?;
+
+    let tmp_path = path.with_extension("tmp");
+    tokio::fs::write(&tmp_path, json)
+        .await
+        .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    tokio::fs::rename(&tmp_path, path)
+        .await
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+
+    tracing::info!(path = %path.display(), rooms = file.rooms.len(), "Saved room metadata");
+    Ok(file.rooms.len())
+}
+
+/// 저장된 방을 owner와 옵션을 유지한 빈 방으로 복원한다. 파일이 없으면 0을 반환한다.
+/// 저장 당시 이미 ROOM_TIMEOUT만큼 활동이 없던 방과 이미 존재하는 방은 건너뛴다.
+pub async fn restore_rooms(state: &AppState, path: &Path) -> Result<usize> {
+    let json = match tokio::fs::read(path).await {
+        Ok(json) => json,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let file: RoomsFile = serde_json::from_slice(&json)
+        .with_context(|| format!("invalid room snapshot {}", path.display()))?;
+    if file.version != SNAPSHOT_VERSION {
+        anyhow::bail!("unsupported room snapshot version {}", file.version);
+    }
+
+    let timeout_ms = state.config.room.timeout_ms;
+    let reconnect_window = Duration::from_millis(state.config.persistence_reconnect_window_ms);
+    let now = Instant::now();
+    let mut restored = 0;
+    for snapshot in file.rooms {
+        // 활동 시각이 없는 이전 버전 파일은 생성 이후 내내 조용했던 것으로 본다
+        let idle_ms = snapshot.idle_ms.unwrap_or(snapshot.age_ms);
+        if idle_ms > timeout_ms || state.rooms.contains_key(&snapshot.room_id) {
+            continue;
+        }
+        let mut room = Room {
+            owner: snapshot.owner,
+            owner_token: snapshot.owner_token,
+            options: snapshot.options,
+            // 클라이언트가 다시 연결할 때까지 비어 있어도 정리하지 않는다
+            reserved_until: Some(now + reconnect_window),
+            ..Room::with_config(snapshot.room_id.clone(), &state.config.room)
+        };
+        if let Some(created_at) = now.checked_sub(Duration::from_millis(snapshot.age_ms)) {
+            room.created_at = created_at;
+        }
+        if let Some(last_activity) = now.checked_sub(Duration::from_millis(idle_ms)) {
+            room.last_activity = std::sync::Mutex::new(last_activity);
+        }
+        state.rooms.insert(snapshot.room_id, room);
+        restored += 1;
+    }
+
+    tracing::info!(path = %path.display(), rooms = restored, "Restored room metadata");
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("ponswarp-rooms-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn saved_rooms_are_restored_as_empty_rooms() {
+        let path = snapshot_path();
+        let source = AppState::new_for_test();
+        for room_id in ["alpha", "beta"] {
+            let room = Room::new(room_id.to_string());
+            room.users.write().await.insert("peer".to_string());
+            source.rooms.insert(room_id.to_string(), room);
+        }
+        assert_eq!(save_rooms(&source, &path).await.expect("save"), 2);
+
+        let restored = AppState::new_for_test();
+        assert_eq!(restore_rooms(&restored, &path).await.expect("load"), 2);
+        let _ = std::fs::remove_file(&path);
+
+        let ids: Vec<String> = snapshot_rooms(&restored)
+            .into_iter()
+            .map(|room| room.room_id)
+            .collect();
+        assert_eq!(ids, vec!["alpha".to_string(), "beta".to_string()]);
+        let alpha = restored.rooms.get("alpha").expect("alpha");
+        assert!(alpha.users.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn owner_and_options_survive_a_round_trip() {
+        let path = snapshot_path();
+        let options = RoomOptions {
+            host_only_offers: true,
+            public: true,
+            reconnect_grace_ms: Some(60_000),
+            ..RoomOptions::default()
+        };
+        let source = AppState::new_for_test();
+        source.rooms.insert(
+            "owned".to_string(),
+            Room {
+                owner: Some("host".to_string()),
+                owner_token: Some("host-token".to_string()),
+                options: options.clone(),
+                ..Room::new("owned".to_string())
+            },
+        );
+        save_rooms(&source, &path).await.expect("save");
+
+        let restored = AppState::new_for_test();
+        restore_rooms(&restored, &path).await.expect("load");
+        let _ = std::fs::remove_file(&path);
+
+        let room = restored.rooms.get("owned").expect("owned");
+        assert_eq!(room.owner.as_deref(), Some("host"));
+        assert_eq!(room.owner_token.as_deref(), Some("host-token"));
+        assert_eq!(room.options, options);
+    }
+
+    #[tokio::test]
+    async fn restored_force_relay_room_keeps_relay_policy() {
+        let path = snapshot_path();
+        let source = AppState::new_for_test();
+        let mut room = Room::new("relay".to_string());
+        room.options.force_relay = true;
+        source.rooms.insert("relay".to_string(), room);
+        save_rooms(&source, &path).await.expect("save");
+
+        let restored = std::sync::Arc::new(AppState::new_for_test());
+        restore_rooms(&restored, &path).await.expect("load");
+        let _ = std::fs::remove_file(&path);
+        let mut guest = restored.insert_test_peer("guest");
+        crate::handlers::handle_join_room(
+            restored.clone(),
+            "guest",
+            "relay",
+            crate::protocol::PeerRole::Participant,
+            None,
+        )
+        .await;
+
+        let policy = crate::state::drain_messages(&mut guest)
+            .into_iter()
+            .find_map(|message| match message {
+                crate::protocol::ServerMessage::JoinedRoom { policy, .. } => Some(policy),
+                _ => None,
+            });
+        assert_eq!(policy.map(|policy| policy.force_relay), Some(true));
+    }
+
+    #[tokio::test]
+    async fn owner_reclaims_restored_host_only_room_with_saved_token() {
+        use crate::handlers::{handle_join_room, handle_offer};
+        use crate::protocol::{PeerRole, ServerMessage};
+
+        let path = snapshot_path();
+        let source = AppState::new_for_test();
+        let mut room = Room::new("hosted".to_string());
+        room.owner = Some("old-peer-id".to_string());
+        room.owner_token = Some("token-host".to_string());
+        room.options.host_only_offers = true;
+        source.rooms.insert("hosted".to_string(), room);
+        save_rooms(&source, &path).await.expect("save");
+
+        let restored = std::sync::Arc::new(AppState::new_for_test());
+        restore_rooms(&restored, &path).await.expect("load");
+        let _ = std::fs::remove_file(&path);
+        // insert_test_peer는 `token-{peer_id}`를 client_token으로 쓴다
+        let _host = restored.insert_test_peer("host");
+        let mut guest = restored.insert_test_peer("guest");
+        for peer_id in ["host", "guest"] {
+            handle_join_room(
+                restored.clone(),
+                peer_id,
+                "hosted",
+                PeerRole::Participant,
+                None,
+            )
+            .await;
+        }
+        crate::state::drain_messages(&mut guest);
+        assert_eq!(
+            restored.rooms.get("hosted").unwrap().owner.as_deref(),
+            Some("host")
+        );
+
+        handle_offer(
+            restored.clone(),
+            "host",
+            "hosted",
+            "v=0",
+            Some("guest"),
+            None,
+        )
+        .await;
+        assert!(crate::state::drain_messages(&mut guest)
+            .iter()
+            .any(|message| matches!(message, ServerMessage::Offer { .. })));
+    }
+
+    #[tokio::test]
+    async fn restored_rooms_wait_for_reconnects_and_keep_idle_time() {
+        let path = snapshot_path();
+        let source = AppState::new_for_test();
+        source
+            .rooms
+            .insert("fresh".to_string(), Room::new("fresh".to_string()));
+        let quiet = Room::new("quiet".to_string());
+        *quiet.last_activity.lock().unwrap() = Instant::now() - Duration::from_secs(10);
+        source.rooms.insert("quiet".to_string(), quiet);
+        save_rooms(&source, &path).await.expect("save");
+
+        let mut config = crate::config::Config::from_env();
+        config.room.timeout_ms = 5_000;
+        config.room.empty_room_linger_ms = 1;
+        let restored = AppState::new_for_test_with_config(config);
+        // 만든 지는 얼마 안 됐어도 저장 당시 이미 ROOM_TIMEOUT만큼 조용했던 방은 복원하지 않는다
+        assert_eq!(restore_rooms(&restored, &path).await.expect("load"), 1);
+        let _ = std::fs::remove_file(&path);
+        assert!(restored.rooms.get("quiet").is_none());
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(crate::handlers::reap_empty_rooms(&restored), 0);
+        assert!(restored.rooms.get("fresh").unwrap().held_empty());
+    }
+
+    #[test]
+    fn snapshot_without_owner_or_options_still_loads() {
+        let snapshot: RoomSnapshot =
+            serde_json::from_str(r#"{"room_id": "old", "age_ms": 5}"#).unwrap();
+        assert_eq!(snapshot.owner, None);
+        assert_eq!(snapshot.options, RoomOptions::default());
+    }
+
+    #[tokio::test]
+    async fn expired_rooms_are_not_restored() {
+        let path = snapshot_path();
+        let file = RoomsFile {
+            version: SNAPSHOT_VERSION,
+            saved_at: 0,
+            rooms: vec![RoomSnapshot {
+                room_id: "stale".to_string(),
+                age_ms: u64::MAX,
+                idle_ms: None,
+                owner: None,
+                owner_token: None,
+                options: RoomOptions::default(),
+            }],
+        };
+        std::fs::write(&path, serde_json::to_vec(&file).unwrap()).unwrap();
+
+        let state = AppState::new_for_test();
+        assert_eq!(restore_rooms(&state, &path).await.expect("load"), 0);
+        let _ = std::fs::remove_file(&path);
+        assert!(state.rooms.is_empty());
+    }
+
+    #[tokio::test]
+    async fn missing_snapshot_is_not_an_error() {
+        let state = AppState::new_for_test();
+        assert_eq!(
+            restore_rooms(&state, &snapshot_path()).await.expect("load"),
+            0
+        );
+    }
+}
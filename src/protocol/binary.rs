@@ -0,0 +1,80 @@
+//! 바이너리 WebSocket frame 형식 (BinaryRelay 전용)
+//!
+//! 클라이언트 → 서버: `[0x01][room_id 길이 u8][room_id][target 길이 u8][target][data...]`
+//! 서버 → 클라이언트: `[0x01][from 길이 u8][from][data...]`
+//!
+//! 문자열은 UTF-8이며 data는 frame의 나머지 전부다. base64 없이 그대로 중계한다.
+
+use super::{ClientMessage, ServerMessage};
+
+/// frame 첫 바이트: BinaryRelay
+pub const BINARY_RELAY: u8 = 0x01;
+
+/// 클라이언트가 보낸 바이너리 frame을 ClientMessage로 해석한다.
+pub fn decode_client_frame(frame: &[u8]) -> Result<ClientMessage, &'static str> {
+    let (&kind, rest) = frame.split_first().ok_or("empty frame")?;
+    if kind != BINARY_RELAY {
+        return Err("unknown frame kind");
+    }
+    let (room_id, rest) = read_str(rest)?;
+    let (target, data) = read_str(rest)?;
+    Ok(ClientMessage::BinaryRelay {
+        room_id,
+        target,
+        data: data.to_vec(),
+    })
+}
+
+/// 바이너리 frame으로 보낼 메시지면 인코딩한다. 그 외 메시지는 None (JSON Text로 보낸다).
+pub fn encode_server_frame(message: &ServerMessage) -> Option<Vec<u8>> {
+    let ServerMessage::BinaryRelay { from, data } = message else {
+        return None;
+    };
+    let from_len = u8::try_from(from.len()).ok()?;
+    let mut frame = Vec::with_capacity(2 + from.len() + data.len());
+    frame.push(BINARY_RELAY);
+    frame.push(from_len);
+    frame.extend_from_slice(from.as_bytes());
+    frame.extend_from_slice(data);
+    Some(frame)
+}
+
+/// 길이(u8)가 앞에 붙은 UTF-8 문자열을 읽고 나머지를 돌려준다.
+fn read_str(bytes: &[u8]) -> Result<(String, &[u8]), &'static str> {
+    let (&len, rest) = bytes.split_first().ok_or("truncated frame")?;
+    if rest.len() < len as usize {
+        return Err("truncated frame");
+    }
+    let (value, rest) = rest.split_at(len as usize);
+    let value = std::str::from_utf8(value).map_err(|_| "invalid utf-8 in frame")?;
+    Ok((value.to_string(), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_frame_decodes_and_rejects_truncation() {
+        let mut frame = vec![BINARY_RELAY, 4];
+        frame.extend_from_slice(b"room");
+        frame.push(4);
+        frame.extend_from_slice(b"peer");
+        frame.extend_from_slice(&[0, 255, 7]);
+
+        let decoded = decode_client_frame(&frame).expect("valid frame");
+        assert!(matches!(
+            decoded,
+            ClientMessage::BinaryRelay { room_id, target, data }
+                if room_id == "room" && target == "peer" && data == [0, 255, 7]
+        ));
+        assert_eq!(
+            decode_client_frame(&frame[..5]).err(),
+            Some("truncated frame")
+        );
+        assert_eq!(
+            decode_client_frame(&[0x7f]).err(),
+            Some("unknown frame kind")
+        );
+    }
+}
@@ -8,29 +8,128 @@ use serde::{Deserialize, Serialize};
 pub enum ClientMessage {
     // Connection
     Heartbeat,
+    /// 현재 세션의 peer_id/방/역할 조회
+    WhoAmI,
+    /// 사용할 프로토콜 확장 목록 (예: ["room_snapshot"]). 서버는 지원하는 것만 `HelloAck`로 돌려준다.
+    Hello {
+        capabilities: Vec<String>,
+    },
+    /// 받을 ServerMessage 종류 지정 (예: ["RoomUsers", "PeerJoined"]). 빈 목록이면 전부 받는다.
+    Subscribe {
+        types: Vec<String>,
+    },
+    IsPeerOnline {
+        peer_id: String,
+    },
 
     // Room Management
     JoinRoom {
         room_id: String,
+        /// 생략 시 Participant
+        #[serde(default)]
+        role: Option<PeerRole>,
+        /// 이 입장으로 방이 새로 만들어질 때만 적용된다.
+        #[serde(default)]
+        options: Option<RoomOptions>,
+        /// 클라이언트가 응답을 짝짓는 데 쓰는 값. 응답에 그대로 돌려준다.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        correlation_id: Option<String>,
+    },
+    /// payload 생략 시 현재 방을 나간다 (`{"type": "LeaveRoom"}` 호환)
+    LeaveRoom(#[serde(default)] Option<LeaveRoomPayload>),
+    /// 방 metadata 설정 (owner 전용, ROOM_METADATA_MAX_BYTES 이하)
+    SetRoomMetadata {
+        room_id: String,
+        metadata: serde_json::Value,
+    },
+    /// 방 표시 이름 변경 (owner 전용). 빈 문자열은 이름을 지운다.
+    SetRoomName {
+        room_id: String,
+        name: String,
+    },
+    /// 공개 방 목록 조회 (로비용)
+    ListPublicRooms,
+    /// 참여 중인 방의 전체 RoomUsers 재요청 (delta 누락 복구용)
+    ResyncRoom {
+        room_id: String,
+        /// 클라이언트가 응답을 짝짓는 데 쓰는 값. 응답에 그대로 돌려준다.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        correlation_id: Option<String>,
+    },
+    /// 방을 모두에게 끝낸다 (owner 전용). 멤버 전원이 SessionEnded를 받고 방은 삭제된다.
+    EndSession {
+        room_id: String,
+    },
+    /// 방 owner 조회 (멤버 전용)
+    GetRoomOwner {
+        room_id: String,
     },
-    LeaveRoom,
 
     // WebRTC Signaling
     Offer {
         room_id: String,
         sdp: String,
         target: Option<String>,
+        /// 재협상 세대. 생략하면 서버가 source→target 쌍마다 매긴다
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        generation: Option<u64>,
     },
     Answer {
         room_id: String,
         sdp: String,
         target: Option<String>,
+        /// 응답하는 Offer의 세대. 생략하면 서버가 마지막 target Offer의 세대를 붙인다
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        generation: Option<u64>,
     },
     IceCandidate {
         room_id: String,
-        candidate: String,
+        candidate: IceCandidateInput,
         target: Option<String>,
     },
+    /// 네트워크 변경으로 ICE restart를 할 예정임을 targets에게 알린다 (Offer는 따로 보낸다).
+    IceRestart {
+        room_id: String,
+        targets: Vec<String>,
+    },
+    /// target에게 그대로 전달하는 작은 바이너리 데이터. 보통 바이너리 frame으로 보낸다 (protocol::binary).
+    BinaryRelay {
+        room_id: String,
+        target: String,
+        data: Vec<u8>,
+    },
+    /// 신원 확인용 데이터(SAS, fingerprint attestation 등)를 target에게 전달한다. 서버는 내용을 해석하지 않는다.
+    IdentityAssertion {
+        room_id: String,
+        target: String,
+        assertion: String,
+    },
+    /// 방 전체 ICE restart를 요청한다. 서버가 새 epoch를 정해 멤버 전원에게 IceRestartEpoch로 알린다.
+    CoordinateIceRestart {
+        room_id: String,
+    },
+    /// 방에서 `role` 역할인 피어 전원에게 임의 이벤트를 전달한다 (예: 모든 Observer에게 알림).
+    RelayToRole {
+        room_id: String,
+        role: PeerRole,
+        event: String,
+        data: serde_json::Value,
+    },
+    /// 에러 문구 언어 변경 (예: "ko", "en"). 연결 시 `?lang=`으로도 정할 수 있다.
+    SetLocale {
+        lang: String,
+    },
+    /// 이 피어가 지원하는 기능을 알린다 (예: "supports-quic"). 이전 목록을 대체한다.
+    SetCapabilities {
+        capabilities: Vec<String>,
+    },
+    /// 방에서 `capability`를 알린 피어에게만 임의 이벤트를 전달한다.
+    RelayToCapable {
+        room_id: String,
+        capability: String,
+        event: String,
+        data: serde_json::Value,
+    },
 
     // File Transfer Manifest (Native QUIC mode)
     Manifest {
@@ -38,6 +137,13 @@ pub enum ClientMessage {
         manifest: String, // JSON stringified manifest
         target: Option<String>,
     },
+    ManifestChunk {
+        room_id: String,
+        target: Option<String>,
+        chunk_index: u32,
+        total_chunks: u32,
+        data: String,
+    },
 
     // 🆕 Transfer Ready (Receiver -> Sender)
     TransferReady {
@@ -51,10 +157,21 @@ pub enum ClientMessage {
         target: Option<String>,
     },
 
+    /// 전송 진행률 (Sender -> Receiver). 보내는 피어당 TRANSFER_PROGRESS_RATE로 솎아낸다.
+    TransferProgress {
+        room_id: String,
+        target: Option<String>,
+        percent: f32,
+        bytes: u64,
+    },
+
     // TURN
     RequestTurnConfig {
         room_id: String,
         force_refresh: Option<bool>,
+        /// 클라이언트가 응답을 짝짓는 데 쓰는 값. 응답에 그대로 돌려준다.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        correlation_id: Option<String>,
     },
     RefreshTurnCredentials {
         room_id: String,
@@ -63,6 +180,82 @@ pub enum ClientMessage {
     CheckTurnServerStatus,
 }
 
+/// 방 생성 시 정하는 방 정책
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomOptions {
+    /// 방을 만든 피어(host)만 Offer를 보낼 수 있다 (star topology). 나머지는 Answer만 보낸다.
+    #[serde(default)]
+    pub host_only_offers: bool,
+    /// 모든 미디어를 TURN으로만 중계한다 (클라이언트는 `iceTransportPolicy: "relay"` 적용)
+    #[serde(default)]
+    pub force_relay: bool,
+    /// ListPublicRooms 결과에 노출한다
+    #[serde(default)]
+    pub public: bool,
+    /// owner가 나가면 방을 닫고 남은 멤버에게 `RoomClosed { reason: "owner_left" }`를 보낸다
+    #[serde(default)]
+    pub close_on_owner_leave: bool,
+    /// 방이 가득 차면 새 참여자를 거절하지 않고 가장 오래 활동이 없던 참여자를 내보낸다 (키오스크용)
+    #[serde(default)]
+    pub evict_oldest_on_full: bool,
+    /// 이 방의 재접속 유예(ms). 설정하면 DISCONNECT_GRACE_MS 대신 사용한다 (최대 10분)
+    #[serde(default)]
+    pub reconnect_grace_ms: Option<u64>,
+}
+
+impl RoomOptions {
+    pub fn policy(&self) -> RoomPolicy {
+        RoomPolicy {
+            force_relay: self.force_relay,
+        }
+    }
+}
+
+/// 공개 방 요약
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicRoomInfo {
+    pub id: String,
+    pub user_count: usize,
+    pub max_size: usize,
+}
+
+/// 입장 시 JoinedRoom으로 전달하는 방 정책. 클라이언트는 RTCPeerConnection 설정에 반영한다.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomPolicy {
+    pub force_relay: bool,
+}
+
+/// 입장 시점의 방 상태 (RoomSnapshot)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomSnapshotData {
+    pub room_id: String,
+    /// 입장한 피어 자신의 socket_id
+    pub you: String,
+    /// 자신을 포함한 현재 멤버
+    pub users: Vec<String>,
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub user_count: usize,
+    /// 참여자(Observer 제외) 최대 인원 (MAX_ROOM_SIZE)
+    pub max_size: usize,
+    pub policy: RoomPolicy,
+    pub created: bool,
+    /// JoinRoom에 담겨 온 correlation_id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
+
+/// LeaveRoom payload
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LeaveRoomPayload {
+    /// 나갈 방. 없으면 현재 방
+    #[serde(default)]
+    pub room_id: Option<String>,
+}
+
 /// 서버 → 클라이언트 메시지
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "payload")]
@@ -70,11 +263,60 @@ pub enum ServerMessage {
     // Connection
     Connected {
         socket_id: String,
+        /// 재접속 후에도 유지되는 클라이언트 토큰. 중계 target으로 사용할 수 있다.
+        client_token: String,
+        /// 재접속 유예 중 `?resume_token=`으로 넘기면 이전 세션과 방을 이어받는다. 본인만 알아야 한다.
+        resume_token: String,
+        /// 서버 크레이트 버전
+        server_version: String,
+        /// 연결을 처리한 서버 인스턴스 (SERVER_INSTANCE_ID)
+        instance_id: String,
     },
     HeartbeatAck,
+    /// Hello로 요청한 확장 중 켜진 것
+    HelloAck {
+        capabilities: Vec<String>,
+    },
+    /// 운영 안내 (MOTD 등)
+    Notice {
+        text: String,
+        level: NoticeLevel,
+    },
+    /// IsPeerOnline 응답. room_id는 요청자와 같은 방일 때만 채워진다.
+    PeerStatus {
+        peer_id: String,
+        online: bool,
+        room_id: Option<String>,
+    },
+    /// WhoAmI 응답
+    Identity {
+        peer_id: String,
+        room_id: Option<String>,
+        role: PeerRole,
+    },
+    /// 시그널링이 조용한 방의 멤버에게 보내는 안내. 클라이언트는 Heartbeat로 응답한다.
+    KeepAlive {
+        room_id: String,
+    },
+    /// ROOM_TIMEOUT으로 방이 `in_ms` 뒤 정리된다는 예고. 그 안에 Heartbeat가 오면 방이 유지된다.
+    RoomClosing {
+        room_id: String,
+        in_ms: u64,
+    },
+    /// 관리자 진단용 서버 발신 ping
+    Ping {
+        nonce: String,
+        server_ts: u64,
+    },
     Error {
         code: String,
         message: String,
+        /// 재시도 전 대기 권장 시간 (server_at_capacity, rate_limited)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retry_after_ms: Option<u64>,
+        /// 거절한 요청에 담겨 온 correlation_id
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        correlation_id: Option<String>,
     },
 
     // Room Events
@@ -82,9 +324,59 @@ pub enum ServerMessage {
         room_id: String,
         socket_id: String,
         user_count: usize,
+        policy: RoomPolicy,
+        /// 방에 설정된 metadata (늦게 입장한 피어용)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<serde_json::Value>,
+        /// 방 표시 이름
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        /// 이 입장으로 방이 새로 만들어졌으면 true (입장한 피어가 owner)
+        #[serde(default)]
+        created: bool,
+        /// 요청에 담겨 온 correlation_id
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        correlation_id: Option<String>,
+    },
+    /// `room_snapshot` 확장을 켠 피어가 입장하면 `RoomUsers` + `JoinedRoom` 대신 받는 방 상태 전체
+    RoomSnapshot(Box<RoomSnapshotData>),
+    /// owner가 방 이름을 바꿨을 때 방 전체에 전달. None이면 이름을 지웠다.
+    RoomNameChanged {
+        name: Option<String>,
+    },
+    /// owner가 metadata를 바꿨을 때 방 전체에 전달
+    RoomMetadataUpdated {
+        metadata: serde_json::Value,
     },
+    /// GetRoomOwner 응답. 복원된 방처럼 owner가 없으면 None
+    RoomOwner {
+        room_id: String,
+        owner: Option<String>,
+    },
+    /// 입장/ResyncRoom 시에만 보내는 전체 목록 (자신 제외)
     RoomUsers {
         users: Vec<String>,
+        /// 요청에 담겨 온 correlation_id
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        correlation_id: Option<String>,
+    },
+    /// ListPublicRooms 응답. 비공개 방은 포함하지 않는다.
+    PublicRooms {
+        rooms: Vec<PublicRoomInfo>,
+    },
+    /// owner가 EndSession으로 방을 끝냄. 수신한 클라이언트는 더 이상 방 멤버가 아니다.
+    SessionEnded {
+        reason: String,
+    },
+    /// 관리자가 방을 닫음. 수신한 클라이언트는 더 이상 방 멤버가 아니다.
+    RoomClosed {
+        room_id: String,
+        reason: String,
+    },
+    /// 이후 참여자 변경분. 클라이언트는 RoomUsers 목록에 순서대로 적용한다.
+    RoomUsersDelta {
+        added: Vec<String>,
+        removed: Vec<String>,
     },
     PeerJoined {
         socket_id: String,
@@ -95,20 +387,70 @@ pub enum ServerMessage {
     },
     RoomFull {
         room_id: String,
+        /// 입장 요청에 담겨 온 correlation_id
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        correlation_id: Option<String>,
+    },
+    /// `evict_oldest_on_full` 방에 새 참여자가 들어와 내보내졌다. 연결은 유지된다.
+    Evicted {
+        room_id: String,
+    },
+    /// 입장 시 전달되는 최근 방 이벤트 (SDP 등 민감한 내용은 포함하지 않음)
+    RoomHistory {
+        events: Vec<RoomEvent>,
     },
 
     // WebRTC Signaling
     Offer {
         from: String,
         sdp: String,
+        /// 받는 쪽도 보낸 쪽에게 Offer를 보낸 상태(glare). perfect negotiation rollback에 사용
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        glare: bool,
+        /// 이 Offer의 재협상 세대. 받는 쪽은 이보다 낮은 세대의 SDP를 버린다
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        generation: Option<u64>,
     },
     Answer {
         from: String,
         sdp: String,
+        /// 응답한 Offer의 세대
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        generation: Option<u64>,
     },
     IceCandidate {
         from: String,
-        candidate: String,
+        candidate: IceCandidatePayload,
+    },
+    /// `from`이 곧 새 Offer로 재협상한다.
+    IceRestart {
+        from: String,
+    },
+    /// 방 전체 ICE restart epoch. 멤버는 이 epoch로 재협상하고 이전 epoch의 candidate는 버린다.
+    IceRestartEpoch {
+        epoch: u64,
+    },
+    /// BinaryRelay 전달. 송신 태스크가 바이너리 frame으로 인코딩한다.
+    BinaryRelay {
+        from: String,
+        data: Vec<u8>,
+    },
+    /// IdentityAssertion 전달
+    IdentityAssertion {
+        from: String,
+        assertion: String,
+    },
+    RelayToRole {
+        from: String,
+        role: PeerRole,
+        event: String,
+        data: serde_json::Value,
+    },
+    RelayToCapable {
+        from: String,
+        capability: String,
+        event: String,
+        data: serde_json::Value,
     },
 
     // File Transfer Manifest (Native QUIC mode)
@@ -116,6 +458,12 @@ pub enum ServerMessage {
         from: String,
         manifest: String,
     },
+    ManifestChunk {
+        from: String,
+        chunk_index: u32,
+        total_chunks: u32,
+        data: String,
+    },
 
     // 🆕 Transfer Ready (Receiver -> Sender)
     TransferReady {
@@ -127,11 +475,24 @@ pub enum ServerMessage {
         from: String,
     },
 
+    TransferProgress {
+        from: String,
+        percent: f32,
+        bytes: u64,
+    },
+
     // TURN
     TurnConfig {
         success: bool,
         data: Option<TurnConfigData>,
         error: Option<String>,
+        /// 요청에 담겨 온 correlation_id
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        correlation_id: Option<String>,
+    },
+    /// 발급받은 TURN 자격증명이 곧 만료된다는 안내 (TURN_EXPIRY_REMINDER_MS). 클라이언트는 갱신을 요청한다.
+    CredentialExpiringSoon {
+        expires_in_ms: u64,
     },
     TurnServerStatusUpdate {
         room_id: String,
@@ -139,6 +500,158 @@ pub enum ServerMessage {
     },
 }
 
+/// 송신이 밀릴 때의 처리 우선순위 (OUTBOUND_SHED_BACKLOG)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessagePriority {
+    /// 적체 시 먼저 버린다. 곧 최신 값이 다시 오는 진행률/keepalive 성격의 메시지
+    Low,
+    /// 요청에 대한 응답(PeerStatus)과 완료(100%) 진행률. 다시 오지 않으므로 버리지 않는다
+    Normal,
+    /// 시그널링, 완료 알림, 에러 등 버리면 안 되는 메시지
+    High,
+}
+
+impl ServerMessage {
+    /// 직렬화할 때의 `type` 이름. Subscribe 필터가 직렬화 없이 종류를 비교하는 데 쓴다
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Connected { .. } => "Connected",
+            Self::HeartbeatAck => "HeartbeatAck",
+            Self::HelloAck { .. } => "HelloAck",
+            Self::Notice { .. } => "Notice",
+            Self::PeerStatus { .. } => "PeerStatus",
+            Self::Identity { .. } => "Identity",
+            Self::KeepAlive { .. } => "KeepAlive",
+            Self::RoomClosing { .. } => "RoomClosing",
+            Self::Ping { .. } => "Ping",
+            Self::Error { .. } => "Error",
+            Self::JoinedRoom { .. } => "JoinedRoom",
+            Self::RoomSnapshot(_) => "RoomSnapshot",
+            Self::RoomNameChanged { .. } => "RoomNameChanged",
+            Self::RoomMetadataUpdated { .. } => "RoomMetadataUpdated",
+            Self::RoomOwner { .. } => "RoomOwner",
+            Self::RoomUsers { .. } => "RoomUsers",
+            Self::PublicRooms { .. } => "PublicRooms",
+            Self::SessionEnded { .. } => "SessionEnded",
+            Self::RoomClosed { .. } => "RoomClosed",
+            Self::RoomUsersDelta { .. } => "RoomUsersDelta",
+            Self::PeerJoined { .. } => "PeerJoined",
+            Self::UserLeft { .. } => "UserLeft",
+            Self::RoomFull { .. } => "RoomFull",
+            Self::Evicted { .. } => "Evicted",
+            Self::RoomHistory { .. } => "RoomHistory",
+            Self::Offer { .. } => "Offer",
+            Self::Answer { .. } => "Answer",
+            Self::IceCandidate { .. } => "IceCandidate",
+            Self::IceRestart { .. } => "IceRestart",
+            Self::IceRestartEpoch { .. } => "IceRestartEpoch",
+            Self::BinaryRelay { .. } => "BinaryRelay",
+            Self::IdentityAssertion { .. } => "IdentityAssertion",
+            Self::RelayToRole { .. } => "RelayToRole",
+            Self::RelayToCapable { .. } => "RelayToCapable",
+            Self::Manifest { .. } => "Manifest",
+            Self::ManifestChunk { .. } => "ManifestChunk",
+            Self::TransferReady { .. } => "TransferReady",
+            Self::TransferComplete { .. } => "TransferComplete",
+            Self::TransferProgress { .. } => "TransferProgress",
+            Self::TurnConfig { .. } => "TurnConfig",
+            Self::CredentialExpiringSoon { .. } => "CredentialExpiringSoon",
+            Self::TurnServerStatusUpdate { .. } => "TurnServerStatusUpdate",
+        }
+    }
+
+    pub fn priority(&self) -> MessagePriority {
+        match self {
+            Self::TransferProgress { percent, .. } if *percent >= 100.0 => MessagePriority::Normal,
+            Self::PeerStatus { .. } => MessagePriority::Normal,
+            Self::TransferProgress { .. } | Self::KeepAlive { .. } | Self::Ping { .. } => {
+                MessagePriority::Low
+            }
+            _ => MessagePriority::High,
+        }
+    }
+}
+
+/// Notice 수준
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoticeLevel {
+    Info,
+    Warn,
+}
+
+/// 방 내 피어 역할
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PeerRole {
+    #[default]
+    Participant,
+    /// 브로드캐스트 수신만 가능하며 시그널링을 보낼 수 없다. 방 인원에 포함되지 않는다.
+    Observer,
+}
+
+/// 방 히스토리에 보관되는 이벤트
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RoomEvent {
+    PeerJoined { socket_id: String, timestamp: u64 },
+    UserLeft { socket_id: String, timestamp: u64 },
+}
+
+/// 클라이언트가 보내는 ICE candidate.
+/// candidate 라인 문자열, JSON 문자열로 직렬화된 객체, `RTCIceCandidateInit` 객체를 모두 허용한다.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IceCandidateInput {
+    Structured(IceCandidatePayload),
+    Raw(String),
+}
+
+/// 서버가 중계하는 정규화된 ICE candidate (`RTCIceCandidateInit` 호환).
+///
+/// ```json
+/// {"candidate": "candidate:1 1 udp 2122260223 10.0.0.1 54321 typ host", "sdpMid": "0", "sdpMLineIndex": 0}
+/// ```
+///
+/// `candidate`가 빈 문자열이면 end-of-candidates 신호다.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IceCandidatePayload {
+    pub candidate: String,
+    #[serde(default)]
+    pub sdp_mid: Option<String>,
+    #[serde(default)]
+    pub sdp_m_line_index: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username_fragment: Option<String>,
+}
+
+impl IceCandidateInput {
+    /// 입력을 정규화된 형태로 변환한다. candidate 라인이 아니면 에러를 반환한다.
+    pub fn normalize(&self) -> Result<IceCandidatePayload, &'static str> {
+        let mut payload = match self {
+            Self::Structured(payload) => payload.clone(),
+            Self::Raw(raw) => match serde_json::from_str::<IceCandidatePayload>(raw) {
+                Ok(payload) => payload,
+                Err(_) => IceCandidatePayload {
+                    candidate: raw.clone(),
+                    sdp_mid: None,
+                    sdp_m_line_index: None,
+                    username_fragment: None,
+                },
+            },
+        };
+
+        let line = payload.candidate.trim();
+        let line = line.strip_prefix("a=").unwrap_or(line);
+        if !line.is_empty() && !line.starts_with("candidate:") {
+            return Err("candidate must be an SDP candidate line");
+        }
+        payload.candidate = line.to_string();
+        Ok(payload)
+    }
+}
+
 /// TURN 설정 데이터
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TurnConfigData {
@@ -146,6 +659,8 @@ pub struct TurnConfigData {
     pub ttl: u64,
     pub timestamp: u64,
     pub room_id: String,
+    /// RTCPeerConnection iceTransportPolicy ("all" | "relay"). force_relay 방이면 relay
+    pub ice_transport_policy: String,
 }
 
 /// ICE 서버 설정
@@ -164,6 +679,43 @@ pub struct IceServer {
 mod tests {
     use super::*;
 
+    #[test]
+    fn kind_matches_serialized_type() {
+        let messages = [
+            ServerMessage::HeartbeatAck,
+            ServerMessage::RoomUsers {
+                users: Vec::new(),
+                correlation_id: None,
+            },
+            ServerMessage::PeerJoined {
+                socket_id: "peer".to_string(),
+                room_id: "room".to_string(),
+            },
+        ];
+        for message in messages {
+            let value = serde_json::to_value(&message).unwrap();
+            assert_eq!(value["type"], message.kind());
+        }
+    }
+
+    #[test]
+    fn request_replies_and_completed_progress_are_not_shed() {
+        let progress = |percent| ServerMessage::TransferProgress {
+            from: "sender".to_string(),
+            percent,
+            bytes: 0,
+        };
+        let status = ServerMessage::PeerStatus {
+            peer_id: "peer".to_string(),
+            online: true,
+            room_id: None,
+        };
+
+        assert_eq!(progress(42.0).priority(), MessagePriority::Low);
+        assert_eq!(progress(100.0).priority(), MessagePriority::Normal);
+        assert_eq!(status.priority(), MessagePriority::Normal);
+    }
+
     #[test]
     fn client_manifest_round_trips_with_target() {
         let message = ClientMessage::Manifest {
@@ -192,6 +744,89 @@ mod tests {
         }
     }
 
+    #[test]
+    fn raw_ice_candidate_string_normalizes_to_structured_form() {
+        let value = serde_json::json!({
+            "type": "IceCandidate",
+            "payload": {
+                "room_id": "room-123",
+                "candidate": "a=candidate:1 1 udp 2122260223 10.0.0.1 54321 typ host",
+                "target": null
+            }
+        });
+
+        let decoded: ClientMessage = serde_json::from_value(value).expect("deserialize");
+        let ClientMessage::IceCandidate { candidate, .. } = decoded else {
+            panic!("unexpected message");
+        };
+        assert_eq!(
+            candidate.normalize().expect("valid candidate"),
+            IceCandidatePayload {
+                candidate: "candidate:1 1 udp 2122260223 10.0.0.1 54321 typ host".to_string(),
+                sdp_mid: None,
+                sdp_m_line_index: None,
+                username_fragment: None,
+            }
+        );
+    }
+
+    #[test]
+    fn structured_ice_candidate_object_and_json_string_normalize_identically() {
+        let object = serde_json::json!({
+            "candidate": "candidate:1 1 udp 2122260223 10.0.0.1 54321 typ host",
+            "sdpMid": "0",
+            "sdpMLineIndex": 0
+        });
+        let from_object: IceCandidateInput =
+            serde_json::from_value(object.clone()).expect("object form");
+        let from_string: IceCandidateInput =
+            serde_json::from_value(serde_json::Value::String(object.to_string()))
+                .expect("string form");
+
+        let normalized = from_object.normalize().expect("valid candidate");
+        assert_eq!(
+            normalized,
+            from_string.normalize().expect("valid candidate")
+        );
+        assert_eq!(normalized.sdp_mid.as_deref(), Some("0"));
+        assert_eq!(normalized.sdp_m_line_index, Some(0));
+
+        let relayed = serde_json::to_value(ServerMessage::IceCandidate {
+            from: "peer-1".to_string(),
+            candidate: normalized,
+        })
+        .expect("serialize");
+        assert_eq!(relayed["payload"]["candidate"], object);
+    }
+
+    #[test]
+    fn ice_candidate_rejects_non_candidate_lines_and_keeps_end_of_candidates() {
+        let garbage = IceCandidateInput::Raw("not a candidate".to_string());
+        assert!(garbage.normalize().is_err());
+
+        let end = IceCandidateInput::Raw(String::new());
+        assert_eq!(end.normalize().expect("end-of-candidates").candidate, "");
+    }
+
+    #[test]
+    fn leave_room_accepts_missing_or_explicit_room_id() {
+        let bare: ClientMessage =
+            serde_json::from_value(serde_json::json!({"type": "LeaveRoom"})).expect("bare");
+        assert!(matches!(bare, ClientMessage::LeaveRoom(None)));
+
+        let explicit: ClientMessage = serde_json::from_value(serde_json::json!({
+            "type": "LeaveRoom",
+            "payload": {"room_id": "room-123"}
+        }))
+        .expect("explicit");
+        match explicit {
+            ClientMessage::LeaveRoom(Some(payload)) => {
+                assert_eq!(payload.room_id.as_deref(), Some("room-123"));
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+    }
+
     #[test]
     fn server_turn_config_omits_absent_credentials() {
         let message = ServerMessage::TurnConfig {
@@ -206,8 +841,10 @@ mod tests {
                 ttl: 600,
                 timestamp: 1_700_000_000,
                 room_id: "room-123".to_string(),
+                ice_transport_policy: "all".to_string(),
             }),
             error: None,
+            correlation_id: None,
         };
 
         let value = serde_json::to_value(&message).expect("serialize turn config");
@@ -1,5 +1,9 @@
 //! 프로토콜 모듈
 
+pub mod binary;
 pub mod messages;
+pub mod sdp;
+pub mod signing;
+pub mod strict;
 
 pub use messages::*;
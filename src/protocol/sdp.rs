@@ -0,0 +1,145 @@
+//! 중계 전 SDP 변환 (코덱 선호도)
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// 각 `m=` 라인의 payload type 순서를 선호 코덱이 앞에 오도록 재배열한다.
+///
+/// `preference`는 코덱 이름 목록(대소문자 무시, 예: `["VP8", "opus"]`)이며,
+/// 선호 목록에 없는 payload type은 원래 순서를 유지한 채 뒤에 남는다.
+/// 목록이 비어 있거나 바뀔 내용이 없으면 입력을 그대로 돌려준다.
+pub fn apply_codec_preference<'a>(sdp: &'a str, preference: &[String]) -> Cow<'a, str> {
+    if preference.is_empty() {
+        return Cow::Borrowed(sdp);
+    }
+
+    let line_ending = if sdp.contains("\r\n") { "\r\n" } else { "\n" };
+    let lines: Vec<&str> = sdp.split(line_ending).collect();
+    let codecs = rtpmap_codecs(&lines);
+
+    let mut changed = false;
+    let mut output = Vec::with_capacity(lines.len());
+    for (index, line) in lines.iter().enumerate() {
+        match line.strip_prefix("m=") {
+            Some(media) => {
+                let codecs = codecs.get(&index).cloned().unwrap_or_default();
+                let reordered = reorder_media_line(media, &codecs, preference);
+                changed |= reordered != *line;
+                output.push(Cow::Owned(reordered));
+            }
+            None => output.push(Cow::Borrowed(*line)),
+        }
+    }
+
+    if !changed {
+        return Cow::Borrowed(sdp);
+    }
+    Cow::Owned(output.join(line_ending))
+}
+
+/// 미디어 섹션별 `a=rtpmap` 정보 (m= 라인 위치 -> payload type -> 코덱 이름)
+fn rtpmap_codecs(lines: &[&str]) -> HashMap<usize, HashMap<String, String>> {
+    let mut sections: HashMap<usize, HashMap<String, String>> = HashMap::new();
+    let mut current = None;
+    for (index, line) in lines.iter().enumerate() {
+        if line.starts_with("m=") {
+            current = Some(index);
+            continue;
+        }
+        let (Some(section), Some(rtpmap)) = (current, line.strip_prefix("a=rtpmap:")) else {
+            continue;
+        };
+        let Some((payload_type, encoding)) = rtpmap.split_once(' ') else {
+            continue;
+        };
+        let name = encoding.split('/').next().unwrap_or_default();
+        sections
+            .entry(section)
+            .or_default()
+            .insert(payload_type.to_string(), name.to_ascii_lowercase());
+    }
+    sections
+}
+
+/// `m=<media> <port> <proto> <fmt...>`의 fmt 순서를 재배열한다.
+fn reorder_media_line(
+    media: &str,
+    codecs: &HashMap<String, String>,
+    preference: &[String],
+) -> String {
+    let parts: Vec<&str> = media.split(' ').collect();
+    if parts.len() <= 4 {
+        return format!("m={media}");
+    }
+    let (header, formats) = parts.split_at(3);
+
+    let rank = |payload_type: &str| {
+        codecs
+            .get(payload_type)
+            .and_then(|name| {
+                preference
+                    .iter()
+                    .position(|preferred| preferred.eq_ignore_ascii_case(name))
+            })
+            .unwrap_or(preference.len())
+    };
+    let mut formats = formats.to_vec();
+    // sort_by_key는 안정 정렬이므로 같은 순위끼리는 원래 순서가 유지된다.
+    formats.sort_by_key(|payload_type| rank(payload_type));
+
+    format!("m={} {}", header.join(" "), formats.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OFFER: &str = "v=0\r\n\
+        o=- 1 2 IN IP4 127.0.0.1\r\n\
+        s=-\r\n\
+        m=audio 9 UDP/TLS/RTP/SAVPF 111 0 8\r\n\
+        a=rtpmap:111 opus/48000/2\r\n\
+        a=rtpmap:0 PCMU/8000\r\n\
+        a=rtpmap:8 PCMA/8000\r\n\
+        m=video 9 UDP/TLS/RTP/SAVPF 96 97 98 99\r\n\
+        a=rtpmap:96 H264/90000\r\n\
+        a=rtpmap:97 rtx/90000\r\n\
+        a=fmtp:97 apt=96\r\n\
+        a=rtpmap:98 VP8/90000\r\n\
+        a=rtpmap:99 rtx/90000\r\n\
+        a=fmtp:99 apt=98\r\n";
+
+    #[test]
+    fn preference_moves_payload_types_to_front() {
+        let preference = vec!["vp8".to_string(), "PCMA".to_string()];
+        let munged = apply_codec_preference(OFFER, &preference);
+
+        let media: Vec<&str> = munged.lines().filter(|l| l.starts_with("m=")).collect();
+        assert_eq!(
+            media,
+            vec![
+                "m=audio 9 UDP/TLS/RTP/SAVPF 8 111 0",
+                "m=video 9 UDP/TLS/RTP/SAVPF 98 96 97 99",
+            ]
+        );
+        assert!(munged.ends_with("a=fmtp:99 apt=98\r\n"));
+        assert_eq!(munged.lines().count(), OFFER.lines().count());
+    }
+
+    #[test]
+    fn empty_preference_is_a_no_op() {
+        assert!(matches!(
+            apply_codec_preference(OFFER, &[]),
+            Cow::Borrowed(sdp) if sdp == OFFER
+        ));
+    }
+
+    #[test]
+    fn unknown_codec_preference_leaves_sdp_untouched() {
+        let preference = vec!["AV1".to_string()];
+        assert!(matches!(
+            apply_codec_preference(OFFER, &preference),
+            Cow::Borrowed(_)
+        ));
+    }
+}
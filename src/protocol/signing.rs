@@ -0,0 +1,120 @@
+//! 서버 메시지 서명 (MESSAGE_SIGNING_SECRET)
+//!
+//! JSON Text frame 끝에 `"seq"`, `"ts"`, `"sig"` 필드를 붙인다. `seq`는 연결마다 1부터 1씩 늘어나는
+//! 번호, `ts`는 서명한 시각(unix ms)이다. `sig`는 `seq`, `ts`까지 붙인 원문
+//! (`{"type":..,"payload":..,"seq":..,"ts":..}`)의 HMAC-SHA256을 소문자 hex로 쓴 것이다.
+//! 클라이언트는 frame 끝의 `,"sig":"..."`를 떼고 `}`를 붙인 문자열로 검증하고, `seq`가 직전보다
+//! 크지 않거나 `ts`가 오래된 메시지는 재전송으로 보고 버린다. 바이너리 frame(BinaryRelay)은 서명하지 않는다.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt::Write;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 공유 비밀로 키를 정해 둔 서명기. 메시지마다 복제해서 쓴다.
+pub struct MessageSigner {
+    mac: HmacSha256,
+}
+
+impl MessageSigner {
+    /// 비밀이 비어 있으면 서명하지 않는다 (None).
+    pub fn new(secret: &str) -> Option<Self> {
+        if secret.is_empty() {
+            return None;
+        }
+        let mac = HmacSha256::new_from_slice(secret.as_bytes()).ok()?;
+        Some(Self { mac })
+    }
+
+    /// 직렬화한 메시지의 서명 (소문자 hex)
+    pub fn sign(&self, json: &str) -> String {
+        let mut mac = self.mac.clone();
+        mac.update(json.as_bytes());
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .fold(String::with_capacity(64), |mut hex, byte| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            })
+    }
+
+    /// 직렬화한 메시지 객체 끝에 연결의 `seq`, 현재 `ts`를 붙이고 그 전체에 대한 `"sig"` 필드를 붙인다.
+    pub fn attach(&self, json: String, seq: u64) -> String {
+        let ts = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default();
+        self.attach_at(json, seq, ts)
+    }
+
+    fn attach_at(&self, json: String, seq: u64, ts: u64) -> String {
+        let Some(body) = json.strip_suffix('}') else {
+            return json;
+        };
+        let stamped = format!(r#"{body},"seq":{seq},"ts":{ts}}}"#);
+        let sig = self.sign(&stamped);
+        let body = &stamped[..stamped.len() - 1];
+        format!(r#"{body},"sig":"{sig}"}}"#)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ServerMessage;
+
+    #[test]
+    fn signature_covers_the_frame_without_sig_field() {
+        let signer = MessageSigner::new("shared-secret").expect("signer");
+        let json = serde_json::to_string(&ServerMessage::HeartbeatAck).unwrap();
+
+        let signed = signer.attach_at(json.clone(), 7, 1_700_000_000_000);
+        let value: serde_json::Value = serde_json::from_str(&signed).unwrap();
+        assert_eq!(value["type"], "HeartbeatAck");
+        assert_eq!(value["seq"], 7);
+        assert_eq!(value["ts"], 1_700_000_000_000u64);
+        let sig = value["sig"].as_str().expect("sig field");
+
+        let (unsigned, _) = signed.rsplit_once(r#","sig":"#).unwrap();
+        let stamped = format!("{unsigned}}}");
+        assert_eq!(
+            stamped,
+            format!(
+                r#"{},"seq":7,"ts":1700000000000}}"#,
+                json.strip_suffix('}').unwrap()
+            )
+        );
+        let mut mac = HmacSha256::new_from_slice(b"shared-secret").unwrap();
+        mac.update(stamped.as_bytes());
+        let expected: String = mac
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        assert_eq!(sig, expected);
+    }
+
+    #[test]
+    fn same_message_signs_differently_per_sequence() {
+        let signer = MessageSigner::new("shared-secret").expect("signer");
+        let json = serde_json::to_string(&ServerMessage::HeartbeatAck).unwrap();
+
+        // 같은 내용이라도 seq가 다르면 서명이 달라 이전 frame을 그대로 다시 보낼 수 없다
+        let sig = |signed: String| {
+            let value: serde_json::Value = serde_json::from_str(&signed).unwrap();
+            value["sig"].as_str().unwrap().to_string()
+        };
+        let first = sig(signer.attach_at(json.clone(), 1, 1_000));
+        let second = sig(signer.attach_at(json.clone(), 2, 1_000));
+        assert_ne!(first, second);
+        assert_ne!(first, sig(signer.attach_at(json, 1, 2_000)));
+    }
+
+    #[test]
+    fn empty_secret_disables_signing() {
+        assert!(MessageSigner::new("").is_none());
+    }
+}
@@ -0,0 +1,66 @@
+//! 알 수 없는 필드 검사 (STRICT_CLIENT_FIELDS)
+//!
+//! 해석한 메시지를 다시 직렬화해 받은 JSON과 key를 비교한다. 최상위는 `type`/`payload`만,
+//! payload는 해석 결과에 남은 key만 허용한다 (값이 null인 key는 생략된 것으로 본다).
+//! `options`, `candidate` 같은 중첩 객체 안은 검사하지 않는다.
+
+use super::ClientMessage;
+use serde_json::Value;
+
+/// Text frame을 해석하고 알 수 없는 필드가 있으면 그 이름을 돌려준다.
+pub fn decode_strict(text: &str) -> Result<ClientMessage, Option<String>> {
+    let value: Value = serde_json::from_str(text).map_err(|_| None)?;
+    let message: ClientMessage = serde_json::from_value(value.clone()).map_err(|_| None)?;
+    match unknown_field(&value, &message) {
+        Some(field) => Err(Some(field)),
+        None => Ok(message),
+    }
+}
+
+fn unknown_field(value: &Value, message: &ClientMessage) -> Option<String> {
+    let object = value.as_object()?;
+    if let Some(key) = object
+        .keys()
+        .find(|key| !matches!(key.as_str(), "type" | "payload"))
+    {
+        return Some(key.clone());
+    }
+    let received = object.get("payload")?.as_object()?;
+    let decoded = serde_json::to_value(message).ok()?;
+    let known = decoded.get("payload").and_then(Value::as_object);
+    received
+        .iter()
+        .find(|(key, field)| {
+            !field.is_null() && known.is_none_or(|known| !known.contains_key(*key))
+        })
+        .map(|(key, _)| key.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typo_field_is_reported_and_known_fields_pass() {
+        let typo = r#"{"type":"JoinRoom","payload":{"roomId":"abc","room_id":"abc"}}"#;
+        assert_eq!(decode_strict(typo).unwrap_err(), Some("roomId".to_string()));
+
+        let extra_top = r#"{"type":"Heartbeat","id":1}"#;
+        assert_eq!(
+            decode_strict(extra_top).unwrap_err(),
+            Some("id".to_string())
+        );
+
+        let valid =
+            r#"{"type":"JoinRoom","payload":{"room_id":"abc","role":null,"correlation_id":"c1"}}"#;
+        assert!(matches!(
+            decode_strict(valid),
+            Ok(ClientMessage::JoinRoom { correlation_id: Some(id), .. }) if id == "c1"
+        ));
+        assert!(matches!(
+            decode_strict(r#"{"type":"Heartbeat"}"#),
+            Ok(ClientMessage::Heartbeat)
+        ));
+        assert_eq!(decode_strict("not json").unwrap_err(), None);
+    }
+}
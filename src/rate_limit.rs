@@ -0,0 +1,92 @@
+//! 연결 단위 메시지 속도 제한 (token bucket)
+
+use crate::config::{LimitsConfig, RoomConfig, SignalingConfig};
+use std::time::Instant;
+
+/// 초당 `rate`개씩 채워지고 최대 `burst`개까지 쌓이는 토큰 버킷
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate_per_sec: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate_per_sec: u32, burst: u32) -> Self {
+        let capacity = f64::from(burst.max(1));
+        Self {
+            rate_per_sec: f64::from(rate_per_sec),
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// MESSAGE_RATE_LIMIT가 0이면 제한하지 않는다.
+    pub fn from_config(limits: &LimitsConfig) -> Option<Self> {
+        (limits.message_rate_per_sec > 0)
+            .then(|| Self::new(limits.message_rate_per_sec, limits.message_burst))
+    }
+
+    /// PUBLIC_ROOMS_RATE가 0이면 제한하지 않는다.
+    pub fn for_room_list(room: &RoomConfig) -> Option<Self> {
+        (room.list_rate_per_sec > 0).then(|| Self::new(room.list_rate_per_sec, room.list_burst))
+    }
+
+    /// TRANSFER_PROGRESS_RATE가 0이면 제한하지 않는다. 몰아서 보내지 못하도록 버스트는 1이다.
+    pub fn for_transfer_progress(signaling: &SignalingConfig) -> Option<Self> {
+        let rate = signaling.transfer_progress_rate;
+        (rate > 0).then(|| Self::new(rate, 1))
+    }
+
+    /// 토큰 하나를 사용한다. 부족하면 다음 토큰까지 남은 시간(ms)을 반환한다.
+    pub fn try_acquire(&mut self) -> Result<(), u64> {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&mut self, now: Instant) -> Result<(), u64> {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        let wait_ms = ((1.0 - self.tokens) / self.rate_per_sec * 1000.0).ceil() as u64;
+        Err(wait_ms.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn burst_is_allowed_then_rejected_with_retry_hint() {
+        let mut bucket = TokenBucket::new(10, 3);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(bucket.try_acquire_at(now).is_ok());
+        }
+
+        let retry_after_ms = bucket.try_acquire_at(now).expect_err("bucket empty");
+        assert!(retry_after_ms > 0);
+        assert!(retry_after_ms <= 100);
+    }
+
+    #[test]
+    fn tokens_refill_over_time() {
+        let mut bucket = TokenBucket::new(10, 1);
+        let start = Instant::now();
+        assert!(bucket.try_acquire_at(start).is_ok());
+        assert!(bucket.try_acquire_at(start).is_err());
+        assert!(bucket
+            .try_acquire_at(start + Duration::from_millis(100))
+            .is_ok());
+    }
+}
@@ -1,19 +1,32 @@
 //! 애플리케이션 상태 관리
 
+use crate::admin::AdminEvent;
 use crate::billing::BillingClient;
-use crate::config::Config;
+use crate::cluster::ClusterBackend;
+use crate::config::{Config, RoomConfig};
 use crate::database::CloudDatabase;
+use crate::handlers::{credential_provider_from_config, TurnCredentialProvider};
+use crate::i18n::LocaleCell;
 use crate::mesh::MeshState;
-use crate::protocol::ServerMessage;
+use crate::metrics::Metrics;
+use crate::protocol::signing::MessageSigner;
+use crate::protocol::{NoticeLevel, PeerRole, RoomEvent, RoomOptions, ServerMessage};
+use crate::rate_limit::TokenBucket;
 use anyhow::{bail, Result};
 use aws_config::BehaviorVersion;
 use aws_sdk_s3::config::{Credentials, Region};
 use aws_sdk_s3::Client;
-use dashmap::DashMap;
-use std::collections::HashSet;
+use dashmap::{DashMap, DashSet};
+use std::collections::{HashSet, VecDeque};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::{mpsc::UnboundedSender, RwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, mpsc::UnboundedSender, Notify, RwLock, Semaphore};
+use uuid::Uuid;
+
+/// 느린 `/admin/events` 구독자를 위해 쌓아 두는 이벤트 수. 넘치면 구독자가 건너뛴다.
+const ADMIN_EVENT_BUFFER: usize = 256;
 
 /// 전역 애플리케이션 상태
 pub struct AppState {
@@ -21,6 +34,46 @@ pub struct AppState {
     pub rooms: DashMap<String, Room>,
     /// 피어 세션 (peer_id -> PeerSession)
     pub peers: DashMap<String, PeerSession>,
+    /// 재접속 안전 클라이언트 토큰 (client_token -> 현재 peer_id)
+    pub client_tokens: DashMap<String, String>,
+    /// 접속 IP별로 만든 방 수 (MAX_ROOMS_PER_OWNER). 방이 삭제되면 줄인다
+    pub owner_rooms: DashMap<IpAddr, usize>,
+    /// 응답(Answer)을 기다리는 target Offer ((from, to) -> 중계 시각). glare 감지용
+    pub pending_offers: DashMap<(String, String), Instant>,
+    /// target Offer의 재협상 세대 ((from, to) -> 마지막 generation)
+    pub offer_generations: DashMap<(String, String), u64>,
+    /// 연결이 끊겼지만 유예 시간 동안 방에 남아 있는 피어 (client_token -> 상태)
+    pub away_peers: DashMap<String, AwayPeer>,
+    /// 브로드캐스트 중 송신이 실패한 피어 (정리 대기열)
+    pub dead_peers: DashSet<String>,
+    /// dead_peers에 새 항목이 들어왔음을 정리 태스크에 알린다
+    pub dead_peers_notify: Notify,
+    /// 접속 시 보내는 MOTD (관리자 API로 런타임 변경 가능)
+    pub motd: RwLock<Option<Motd>>,
+    /// 진행 중인 청크 manifest ((from, target 또는 room_id) -> 조립 상태)
+    pub manifest_chunks: DashMap<(String, String), ManifestAssembly>,
+    /// Connected, /health, 로그 span에 찍는 인스턴스 id (실행 중에는 변하지 않음)
+    pub instance_id: String,
+    /// 운영 메트릭 (`{BASE_PATH}/metrics`)
+    pub metrics: Metrics,
+    /// `/admin/events` 구독자에게 보내는 실시간 이벤트
+    pub admin_events: broadcast::Sender<AdminEvent>,
+    /// 모든 방의 멤버 수 합계 (MAX_TOTAL_USERS). 입장/퇴장/방 삭제 시 갱신한다
+    pub room_users: AtomicUsize,
+    /// 방에 들어가 있지 않은 연결 수 (MAX_ROOMLESS_PEERS). 세션의 방이 바뀔 때 갱신한다
+    pub roomless_peers: AtomicUsize,
+    /// 확보된 연결 슬롯 수 (MAX_CONNECTIONS). 등록 전에 `check_capacity`가 올리고 연결이 끝나면 내린다
+    pub connection_slots: Arc<AtomicUsize>,
+    /// 시작 후 TURN 도달 확인이 성공했는지 (REQUIRE_TURN_READY)
+    pub turn_reachable: AtomicBool,
+    /// 메시지 처리 동시 실행 허가 (MAX_CONCURRENT_HANDLERS, 0이면 None)
+    pub handler_permits: Option<Semaphore>,
+    /// 진행 중인 WebSocket 업그레이드 허가 (MAX_CONCURRENT_UPGRADES, 0이면 None)
+    pub upgrade_permits: Option<Arc<Semaphore>>,
+    /// 서버 메시지 서명기 (MESSAGE_SIGNING_SECRET, 비어 있으면 None)
+    pub message_signer: Option<Arc<MessageSigner>>,
+    /// 중계 문자열 차단 정규식 (RELAY_CONTENT_BLOCKLIST, 비어 있으면 None)
+    pub content_blocklist: Option<regex_lite::Regex>,
     /// 설정
     pub config: Arc<Config>,
     /// Cloudflare R2 임시 파일 공유 저장소
@@ -33,6 +86,10 @@ pub struct AppState {
     pub http: reqwest::Client,
     /// In-memory Mesh MVP registry guarded by PONSWARP_MESH_ENABLED.
     pub mesh: Arc<MeshState>,
+    /// 다중 노드 중계 백엔드 (기본은 단일 노드)
+    pub cluster: Arc<dyn ClusterBackend>,
+    /// TURN 자격증명 발급 방식 (기본은 TURN_SECRET HMAC)
+    pub turn_credentials: Arc<dyn TurnCredentialProvider>,
 }
 
 impl AppState {
@@ -40,18 +97,181 @@ impl AppState {
         let cloud = CloudStorage::from_config(&config).await?.map(Arc::new);
         let cloud_db = CloudDatabase::from_config(&config).await?.map(Arc::new);
         let billing = BillingClient::from_config(&config)?.map(Arc::new);
+        let cluster = crate::cluster::backend_from_config(&config).await?;
+        let http = reqwest::Client::new();
+        let turn_credentials = credential_provider_from_config(&config.turn, &http);
         Ok(Self {
             rooms: DashMap::new(),
             peers: DashMap::new(),
+            client_tokens: DashMap::new(),
+            owner_rooms: DashMap::new(),
+            manifest_chunks: DashMap::new(),
+            away_peers: DashMap::new(),
+            pending_offers: DashMap::new(),
+            offer_generations: DashMap::new(),
+            dead_peers: DashSet::new(),
+            dead_peers_notify: Notify::new(),
+            motd: RwLock::new(Motd::from_config(&config)),
+            instance_id: instance_id_from_config(&config),
+            metrics: Metrics::default(),
+            admin_events: broadcast::channel(ADMIN_EVENT_BUFFER).0,
+            room_users: AtomicUsize::new(0),
+            roomless_peers: AtomicUsize::new(0),
+            connection_slots: Arc::default(),
+            turn_reachable: AtomicBool::new(false),
+            handler_permits: handler_permits_from_config(&config),
+            upgrade_permits: upgrade_permits_from_config(&config),
+            message_signer: MessageSigner::new(&config.signaling.message_signing_secret)
+                .map(Arc::new),
+            content_blocklist: content_blocklist_from_config(&config),
             config: Arc::new(config),
             cloud,
             cloud_db,
             billing,
-            http: reqwest::Client::new(),
+            http,
             mesh: Arc::new(MeshState::default()),
+            cluster,
+            turn_credentials,
         })
     }
 
+    /// 방 멤버 한 자리를 예약한다. MAX_TOTAL_USERS에 이미 도달했으면 false.
+    pub fn reserve_room_user(&self) -> bool {
+        let cap = self.config.limits.max_total_users;
+        self.room_users
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (cap == 0 || count < cap).then_some(count + 1)
+            })
+            .is_ok()
+    }
+
+    /// 방에서 빠진 멤버 수만큼 합계를 줄인다.
+    pub fn release_room_users(&self, count: usize) {
+        let _ = self
+            .room_users
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                Some(current.saturating_sub(count))
+            });
+    }
+
+    /// `/admin/events` 구독자에게 이벤트를 보낸다. 구독자가 없으면 버린다.
+    pub fn emit_admin_event(&self, event: AdminEvent) {
+        let _ = self.admin_events.send(event);
+    }
+
+    /// 방 없는 연결 수를 세션 상태 변화에 맞춰 고친다. `counted`는 바뀌기 전에 방 없는 연결로
+    /// 세고 있었는지, `roomless`는 바뀐 뒤 방이 없는지다.
+    pub fn track_roomless(&self, counted: bool, roomless: bool) {
+        match (counted, roomless) {
+            (false, true) => {
+                self.roomless_peers.fetch_add(1, Ordering::AcqRel);
+            }
+            (true, false) => {
+                let _ = self.roomless_peers.fetch_update(
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                    |count| Some(count.saturating_sub(1)),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// 접속 후 `grace`가 지나도록 방에 들어가지 않은 피어 (ROOMLESS_GRACE_MS)
+    pub fn roomless_past(&self, grace: std::time::Duration) -> Vec<String> {
+        self.peers
+            .iter()
+            .filter(|session| session.connected_at.elapsed() >= grace)
+            .filter(|session| {
+                session
+                    .room_id
+                    .try_read()
+                    .is_ok_and(|room_id| room_id.is_none())
+            })
+            .map(|session| session.key().clone())
+            .collect()
+    }
+
+    /// `ip`가 만든 방 수를 하나 늘린다. MAX_ROOMS_PER_OWNER에 닿았으면 늘리지 않고 false.
+    /// 방 map의 entry lock을 잡은 채 호출해도 되도록 별도 map에서 센다.
+    pub fn claim_owner_room(&self, ip: Option<IpAddr>) -> bool {
+        let limit = self.config.room.max_rooms_per_owner;
+        let Some(ip) = ip.filter(|_| limit > 0) else {
+            return true;
+        };
+        let mut owned = self.owner_rooms.entry(ip).or_insert(0);
+        if *owned >= limit {
+            return false;
+        }
+        *owned += 1;
+        true
+    }
+
+    /// 삭제한 방이 owner IP의 방 수에 세어져 있었으면 돌려준다.
+    pub fn release_owner_room(&self, room: &Room) {
+        let Some(ip) = room.owner_ip else {
+            return;
+        };
+        if let dashmap::mapref::entry::Entry::Occupied(mut owned) = self.owner_rooms.entry(ip) {
+            if *owned.get() <= 1 {
+                owned.remove();
+            } else {
+                *owned.get_mut() -= 1;
+            }
+        }
+    }
+
+    /// 방의 마지막 활동 시각을 갱신한다.
+    pub fn touch_room(&self, room_id: &str) {
+        if let Some(room) = self.rooms.get(room_id) {
+            room.touch();
+        }
+    }
+
+    /// 정리 스케줄러에서 호출한다. 주요 map 크기를 로그로 남기고, MAP_SHRINK이면
+    /// 트래픽이 빠져 남는 용량이 큰 map을 줄인다. 줄인 map 수를 돌려준다.
+    pub fn maintain_maps(&self) -> usize {
+        tracing::info!(
+            peers = self.peers.len(),
+            rooms = self.rooms.len(),
+            client_tokens = self.client_tokens.len(),
+            away_peers = self.away_peers.len(),
+            pending_offers = self.pending_offers.len(),
+            offer_generations = self.offer_generations.len(),
+            manifest_chunks = self.manifest_chunks.len(),
+            "State map sizes"
+        );
+        if !self.config.limits.map_shrink {
+            return 0;
+        }
+        let shrunk = [
+            shrink_if_sparse(&self.peers),
+            shrink_if_sparse(&self.rooms),
+            shrink_if_sparse(&self.client_tokens),
+            shrink_if_sparse(&self.away_peers),
+            shrink_if_sparse(&self.pending_offers),
+            shrink_if_sparse(&self.offer_generations),
+            shrink_if_sparse(&self.manifest_chunks),
+        ]
+        .into_iter()
+        .filter(|shrunk| *shrunk)
+        .count();
+        if shrunk > 0 {
+            tracing::info!(shrunk_maps = shrunk, "Shrunk sparse state maps");
+        }
+        shrunk
+    }
+
+    /// 중계 target을 현재 peer_id로 해석한다. peer_id 또는 client_token을 받는다.
+    pub fn resolve_peer_id(&self, target: &str) -> Option<String> {
+        if self.peers.contains_key(target) {
+            return Some(target.to_string());
+        }
+        self.client_tokens
+            .get(target)
+            .map(|peer_id| peer_id.value().clone())
+    }
+
     pub fn cloud_storage(
         &self,
     ) -> Result<&CloudStorage, crate::handlers::cloud_share::CloudShareError> {
@@ -63,20 +283,172 @@ impl AppState {
     }
 }
 
+/// RELAY_CONTENT_BLOCKLIST가 비어 있으면 검사하지 않는다.
+/// 잘못된 정규식은 Config::validate가 시작 전에 거부한다
+fn content_blocklist_from_config(config: &Config) -> Option<regex_lite::Regex> {
+    let pattern = &config.signaling.relay_content_blocklist;
+    if pattern.is_empty() {
+        return None;
+    }
+    regex_lite::Regex::new(pattern).ok()
+}
+
+/// MAX_CONCURRENT_HANDLERS가 0이면 제한하지 않는다.
+fn handler_permits_from_config(config: &Config) -> Option<Semaphore> {
+    let limit = config.limits.max_concurrent_handlers;
+    (limit > 0).then(|| Semaphore::new(limit))
+}
+
+/// MAX_CONCURRENT_UPGRADES가 0이면 제한하지 않는다.
+fn upgrade_permits_from_config(config: &Config) -> Option<Arc<Semaphore>> {
+    let limit = config.limits.max_concurrent_upgrades;
+    (limit > 0).then(|| Arc::new(Semaphore::new(limit)))
+}
+
+/// SERVER_INSTANCE_ID가 없으면 짧은 무작위 id를 만든다.
+fn instance_id_from_config(config: &Config) -> String {
+    if config.instance_id.is_empty() {
+        Uuid::new_v4().simple().to_string()[..12].to_string()
+    } else {
+        config.instance_id.clone()
+    }
+}
+
 #[cfg(test)]
 impl AppState {
     pub fn new_for_test_with_mesh(mesh_enabled: bool) -> Self {
         let mut config = Config::from_env();
         config.mesh.enabled = mesh_enabled;
+        Self::new_for_test_with_config(config)
+    }
+
+    pub fn new_for_test() -> Self {
+        Self::new_for_test_with_config(Config::from_env())
+    }
+
+    pub fn new_for_test_with_config(config: Config) -> Self {
+        let http = reqwest::Client::new();
+        let turn_credentials = credential_provider_from_config(&config.turn, &http);
         Self {
             rooms: DashMap::new(),
             peers: DashMap::new(),
+            client_tokens: DashMap::new(),
+            owner_rooms: DashMap::new(),
+            manifest_chunks: DashMap::new(),
+            away_peers: DashMap::new(),
+            pending_offers: DashMap::new(),
+            offer_generations: DashMap::new(),
+            dead_peers: DashSet::new(),
+            dead_peers_notify: Notify::new(),
+            motd: RwLock::new(Motd::from_config(&config)),
+            instance_id: instance_id_from_config(&config),
+            metrics: Metrics::default(),
+            admin_events: broadcast::channel(ADMIN_EVENT_BUFFER).0,
+            room_users: AtomicUsize::new(0),
+            roomless_peers: AtomicUsize::new(0),
+            connection_slots: Arc::default(),
+            turn_reachable: AtomicBool::new(false),
+            handler_permits: handler_permits_from_config(&config),
+            upgrade_permits: upgrade_permits_from_config(&config),
+            message_signer: MessageSigner::new(&config.signaling.message_signing_secret)
+                .map(Arc::new),
+            content_blocklist: content_blocklist_from_config(&config),
             config: Arc::new(config),
             cloud: None,
             cloud_db: None,
             billing: None,
-            http: reqwest::Client::new(),
+            http,
             mesh: Arc::new(MeshState::default()),
+            cluster: Arc::new(crate::cluster::LocalBackend::new()),
+            turn_credentials,
+        }
+    }
+
+    pub fn new_for_test_with_cluster(cluster: Arc<dyn ClusterBackend>) -> Self {
+        Self {
+            cluster,
+            ..Self::new_for_test()
+        }
+    }
+
+    /// 채널을 가진 테스트용 피어 세션을 등록하고 수신 측을 반환한다.
+    pub fn insert_test_peer(
+        &self,
+        peer_id: &str,
+    ) -> tokio::sync::mpsc::UnboundedReceiver<ServerMessage> {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        self.track_roomless(false, true);
+        self.connection_slots.fetch_add(1, Ordering::AcqRel);
+        self.peers.insert(
+            peer_id.to_string(),
+            PeerSession {
+                id: peer_id.to_string(),
+                client_token: format!("token-{peer_id}"),
+                resume_token: format!("resume-{peer_id}"),
+                remote_ip: None,
+                room_id: RwLock::new(None),
+                role: RwLock::new(PeerRole::default()),
+                sender,
+                connected_at: Instant::now(),
+                stats: Arc::new(PeerStats::default()),
+                room_list_limiter: TokenBucket::for_room_list(&self.config.room)
+                    .map(std::sync::Mutex::new),
+                progress_limiter: TokenBucket::for_transfer_progress(&self.config.signaling)
+                    .map(std::sync::Mutex::new),
+                close_signal: Arc::new(Notify::new()),
+                subscriptions: Arc::default(),
+                capabilities: Capabilities::default(),
+                turn_expires_at_ms: AtomicU64::new(0),
+                locale: Arc::default(),
+                last_room_leave: std::sync::Mutex::new(None),
+                completed_transfers: std::sync::Mutex::default(),
+            },
+        );
+        receiver
+    }
+}
+
+/// 이보다 작은 map은 줄이지 않는다 (shard마다 최소 용량이 있어 매번 줄였다 늘리게 된다)
+const SHRINK_MIN_CAPACITY: usize = 1024;
+
+/// 용량이 항목 수의 4배 이상 남으면 줄인다. shard write lock을 차례로 잠깐씩 잡는다.
+fn shrink_if_sparse<K: Eq + std::hash::Hash, V>(map: &DashMap<K, V>) -> bool {
+    let capacity = map.capacity();
+    if capacity < SHRINK_MIN_CAPACITY || capacity < map.len().saturating_mul(4) {
+        return false;
+    }
+    map.shrink_to_fit();
+    true
+}
+
+/// 테스트 수신 채널에 쌓인 메시지를 모두 꺼낸다.
+#[cfg(test)]
+pub fn drain_messages(
+    receiver: &mut tokio::sync::mpsc::UnboundedReceiver<ServerMessage>,
+) -> Vec<ServerMessage> {
+    let mut messages = Vec::new();
+    while let Ok(message) = receiver.try_recv() {
+        messages.push(message);
+    }
+    messages
+}
+
+/// 접속 직후 전달하는 안내 문구
+#[derive(Debug, Clone)]
+pub struct Motd {
+    pub text: String,
+    pub level: NoticeLevel,
+}
+
+impl Motd {
+    fn from_config(config: &Config) -> Option<Self> {
+        if config.motd.is_empty() {
+            None
+        } else {
+            Some(Self {
+                text: config.motd.clone(),
+                level: config.motd_level,
+            })
         }
     }
 }
@@ -142,10 +514,32 @@ impl CloudStorage {
 
 /// 방 정보
 pub struct Room {
-    #[allow(dead_code)]
     pub id: String,
     pub users: RwLock<HashSet<String>>,
     pub created_at: Instant,
+    /// 마지막 시그널링 중계/멤버 Heartbeat 시각 (ROOM_TIMEOUT 정리 기준)
+    pub last_activity: std::sync::Mutex<Instant>,
+    /// 최근 이벤트 링 버퍼 (ROOM_HISTORY_SIZE)
+    pub history: RwLock<VecDeque<RoomEvent>>,
+    /// 방 단위 브로드캐스트 중계 한도 (ROOM_MSG_RATE)
+    pub relay_limiter: Option<std::sync::Mutex<TokenBucket>>,
+    /// 방을 만든 피어. 복원된 방은 저장 당시 owner (재시작 전 peer_id)
+    pub owner: Option<String>,
+    /// 방을 만든 클라이언트의 client_token (재접속해도 같다)
+    pub owner_token: Option<String>,
+    /// 방을 만든 연결의 IP. MAX_ROOMS_PER_OWNER가 켜져 있을 때만 기록한다
+    pub owner_ip: Option<IpAddr>,
+    pub options: RoomOptions,
+    /// owner가 SetRoomMetadata로 설정한 앱 정의 값
+    pub metadata: RwLock<Option<serde_json::Value>>,
+    /// owner가 SetRoomName으로 정한 표시 이름
+    pub name: RwLock<Option<String>>,
+    /// CoordinateIceRestart로 올라가는 ICE restart epoch (0이면 아직 없음)
+    pub ice_restart_epoch: AtomicU64,
+    /// 관리자 drain 중이면 새 입장을 받지 않는다
+    pub draining: AtomicBool,
+    /// 관리자가 예약한 방의 보존 기한. 이때까지는 비어 있어도 삭제하지 않는다. 입장으로 생긴 방은 None
+    pub reserved_until: Option<Instant>,
 }
 
 impl Room {
@@ -154,16 +548,291 @@ impl Room {
             id,
             users: RwLock::new(HashSet::new()),
             created_at: Instant::now(),
+            last_activity: std::sync::Mutex::new(Instant::now()),
+            history: RwLock::new(VecDeque::new()),
+            relay_limiter: None,
+            owner: None,
+            owner_token: None,
+            owner_ip: None,
+            options: RoomOptions::default(),
+            metadata: RwLock::new(None),
+            name: RwLock::new(None),
+            ice_restart_epoch: AtomicU64::new(0),
+            draining: AtomicBool::new(false),
+            reserved_until: None,
+        }
+    }
+
+    /// 방 설정(중계 한도 등)을 적용해 생성한다.
+    pub fn with_config(id: String, config: &RoomConfig) -> Self {
+        let relay_limiter = (config.msg_rate_per_sec > 0).then(|| {
+            std::sync::Mutex::new(TokenBucket::new(config.msg_rate_per_sec, config.msg_burst))
+        });
+        Self {
+            relay_limiter,
+            ..Self::new(id)
+        }
+    }
+
+    /// Offer를 보낼 수 있는 피어인지 확인한다.
+    pub fn may_offer(&self, peer_id: &str) -> bool {
+        !self.options.host_only_offers || self.owner.as_deref() == Some(peer_id)
+    }
+
+    /// 브로드캐스트 중계 한 건을 허용할지 확인한다. 한도가 없으면 항상 허용.
+    pub fn try_acquire_relay(&self) -> Result<(), u64> {
+        match &self.relay_limiter {
+            Some(limiter) => limiter
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .try_acquire(),
+            None => Ok(()),
+        }
+    }
+
+    /// 예약 기한이 남아 있는 방인지
+    pub fn is_reserved(&self) -> bool {
+        self.reserved_until
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// 예약 기한 동안 비어 있는 방은 정리하지 않는다. 누가 들어와 있으면 일반 방과 같다
+    pub fn held_empty(&self) -> bool {
+        self.is_reserved() && self.users.try_read().is_ok_and(|users| users.is_empty())
+    }
+
+    pub fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    /// 마지막 활동 이후 경과 시간
+    pub fn idle_for(&self) -> std::time::Duration {
+        self.last_activity.lock().unwrap().elapsed()
+    }
+
+    /// 이벤트를 기록하고 `capacity`를 넘는 오래된 항목은 버린다.
+    pub async fn record_event(&self, event: RoomEvent, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        let mut history = self.history.write().await;
+        history.push_back(event);
+        while history.len() > capacity {
+            history.pop_front();
+        }
+    }
+}
+
+/// 재접속 유예 중인 피어. 같은 client_token으로 돌아오면 peer_id를 이어받는다.
+pub struct AwayPeer {
+    pub peer_id: String,
+    /// 이어받을 때 다시 연결할 클라이언트 토큰
+    pub client_token: String,
+    pub room_id: String,
+    pub role: PeerRole,
+    pub since: Instant,
+}
+
+/// 청크 단위로 도착 중인 manifest
+pub struct ManifestAssembly {
+    pub room_id: String,
+    pub total_chunks: u32,
+    pub chunks: Vec<Option<String>>,
+    /// 다음에 중계할 청크 번호 (순서 보장용)
+    pub next_index: u32,
+    pub received_bytes: usize,
+    pub started_at: Instant,
+}
+
+impl ManifestAssembly {
+    pub fn new(room_id: String, total_chunks: u32) -> Self {
+        Self {
+            room_id,
+            total_chunks,
+            chunks: vec![None; total_chunks as usize],
+            next_index: 0,
+            received_bytes: 0,
+            started_at: Instant::now(),
         }
     }
 }
 
 /// 피어 세션 정보
 pub struct PeerSession {
-    #[allow(dead_code)]
     pub id: String,
+    /// 재접속 시에도 유지되는 클라이언트 토큰
+    pub client_token: String,
+    /// 재접속 유예 중 세션을 이어받을 때 필요한 서버 발급 비밀값 (연결마다 새로 발급)
+    pub resume_token: String,
+    /// 접속한 클라이언트 IP (ConnectInfo 기준)
+    pub remote_ip: Option<IpAddr>,
     pub room_id: RwLock<Option<String>>,
+    /// 현재 방에서의 역할 (입장 시 설정)
+    pub role: RwLock<PeerRole>,
     pub sender: UnboundedSender<ServerMessage>,
-    #[allow(dead_code)]
     pub connected_at: Instant,
+    /// 송수신 카운터 (송신 태스크와 공유)
+    pub stats: Arc<PeerStats>,
+    /// ListPublicRooms 조회 한도 (PUBLIC_ROOMS_RATE)
+    pub room_list_limiter: Option<std::sync::Mutex<TokenBucket>>,
+    /// TransferProgress 중계 한도 (TRANSFER_PROGRESS_RATE)
+    pub progress_limiter: Option<std::sync::Mutex<TokenBucket>>,
+    /// 서버 측 강제 종료 신호 (관리자 close)
+    pub close_signal: Arc<Notify>,
+    /// Subscribe로 고른 수신 메시지 종류 (송신 태스크와 공유)
+    pub subscriptions: Arc<MessageFilter>,
+    /// Hello로 켠 프로토콜 확장
+    pub capabilities: Capabilities,
+    /// 마지막으로 발급한 TURN 자격증명의 만료 시각 (unix ms, 0이면 없거나 이미 안내함)
+    pub turn_expires_at_ms: AtomicU64,
+    /// 에러 문구 언어 (`?lang=`, SetLocale). 송신 태스크와 공유
+    pub locale: Arc<LocaleCell>,
+    /// 마지막으로 나간 방과 그 시각 (ROOM_TRANSITION_DEBOUNCE_MS)
+    pub last_room_leave: std::sync::Mutex<Option<(String, Instant)>>,
+    /// 완료(100%) TransferProgress를 이미 한도 없이 보낸 (방, target)
+    pub completed_transfers: std::sync::Mutex<HashSet<(String, Option<String>)>>,
+}
+
+/// 입장 시 RoomUsers + JoinedRoom 대신 RoomSnapshot 하나를 받는다
+pub const CAP_ROOM_SNAPSHOT: &str = "room_snapshot";
+
+/// 서버가 지원하는 프로토콜 확장
+const SUPPORTED_CAPABILITIES: &[&str] = &[CAP_ROOM_SNAPSHOT];
+
+/// SetCapabilities로 알릴 수 있는 기능 이름 수와 길이
+const MAX_ADVERTISED_CAPABILITIES: usize = 32;
+const MAX_CAPABILITY_CHARS: usize = 64;
+
+/// 피어가 켠 프로토콜 확장(Hello)과 다른 피어에게 알린 기능(SetCapabilities).
+/// Hello 전에는 확장이 모두 꺼져 있다 (기존 클라이언트 동작).
+#[derive(Debug, Default)]
+pub struct Capabilities {
+    enabled: std::sync::RwLock<HashSet<String>>,
+    advertised: std::sync::RwLock<HashSet<String>>,
+}
+
+impl Capabilities {
+    /// 요청 중 지원하는 확장만 켜고(이전 설정은 대체) 켠 목록을 돌려준다.
+    pub fn negotiate(&self, requested: Vec<String>) -> Vec<String> {
+        let enabled: HashSet<String> = requested
+            .into_iter()
+            .filter(|name| SUPPORTED_CAPABILITIES.contains(&name.as_str()))
+            .collect();
+        let mut accepted: Vec<String> = enabled.iter().cloned().collect();
+        accepted.sort();
+        *self.enabled.write().unwrap() = enabled;
+        accepted
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.enabled.read().unwrap().contains(name)
+    }
+
+    /// RelayToCapable 대상이 될 기능 목록을 바꾼다 (이전 목록은 대체).
+    /// 빈 이름과 너무 긴 이름은 버리고 앞에서부터 최대 32개만 남긴다.
+    pub fn advertise(&self, names: Vec<String>) {
+        let advertised = names
+            .into_iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty() && name.chars().count() <= MAX_CAPABILITY_CHARS)
+            .take(MAX_ADVERTISED_CAPABILITIES)
+            .collect();
+        *self.advertised.write().unwrap() = advertised;
+    }
+
+    pub fn advertises(&self, name: &str) -> bool {
+        self.advertised.read().unwrap().contains(name)
+    }
+}
+
+/// 피어에게 전달할 ServerMessage 종류. 설정 전에는 모두 전달한다.
+#[derive(Debug, Default)]
+pub struct MessageFilter {
+    types: std::sync::RwLock<Option<HashSet<String>>>,
+}
+
+impl MessageFilter {
+    /// 전달할 종류를 바꾼다. 빈 목록이면 필터를 해제한다.
+    pub fn set(&self, types: Vec<String>) {
+        let types = (!types.is_empty()).then(|| types.into_iter().collect());
+        *self.types.write().unwrap() = types;
+    }
+
+    /// 이 메시지를 보내야 하는지. Error는 구독과 관계없이 항상 전달한다.
+    pub fn allows(&self, message: &ServerMessage) -> bool {
+        let types = self.types.read().unwrap();
+        let Some(types) = types.as_ref() else {
+            return true;
+        };
+        if matches!(message, ServerMessage::Error { .. }) {
+            return true;
+        }
+        types.contains(message.kind())
+    }
+}
+
+/// 연결 단위 송수신 통계
+#[derive(Debug, Default)]
+pub struct PeerStats {
+    pub messages_received: AtomicU64,
+    pub messages_sent: AtomicU64,
+    pub bytes_received: AtomicU64,
+    pub bytes_sent: AtomicU64,
+    /// 마지막 Heartbeat 수신 시각 (unix ms, 0이면 수신 전)
+    pub last_heartbeat_ms: AtomicU64,
+}
+
+impl PeerStats {
+    pub fn record_received(&self, bytes: usize) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_sent(&self, bytes: usize) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_heartbeat(&self) {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.last_heartbeat_ms.store(now_ms, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn map_maintenance_shrinks_without_disrupting_sessions() {
+        let mut config = Config::from_env();
+        config.limits.map_shrink = true;
+        let state = AppState::new_for_test_with_config(config);
+        let mut active = state.insert_test_peer("active");
+
+        // 트래픽 급증 뒤 빠져나간 상황
+        for n in 0..4096 {
+            state
+                .client_tokens
+                .insert(format!("token-{n}"), format!("peer-{n}"));
+        }
+        state.client_tokens.retain(|_, peer_id| peer_id == "peer-0");
+        let before = state.client_tokens.capacity();
+
+        assert!(state.maintain_maps() >= 1);
+        assert!(state.client_tokens.capacity() < before);
+        assert_eq!(state.resolve_peer_id("token-0").as_deref(), Some("peer-0"));
+
+        let session = state.peers.get("active").expect("session kept");
+        session.sender.send(ServerMessage::HeartbeatAck).unwrap();
+        drop(session);
+        assert!(matches!(
+            drain_messages(&mut active).as_slice(),
+            [ServerMessage::HeartbeatAck]
+        ));
+    }
 }
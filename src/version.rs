@@ -0,0 +1,29 @@
+//! 서버 빌드/버전 정보
+
+use serde::Serialize;
+
+/// 크레이트 버전 (Cargo.toml)
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// 빌드 시점의 git short SHA. git 정보가 없으면 "unknown"
+pub const GIT_SHA: &str = env!("PONSWARP_GIT_SHA");
+/// 빌드 시각 (unix seconds)
+pub const BUILD_TIMESTAMP: &str = env!("PONSWARP_BUILD_TIMESTAMP");
+
+/// `/version` 응답
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    pub build_timestamp: u64,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        Self {
+            version: VERSION,
+            git_sha: GIT_SHA,
+            build_timestamp: BUILD_TIMESTAMP.parse().unwrap_or(0),
+        }
+    }
+}